@@ -1,18 +1,21 @@
+use std::collections::HashSet;
+
 use input_tree::{
     direction::VerticalDirection,
     editing::{editable::Editable, BasicEdit},
     focus::{MinimalInputRowPosition, MinimalInputRowRange},
+    node::InputNode,
     row::Offset,
 };
 use parser::autocomplete::{AutocompleteRule, AutocompleteRuleMatch};
 
+use crate::caret::MinimalCaret;
+
 #[derive(Default)]
 pub struct AutocorrectActionBuilder {
     autocorrect: Option<AutocorrectAction>,
 }
 
-
-
 impl AutocorrectActionBuilder {
     pub fn finish(self, edits: &[BasicEdit]) -> Option<AutocorrectAction> {
         self.autocorrect.map(|mut v| {
@@ -29,6 +32,68 @@ pub struct AutocorrectAction {
     pub caret_range: MinimalInputRowRange,
 }
 
+/// Per editor settings for the autocorrect
+#[derive(Default)]
+pub struct AutocorrectSettings {
+    /// Keywords of rules that are never applied automatically
+    disabled_rules: HashSet<String>,
+}
+
+impl AutocorrectSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_enabled(&mut self, keyword: &str, enabled: bool) {
+        if enabled {
+            self.disabled_rules.remove(keyword);
+        } else {
+            self.disabled_rules.insert(keyword.to_string());
+        }
+    }
+
+    pub fn is_enabled(&self, rule: &AutocompleteRule) -> bool {
+        !self.disabled_rules.contains(&rule.parser)
+    }
+}
+
+/// A selection that was replaced by typing.
+/// The typed text could be a keyword like sqrt, which then gets wrapped around the selection.
+pub struct ReplacedSelection {
+    pub values: Vec<InputNode>,
+    /// Where the keyword is being typed
+    pub keyword_range: MinimalInputRowRange,
+}
+
+impl ReplacedSelection {
+    /// The selection is only kept while the caret is in the keyword
+    pub fn contains(&self, caret: &MinimalCaret) -> bool {
+        let (start, end) = ordered(&self.keyword_range);
+        [&caret.start_position, &caret.end_position]
+            .iter()
+            .all(|position| {
+                position.row_indices == self.keyword_range.row_indices
+                    && start <= position.offset
+                    && position.offset <= end
+            })
+    }
+
+    pub fn is_keyword(&self, range: &MinimalInputRowRange) -> bool {
+        range.row_indices == self.keyword_range.row_indices
+            && ordered(range) == ordered(&self.keyword_range)
+    }
+}
+
+impl Editable for ReplacedSelection {
+    fn apply_edit(&mut self, edit: &BasicEdit) {
+        self.keyword_range.apply_edit(edit);
+    }
+}
+
+fn ordered(range: &MinimalInputRowRange) -> (Offset, Offset) {
+    (range.start.min(range.end), range.start.max(range.end))
+}
+
 pub struct AutocompleteResults<'a> {
     selected_index: usize,
     matches: Vec<AutocompleteRuleMatch<'a>>,
@@ -50,10 +115,14 @@ impl<'a> AutocompleteResults<'a> {
     }
 
     pub fn start_autocorrect(&self) -> AutocorrectActionBuilder {
-        let selected = self.get_selected();
-        if !selected.is_complete_match() {
-            return Default::default();
-        }
+        // The selected rule wins, but any perfect match will do. Like "prod" while "product" is selected.
+        let selected = Some(self.get_selected())
+            .filter(|v| v.is_complete_match())
+            .or_else(|| self.matches.iter().find(|v| v.is_complete_match()));
+        let selected = match selected {
+            Some(selected) => selected,
+            None => return Default::default(),
+        };
         let caret_range = self.get_caret_range(selected);
         AutocorrectActionBuilder {
             autocorrect: Some(AutocorrectAction {
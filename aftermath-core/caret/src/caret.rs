@@ -1,11 +1,12 @@
 use input_tree::{
+    editing::{editable::Editable, BasicEdit},
     focus::{
         InputGridRange, InputRowPosition, InputRowRange, MinimalInputGridRange,
         MinimalInputRowPosition, MinimalInputRowRange,
     },
     grid::{GridRectangle, Index2D},
     input_tree::InputTree,
-    row::Offset,
+    row::{Offset, RowIndex},
 };
 use serde::{Deserialize, Serialize};
 
@@ -28,6 +29,103 @@ impl Default for MinimalCaret {
     }
 }
 
+impl MinimalCaret {
+    pub fn collapsed(position: MinimalInputRowPosition) -> Self {
+        Self {
+            start_position: position.clone(),
+            end_position: position,
+        }
+    }
+
+    pub fn from_range(range: &MinimalInputRowRange) -> Self {
+        Self {
+            start_position: MinimalInputRowPosition {
+                row_indices: range.row_indices.clone(),
+                offset: range.start,
+            },
+            end_position: MinimalInputRowPosition {
+                row_indices: range.row_indices.clone(),
+                offset: range.end,
+            },
+        }
+    }
+}
+
+impl Editable for MinimalCaret {
+    fn apply_edit(&mut self, edit: &BasicEdit) {
+        self.start_position.apply_edit(edit);
+        self.end_position.apply_edit(edit);
+    }
+}
+
+/// Merges carets that overlap, like two carets that ended up at the same place after deleting.
+/// The first caret is the main caret, and it is the one that remains.
+pub fn merge_carets(tree: &InputTree, carets: Vec<MinimalCaret>) -> Vec<MinimalCaret> {
+    if carets.len() <= 1 {
+        return carets;
+    }
+    let mut merged: Vec<(MinimalCaret, Option<MinimalInputRowRange>)> = vec![];
+    for caret in carets {
+        let range = match Caret::from_minimal(tree, &caret).into_selection() {
+            CaretSelection::Row(range) => Some(range.to_minimal()),
+            CaretSelection::Grid(_) => None,
+        };
+        let overlapping = range.as_ref().and_then(|range| {
+            merged.iter_mut().find_map(|(other_caret, other_range)| {
+                let union = union_of_ranges(other_range.as_ref()?, range)?;
+                Some((other_caret, other_range, union))
+            })
+        });
+        match overlapping {
+            Some((other_caret, other_range, union)) => {
+                if other_range.as_ref() != Some(&union) {
+                    *other_caret = MinimalCaret::from_range(&union);
+                    *other_range = Some(union);
+                }
+            }
+            None => merged.push((caret, range)),
+        }
+    }
+    merged.into_iter().map(|(caret, _)| caret).collect()
+}
+
+/// Whether the ranges overlap, and the range that covers both.
+/// Ranges that only touch stay separate, like two selected "a+" in "a+a+". Collapsed carets merge when they are at the same place.
+/// Keeps the direction of the first range.
+fn union_of_ranges(
+    a: &MinimalInputRowRange,
+    b: &MinimalInputRowRange,
+) -> Option<MinimalInputRowRange> {
+    let (a_start, a_end) = (a.start.min(a.end), a.start.max(a.end));
+    let (b_start, b_end) = (b.start.min(b.end), b.start.max(b.end));
+    if a.row_indices == b.row_indices {
+        let is_same_position = a_start == a_end && b_start == b_end && a_start == b_start;
+        let is_overlapping = a_start < b_end && b_start < a_end;
+        if !is_same_position && !is_overlapping {
+            return None;
+        }
+        let (start, end) = (a_start.min(b_start), a_end.max(b_end));
+        return Some(MinimalInputRowRange {
+            row_indices: a.row_indices.clone(),
+            start: if a.start <= a.end { start } else { end },
+            end: if a.start <= a.end { end } else { start },
+        });
+    }
+    // A range inside of a container that is selected by the other range
+    let contains = |outer: &MinimalInputRowRange, inner: &MinimalInputRowRange| {
+        let (start, end) = (outer.start.min(outer.end), outer.start.max(outer.end));
+        inner.row_indices.starts_with(&outer.row_indices)
+            && matches!(inner.row_indices.at(outer.row_indices.len()), Some(RowIndex(index, _)) if start.0 <= index && index < end.0)
+    };
+    if contains(a, b) {
+        Some(a.clone())
+    } else if contains(b, a) {
+        Some(b.clone())
+    } else {
+        None
+    }
+}
+
 pub struct Caret<'a> {
     tree: &'a InputTree,
     start_position: InputRowPosition<'a>,
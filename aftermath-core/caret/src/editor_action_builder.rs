@@ -2,7 +2,11 @@ use std::ops::Deref;
 
 use input_tree::editing::{editable::Editable, BasicEdit};
 
-use crate::{caret::MinimalCaret, math_editor::MathEditor, primitive::CaretEdit};
+use crate::{
+    caret::{merge_carets, MinimalCaret},
+    math_editor::MathEditor,
+    primitive::CaretEdit,
+};
 
 /// Almost every function in the math editor delays the actual editing until the end of the function.
 /// So this struct is used to build up the edits and then convert it into a CaretEdit at the end.
@@ -11,44 +15,94 @@ use crate::{caret::MinimalCaret, math_editor::MathEditor, primitive::CaretEdit};
 pub struct EditorActionBuilder<'editor> {
     editor: &'editor mut MathEditor,
     caret_before: MinimalCaret,
+    secondary_carets_before: Vec<MinimalCaret>,
+    /// Moved along with the edits, unless they are set explicitly
+    secondary_carets_after: Vec<MinimalCaret>,
     edits: Vec<BasicEdit>,
+    /// How many of the edits were already applied to the input tree
+    applied_edits_count: usize,
 }
 
 impl<'editor> EditorActionBuilder<'editor> {
     pub fn new(editor: &'editor mut MathEditor) -> Self {
         let caret_before = editor.caret.clone();
+        let secondary_carets_before = editor.secondary_carets.clone();
         Self {
             editor,
             caret_before,
+            secondary_carets_after: secondary_carets_before.clone(),
+            secondary_carets_before,
             edits: Vec::new(),
+            applied_edits_count: 0,
         }
     }
 
     pub fn add_edit(&mut self, edit: BasicEdit) {
-        self.edits.push(edit);
+        self.add_edits(vec![edit]);
     }
 
     pub fn add_edits(&mut self, edits: Vec<BasicEdit>) {
+        for caret in self.secondary_carets_after.iter_mut() {
+            caret.apply_edits(&edits);
+        }
         self.edits.extend(edits);
     }
 
+    /// Applies the edits to the input tree right away, so that the next edits can be computed on the updated tree.
+    /// Used for editing at multiple carets. The edits still end up in a single undo step.
+    pub fn apply_edits_now(&mut self, edits: Vec<BasicEdit>) {
+        self.editor.input.apply_edits(&edits);
+        self.editor.parsed = None;
+        self.add_edits(edits);
+        self.applied_edits_count = self.edits.len();
+    }
+
+    pub fn set_secondary_carets(&mut self, carets: Vec<MinimalCaret>) {
+        self.secondary_carets_after = carets;
+    }
+
     pub fn discard(self) {
         // no-op
     }
 
     pub fn finish(self, caret_after: MinimalCaret) -> CaretEdit {
+        if !self.edits.is_empty() {
+            self.editor
+                .input
+                .apply_edits(&self.edits[self.applied_edits_count..]);
+            self.editor.parsed = None;
+        }
+        let mut carets = merge_carets(
+            &self.editor.input,
+            std::iter::once(caret_after)
+                .chain(self.secondary_carets_after)
+                .collect(),
+        );
+        let caret_after = carets.remove(0);
         let edit = CaretEdit {
             caret_before: self.caret_before,
             caret_after,
+            secondary_carets_before: self.secondary_carets_before,
+            secondary_carets_after: carets,
             edits: self.edits,
         };
 
         if !edit.edits.is_empty() {
-            self.editor.input.apply_edits(&edit.edits);
-            self.editor.parsed = None;
+            if let Some(snippet) = &mut self.editor.snippet {
+                snippet.apply_edits(&edit.edits);
+            }
+            if let Some(replaced_selection) = &mut self.editor.replaced_selection {
+                replaced_selection.apply_edits(&edit.edits);
+            }
             self.editor.undo_stack.push(edit.clone().into());
         }
+        if let Some(replaced_selection) = &self.editor.replaced_selection {
+            if !replaced_selection.contains(&edit.caret_after) {
+                self.editor.replaced_selection = None;
+            }
+        }
         self.editor.caret = edit.caret_after.clone();
+        self.editor.secondary_carets = edit.secondary_carets_after.clone();
         edit
     }
 }
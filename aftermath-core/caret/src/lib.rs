@@ -1,6 +1,8 @@
+pub mod autocomplete;
 pub mod caret;
 pub mod editor_action_builder;
 pub mod math_editor;
 pub mod primitive;
+pub mod snippet;
+pub mod symbol_table;
 pub mod undo_redo_manager;
-pub mod autocomplete;
@@ -1,10 +1,17 @@
 use std::rc::Rc;
 
-use crate::autocomplete::{AutocompleteResults, AutocorrectAction, AutocorrectActionBuilder};
+use crate::autocomplete::{
+    AutocompleteResults, AutocorrectAction, AutocorrectActionBuilder, AutocorrectSettings,
+    ReplacedSelection,
+};
 use crate::caret::{CaretSelection, MinimalCaretSelection};
 use crate::editor_action_builder::EditorActionBuilder;
 use crate::primitive::primitive_edit::{insert_at_range, remove_at_caret, CaretRemoveMode};
-use crate::primitive::{CaretEdit, MoveMode, NavigationSettings};
+use crate::primitive::{
+    next_occurrence, next_placeholder, occurrences, operand_start, placeholders,
+    previous_placeholder, token_ranges, tokens, CaretEdit, MoveMode, NavigationSettings,
+};
+use crate::snippet::ActiveSnippet;
 use crate::{
     caret::{Caret, MinimalCaret},
     primitive::UndoAction,
@@ -12,16 +19,17 @@ use crate::{
 };
 use input_tree::editing::editable::Editable;
 use input_tree::editing::BasicEdit;
-use input_tree::focus::InputRowRange;
+use input_tree::focus::{InputRowPosition, InputRowRange};
 use input_tree::input_tree::InputTree;
-use input_tree::row::{InputRow, Offset};
+use input_tree::row::{InputRow, Offset, RowIndex};
 use input_tree::{
     direction::{Direction, VerticalDirection},
     focus::{MinimalInputRowPosition, MinimalInputRowRange},
-    node::InputNode,
+    node::{Fence, InputNode, InputNodeVariant},
 };
 
 use parser::autocomplete::{AutocompleteMatcher, AutocompleteRule, AutocompleteRuleMatch};
+use parser::diagnostic::Diagnostic;
 use parser::parser::MathParser;
 use parser::syntax_tree::SyntaxNode;
 use serialization::{deserialize_input_nodes, serialize_input_nodes};
@@ -37,10 +45,18 @@ pub struct MathEditor {
     pub(crate) parsed: Option<SyntaxNode>,
     /// Main caret
     pub(crate) caret: MinimalCaret,
+    /// Additional carets for editing at multiple places at once.
+    /// Typing and deleting happens at every caret, while the autocomplete and snippets only follow the main caret.
+    pub(crate) secondary_carets: Vec<MinimalCaret>,
     /// Selection mode, describes how the current selection works
     pub(crate) selection_mode: Option<MoveMode>,
     /// Keeps track of the autocomplete popup
     pub(crate) autocomplete_state: AutocompleteState,
+    pub(crate) autocorrect_settings: AutocorrectSettings,
+    /// Tab stops of the last inserted snippet, until the user tabs past the last one
+    pub(crate) snippet: Option<ActiveSnippet>,
+    /// The selection that the user is typing a keyword over
+    pub(crate) replaced_selection: Option<ReplacedSelection>,
     /// Undo-redo stack, will record actual edits
     pub(crate) undo_stack: UndoRedoManager<UndoAction>,
     pub(crate) caret_mover: NavigationSettings,
@@ -54,8 +70,12 @@ impl MathEditor {
             parser,
             parsed: None,
             caret: Default::default(),
+            secondary_carets: vec![],
             selection_mode: None,
             autocomplete_state: AutocompleteState::new(),
+            autocorrect_settings: AutocorrectSettings::new(),
+            snippet: None,
+            replaced_selection: None,
             undo_stack: UndoRedoManager::new(),
             caret_mover: NavigationSettings::new(),
         }
@@ -72,34 +92,46 @@ impl MathEditor {
     }
     pub fn move_caret(&mut self, direction: Direction, mode: MoveMode) {
         let autocorrect = self.start_autocorrect();
-        let builder = EditorActionBuilder::new(self);
-        let mut caret = Caret::from_minimal(&builder.input, &builder.caret);
-        builder.caret_mover.move_caret(&mut caret, direction, mode);
-        let caret = caret.to_minimal();
+        let mut builder = EditorActionBuilder::new(self);
+        let move_caret = |caret: &MinimalCaret| {
+            let mut caret = Caret::from_minimal(&builder.input, caret);
+            builder.caret_mover.move_caret(&mut caret, direction, mode);
+            caret.to_minimal()
+        };
+        let caret = move_caret(&builder.caret);
+        let secondary_carets = builder.secondary_carets.iter().map(move_caret).collect();
+        builder.set_secondary_carets(secondary_carets);
         let action = builder.finish(caret);
         self.apply_autocorrect(autocorrect.finish(&action.edits));
     }
     pub fn select_with_caret(&mut self, direction: Direction, mode: MoveMode) -> Option<()> {
         let autocorrect = self.start_autocorrect();
-        let builder = EditorActionBuilder::new(self);
-        let selection = Caret::from_minimal(&builder.input, &builder.caret).into_selection();
-        let caret = match selection {
-            CaretSelection::Row(range) => {
-                let new_end = builder.caret_mover.move_caret_range(
-                    (&range.end_position()).into(),
-                    direction,
-                    mode,
-                )?;
-                MinimalCaret {
-                    start_position: builder.caret.start_position.clone(),
-                    end_position: new_end.to_minimal(),
+        let mut builder = EditorActionBuilder::new(self);
+        let select_with_caret = |caret: &MinimalCaret| {
+            let selection = Caret::from_minimal(&builder.input, caret).into_selection();
+            match selection {
+                CaretSelection::Row(range) => {
+                    let new_end = builder.caret_mover.move_caret_range(
+                        (&range.end_position()).into(),
+                        direction,
+                        mode,
+                    )?;
+                    Some(MinimalCaret {
+                        start_position: caret.start_position.clone(),
+                        end_position: new_end.to_minimal(),
+                    })
                 }
-            }
-            CaretSelection::Grid(_) => {
-                // Grid selection changing needs to be implemented
-                todo!()
+                // Grid selection changing needs to be implemented, until then the caret stays where it is
+                CaretSelection::Grid(_) => None,
             }
         };
+        let caret = select_with_caret(&builder.caret)?;
+        let secondary_carets = builder
+            .secondary_carets
+            .iter()
+            .map(|caret| select_with_caret(caret).unwrap_or_else(|| caret.clone()))
+            .collect();
+        builder.set_secondary_carets(secondary_carets);
         let action = builder.finish(caret);
         self.apply_autocorrect(autocorrect.finish(&action.edits));
         Some(())
@@ -110,65 +142,270 @@ impl MathEditor {
         _move_mode: MoveMode,
     ) -> Option<()> {
         let autocorrect = self.start_autocorrect();
-        let mut builder = EditorActionBuilder::new(self);
-        let selection = Caret::from_minimal(&builder.input, &builder.caret).into_selection();
-        let new_caret = match selection {
-            CaretSelection::Row(range) => {
-                let (basic_edit, new_position) =
-                    remove_at_caret(&builder.caret_mover, &range, remove_mode)?;
-                builder.add_edits(basic_edit);
-                MinimalCaret {
-                    start_position: new_position.clone(),
-                    end_position: new_position,
-                }
-            }
-            CaretSelection::Grid(_range) => {
-                // Grid deleting needs to be implemented
-                todo!();
-            }
-        };
-
-        let action = builder.finish(new_caret);
+        let action = self.edit_at_carets(|editor, range| {
+            remove_at_caret(&editor.caret_mover, range, remove_mode)
+        })?;
         self.apply_autocorrect(autocorrect.finish(&action.edits));
+        self.mirror_snippet();
         Some(())
     }
 
     /// Can also accept a \n and other special characters
     /// For example, when the user presses enter, we can insert a new row (table)
     pub fn insert_at_caret(&mut self, values: Vec<String>) {
+        if self.skip_closing_fence(&values) {
+            return;
+        }
         let autocorrect = self.start_autocorrect();
-        let action =
-            self.insert_nodes_at_caret(values.into_iter().map(InputNode::Symbol).collect());
+        let action = match self.forced_autocorrect(&values) {
+            Some(action) => Some(action),
+            None => match self.wrap_selection_in_fence(&values) {
+                Some(fenced) => self.insert_nodes_at_caret(vec![fenced]),
+                None => self.type_over_selection(values),
+            },
+        };
         if let Some(action) = action {
             self.apply_autocorrect(autocorrect.finish(&action.edits));
+            self.mirror_snippet();
         }
     }
-    fn insert_nodes_at_caret(&mut self, values: Vec<InputNode>) -> Option<CaretEdit> {
-        let mut builder = EditorActionBuilder::new(self);
-        let selection = Caret::from_minimal(&builder.input, &builder.caret).into_selection();
-        let new_caret = match selection {
-            CaretSelection::Row(range) => {
-                let (basic_edit, new_position) = insert_at_range(&range, values)?;
-                builder.add_edits(basic_edit);
-                MinimalCaret {
-                    start_position: new_position.clone(),
-                    end_position: new_position,
-                }
+    /// Typing replaces the selection, but the selection is remembered in case a keyword like sqrt is being typed
+    fn type_over_selection(&mut self, values: Vec<String>) -> Option<CaretEdit> {
+        let replaced = match Caret::from_minimal(&self.input, &self.caret).into_selection() {
+            CaretSelection::Row(range) if !range.is_collapsed() && self.has_single_caret() => {
+                Some((
+                    range.values().to_vec(),
+                    range.row_indices().clone(),
+                    range.left_offset(),
+                ))
             }
-            CaretSelection::Grid(_range) => {
-                // Grid inserting needs to be implemented
-                todo!();
+            _ => None,
+        };
+        let count = values.len();
+        let action =
+            self.insert_nodes_at_caret(values.into_iter().map(InputNode::Symbol).collect());
+        if let (Some((values, row_indices, start)), Some(_)) = (replaced, &action) {
+            self.replaced_selection = Some(ReplacedSelection {
+                values,
+                keyword_range: MinimalInputRowRange {
+                    row_indices,
+                    start,
+                    end: Offset(start.0 + count),
+                },
+            });
+        }
+        action
+    }
+    /// Some symbols turn into containers right away, instead of going through the autocomplete.
+    /// - `^` and `_` create a superscript or subscript
+    /// - `/` creates a fraction, with the selection or the operand before the caret as the numerator
+    /// - `(` creates an empty pair of round brackets. Other brackets stay symbols, since curly brackets are
+    ///   parsed as symbols by the set-builder rules, and square brackets can be closed by a round one, like in [0, 1).
+    ///
+    /// With multiple carets, the symbols are inserted as they are.
+    fn forced_autocorrect(&mut self, values: &[String]) -> Option<CaretEdit> {
+        let value = match values {
+            [value] if self.has_single_caret() => value.as_str(),
+            _ => return None,
+        };
+        let range = match Caret::from_minimal(&self.input, &self.caret).into_selection() {
+            CaretSelection::Row(range) if !is_in_text(&range.start_position()) => {
+                range.to_minimal()
             }
+            _ => return None,
+        };
+        if value == "/" {
+            if let Some(edit) = self.unfold_empty_fraction(&range) {
+                return Some(edit);
+            }
+        }
+        let selected = InputRowRange::from_minimal(self.input.root_focus(), &range)
+            .values()
+            .to_vec();
+        let caret_offset = Offset(selected.len());
+        let (range, container, caret_row, caret_offset) = match value {
+            "^" => (
+                range,
+                InputNode::sup(InputRow::new(selected)),
+                0,
+                caret_offset,
+            ),
+            "_" => (
+                range,
+                InputNode::sub(InputRow::new(selected)),
+                0,
+                caret_offset,
+            ),
+            "(" if range.start == range.end => (
+                range,
+                InputNode::fenced(Fence::Round, Fence::Round, InputRow::default()),
+                0,
+                Offset(0),
+            ),
+            "/" => {
+                let range = if range.start == range.end {
+                    let position = MinimalInputRowPosition {
+                        row_indices: range.row_indices.clone(),
+                        offset: range.end,
+                    };
+                    MinimalInputRowRange {
+                        start: operand_start(self.get_syntax_tree(), &position),
+                        ..range
+                    }
+                } else {
+                    range
+                };
+                let numerator = InputRowRange::from_minimal(self.input.root_focus(), &range)
+                    .values()
+                    .to_vec();
+                let fraction = InputNode::fraction([InputRow::new(numerator), InputRow::default()]);
+                (range, fraction, 1, Offset(0))
+            }
+            _ => return None,
         };
-        Some(builder.finish(new_caret))
 
-        // TODO:
-        // Forced autocorrect (ligatures anyone?)
-        // - / fraction
-        // - ^ exponent
-        // - _ subscript
+        // The caret goes into the new container
+        let mut row_indices = range.row_indices.clone();
+        row_indices.push(RowIndex(range.start.min(range.end).0, caret_row));
+        let position = MinimalInputRowPosition {
+            row_indices,
+            offset: caret_offset,
+        };
+        let mut builder = EditorActionBuilder::new(self);
+        let (basic_edit, _) = BasicEdit::replace_range(
+            &InputRowRange::from_minimal(builder.input.root_focus(), &range),
+            vec![container],
+        );
+        builder.add_edits(basic_edit);
+        Some(builder.finish(MinimalCaret::collapsed(position)))
+    }
+    /// Typing / again in an empty denominator turns the fraction back into a literal /, for writing a/b inline.
+    /// frac(a, |) becomes a/|
+    fn unfold_empty_fraction(&mut self, range: &MinimalInputRowRange) -> Option<CaretEdit> {
+        let range = InputRowRange::from_minimal(self.input.root_focus(), range);
+        let position = range.start_position();
+        if !range.is_collapsed()
+            || !position.row_focus.is_empty()
+            || position.row_focus.index_in_parent() != Some(1)
+        {
+            return None;
+        }
+        let fraction = position.row_focus.parent()?;
+        if !matches!(
+            fraction.node(),
+            InputNode::Container(InputNodeVariant::Fraction, _)
+        ) {
+            return None;
+        }
+        let mut values = fraction.clone().child_at(0).ok()?.row().values.clone();
+        values.push(InputNode::symbol("/"));
+        let index = fraction.index_in_parent();
+        let row_focus = fraction.parent();
+        let position = MinimalInputRowPosition {
+            row_indices: row_focus.row_indices().clone(),
+            offset: Offset(index + values.len()),
+        };
+        let fraction_range =
+            InputRowRange::new(row_focus, Offset(index), Offset(index + 1)).to_minimal();
+
+        let mut builder = EditorActionBuilder::new(self);
+        let (basic_edit, _) = BasicEdit::replace_range(
+            &InputRowRange::from_minimal(builder.input.root_focus(), &fraction_range),
+            values,
+        );
+        builder.add_edits(basic_edit);
+        Some(builder.finish(MinimalCaret::collapsed(position)))
+    }
+    /// Typing the closing bracket at the end of a group moves the caret out of the group, instead of adding a bracket.
+    /// (a+b|) becomes (a+b)|
+    fn skip_closing_fence(&mut self, values: &[String]) -> bool {
+        let value = match values {
+            [value] if self.has_single_caret() => value.as_str(),
+            _ => return false,
+        };
+        let range = match Caret::from_minimal(&self.input, &self.caret).into_selection() {
+            CaretSelection::Row(range) if range.is_collapsed() => range,
+            _ => return false,
+        };
+        let position = range.start_position();
+        if position.offset.0 != position.row_focus.len() {
+            return false;
+        }
+        let Some(parent) = position.row_focus.clone().parent() else {
+            return false;
+        };
+        match parent.node() {
+            InputNode::Container(InputNodeVariant::Fenced(_, close), _)
+                if close.closing() == value => {}
+            _ => return false,
+        }
+        let index = parent.index_in_parent();
+        self.caret = MinimalCaret::collapsed(MinimalInputRowPosition {
+            row_indices: parent.parent().row_indices().clone(),
+            offset: Offset(index + 1),
+        });
+        true
+    }
+    /// Typing an opening bracket while something is selected puts the selection in brackets
+    fn wrap_selection_in_fence(&self, values: &[String]) -> Option<InputNode> {
+        let fence = match values {
+            [value] if self.has_single_caret() => Fence::from_opening(value)?,
+            _ => return None,
+        };
+        match Caret::from_minimal(&self.input, &self.caret).into_selection() {
+            CaretSelection::Row(range) if !range.is_collapsed() => Some(InputNode::fenced(
+                fence,
+                fence,
+                InputRow::new(range.values().to_vec()),
+            )),
+            _ => None,
+        }
+    }
+    fn insert_nodes_at_caret(&mut self, values: Vec<InputNode>) -> Option<CaretEdit> {
+        self.edit_at_carets(|_, range| insert_at_range(range, values.clone()))
+    }
+    /// Does an edit at every caret, starting with the main caret.
+    /// Each edit is computed on the tree that already has the previous edits, and the other carets are moved along.
+    /// Everything ends up in one undo step.
+    fn edit_at_carets(
+        &mut self,
+        edit_at: impl Fn(
+            &MathEditor,
+            &InputRowRange<'_>,
+        ) -> Option<(Vec<BasicEdit>, MinimalInputRowPosition)>,
+    ) -> Option<CaretEdit> {
+        let mut builder = EditorActionBuilder::new(self);
+        let mut carets: Vec<MinimalCaret> = std::iter::once(builder.caret.clone())
+            .chain(builder.secondary_carets.iter().cloned())
+            .collect();
+        let mut has_edits = false;
+        for index in 0..carets.len() {
+            let selection = Caret::from_minimal(&builder.input, &carets[index]).into_selection();
+            let (basic_edit, new_position) = match selection {
+                CaretSelection::Row(range) => match edit_at(&builder, &range) {
+                    Some(v) => v,
+                    None => continue,
+                },
+                // Grid editing needs to be implemented, until then the caret is skipped
+                CaretSelection::Grid(_) => continue,
+            };
+            for caret in carets.iter_mut() {
+                caret.apply_edits(&basic_edit);
+            }
+            carets[index] = MinimalCaret::collapsed(new_position);
+            builder.apply_edits_now(basic_edit);
+            has_edits = true;
+        }
+        if !has_edits {
+            builder.discard();
+            return None;
+        }
+        let caret = carets.remove(0);
+        builder.set_secondary_carets(carets);
+        Some(builder.finish(caret))
     }
     pub fn select_all(&mut self) {
+        self.secondary_carets.clear();
         self.caret = MinimalCaret {
             start_position: MinimalInputRowPosition {
                 row_indices: Default::default(),
@@ -180,6 +417,57 @@ impl MathEditor {
             },
         }
     }
+    /// Selects the next empty row or placeholder, so that typing fills it in. Wraps around at the end.
+    /// Inside of a snippet, this goes to the next tab stop instead.
+    pub fn move_to_next_placeholder(&mut self) -> Option<()> {
+        self.snippet_index_at_caret();
+        if let Some(snippet) = &mut self.snippet {
+            match snippet.next_tab_stop() {
+                Some(tab_stop) => {
+                    let tab_stop = tab_stop.clone();
+                    self.select_placeholder(tab_stop);
+                    return Some(());
+                }
+                None => self.snippet = None,
+            }
+        }
+        let caret = Caret::from_minimal(&self.input, &self.caret);
+        let position = caret
+            .start_position()
+            .max(caret.end_position())
+            .to_minimal();
+        let placeholder = next_placeholder(&placeholders(&self.input.root), &position)?;
+        self.select_placeholder(placeholder);
+        Some(())
+    }
+    /// Selects the previous empty row or placeholder. Wraps around at the start.
+    pub fn move_to_previous_placeholder(&mut self) -> Option<()> {
+        self.snippet_index_at_caret();
+        if let Some(snippet) = &mut self.snippet {
+            let tab_stop = snippet.previous_tab_stop().clone();
+            self.select_placeholder(tab_stop);
+            return Some(());
+        }
+        let caret = Caret::from_minimal(&self.input, &self.caret);
+        let position = caret
+            .start_position()
+            .min(caret.end_position())
+            .to_minimal();
+        let placeholder = previous_placeholder(&placeholders(&self.input.root), &position)?;
+        self.select_placeholder(placeholder);
+        Some(())
+    }
+    /// Placeholders are filled in one at a time, so this also gets rid of the other carets
+    fn select_placeholder(&mut self, placeholder: MinimalInputRowRange) {
+        self.secondary_carets.clear();
+        self.select_range(placeholder);
+    }
+    fn select_range(&mut self, range: MinimalInputRowRange) {
+        let autocorrect = self.start_autocorrect();
+        let builder = EditorActionBuilder::new(self);
+        let action = builder.finish(MinimalCaret::from_range(&range));
+        self.apply_autocorrect(autocorrect.finish(&action.edits));
+    }
     pub fn undo(&mut self) -> Option<()> {
         let action = self.undo_stack.undo()?;
         self.apply_action(action);
@@ -194,8 +482,10 @@ impl MathEditor {
         match action {
             UndoAction::CaretEdit(caret_edit) => {
                 self.caret = caret_edit.caret_after;
+                self.secondary_carets = caret_edit.secondary_carets_after;
                 self.input.apply_edits(&caret_edit.edits);
                 self.parsed = None;
+                self.snippet = None;
             }
         }
     }
@@ -203,13 +493,71 @@ impl MathEditor {
     pub fn start_selection(&mut self, position: MinimalInputRowPosition, mode: MoveMode) {
         self.selection_mode = Some(mode);
         let autocorrect = self.start_autocorrect();
-        let builder = EditorActionBuilder::new(self);
+        let mut builder = EditorActionBuilder::new(self);
         // TODO: Use the mode. Kinda like
         // editor.caret_mover.move_mode_to_range(mode) // and then use that info to extend the selection
-        let action = builder.finish(MinimalCaret {
-            start_position: position.clone(),
-            end_position: position,
-        });
+        builder.set_secondary_carets(vec![]);
+        let action = builder.finish(MinimalCaret::collapsed(position));
+        self.apply_autocorrect(autocorrect.finish(&action.edits));
+    }
+    /// Adds another caret, which becomes the main caret. The old carets stay where they are.
+    pub fn add_caret(&mut self, position: MinimalInputRowPosition) {
+        self.add_main_caret(MinimalCaret::collapsed(position));
+    }
+    /// Selects the token at the caret. If something is already selected, then the next place with the same content gets a new caret.
+    pub fn select_next_occurrence(&mut self) -> Option<()> {
+        let range = match Caret::from_minimal(&self.input, &self.caret).into_selection() {
+            CaretSelection::Row(range) => range.to_minimal(),
+            CaretSelection::Grid(_) => return None,
+        };
+        if range.start == range.end {
+            // The token before the caret wins, since that's usually the one that was just typed
+            let offset = range.start.0;
+            let tokens = token_ranges(self.get_syntax_tree(), &range.row_indices);
+            let token = tokens
+                .iter()
+                .find(|token| token.start < offset && offset <= token.end)
+                .or_else(|| tokens.iter().find(|token| token.start == offset))?
+                .clone();
+            self.select_range(MinimalInputRowRange {
+                row_indices: range.row_indices,
+                start: Offset(token.start),
+                end: Offset(token.end),
+            });
+            return Some(());
+        }
+
+        let values = InputRowRange::from_minimal(self.input.root_focus(), &range)
+            .values()
+            .to_vec();
+        let selected: Vec<_> = self
+            .get_caret()
+            .into_iter()
+            .filter_map(|selection| match selection {
+                MinimalCaretSelection::Row(range) => Some(range),
+                MinimalCaretSelection::Grid(_) => None,
+            })
+            .collect();
+        let position = MinimalInputRowPosition {
+            row_indices: range.row_indices.clone(),
+            offset: range.start.max(range.end),
+        };
+        let next = next_occurrence(
+            &occurrences(&self.input.root, &values),
+            &position,
+            &selected,
+        )?;
+        self.add_main_caret(MinimalCaret::from_range(&next));
+        Some(())
+    }
+    fn add_main_caret(&mut self, caret: MinimalCaret) {
+        let autocorrect = self.start_autocorrect();
+        let mut builder = EditorActionBuilder::new(self);
+        let secondary_carets = std::iter::once(builder.caret.clone())
+            .chain(builder.secondary_carets.iter().cloned())
+            .collect();
+        builder.set_secondary_carets(secondary_carets);
+        let action = builder.finish(caret);
         self.apply_autocorrect(autocorrect.finish(&action.edits));
     }
     pub fn extend_selection(&mut self, position: MinimalInputRowPosition) {
@@ -257,6 +605,7 @@ impl MathEditor {
         let action = self.insert_nodes_at_caret(nodes);
         if let Some(action) = action {
             self.apply_autocorrect(autocorrect.finish(&action.edits));
+            self.mirror_snippet();
         }
         Ok(())
     }
@@ -271,8 +620,27 @@ impl MathEditor {
         let autocomplete = self.get_autocomplete()?;
         let selected_autocomplete = autocomplete.get_selected();
         let range = autocomplete.get_caret_range(selected_autocomplete);
-        let values = selected_autocomplete.rule.result.to_vec();
+        let mut values = selected_autocomplete.rule.result.to_vec();
+        let keyword = selected_autocomplete.rule.parser.clone();
+        let position = MinimalInputRowPosition {
+            row_indices: range.row_indices.clone(),
+            offset: range.start.min(range.end),
+        };
+        let snippet = ActiveSnippet::new(&selected_autocomplete.rule.tab_stops, &position);
+        if let Some(replaced_selection) = self.replaced_selection.take() {
+            if replaced_selection.is_keyword(&range) {
+                values = wrap_nodes(values, replaced_selection.values);
+            }
+        }
         self.splice_at_range(range, values);
+        self.autocomplete_state.remember_used(keyword);
+
+        // Snippets start at their first tab stop
+        if let Some(snippet) = snippet {
+            let tab_stop = snippet.current().clone();
+            self.snippet = Some(snippet);
+            self.select_placeholder(tab_stop);
+        }
         Some(())
     }
     pub fn move_in_autocomplete(&mut self, direction: VerticalDirection) -> Option<()> {
@@ -291,6 +659,9 @@ impl MathEditor {
     /// When the caret moves, we apply "perfect match" autocompletes
     fn apply_autocorrect(&mut self, autocorrect: Option<AutocorrectAction>) -> Option<()> {
         let autocorrect = autocorrect?;
+        if !self.autocorrect_settings.is_enabled(&autocorrect.rule) {
+            return None;
+        }
         let caret_start_position =
             match Caret::from_minimal(&self.input, &self.caret).into_selection() {
                 CaretSelection::Row(range) => Some(range.start_position()),
@@ -305,31 +676,111 @@ impl MathEditor {
         }
 
         // It might no longer be a perfect match, so we need to check again
+        let values = autocomplete_range.values();
         let is_perfect_match = autocorrect
             .rule
-            .matches(
-                autocomplete_range.values(),
-                autocomplete_range.left_offset().0,
-                1,
-            )
+            .matches(values, values.len(), 1)
             .iter()
-            .any(|rule_match| rule_match.is_complete_match());
+            .any(|rule_match| {
+                rule_match.is_complete_match() && rule_match.input_match_length == values.len()
+            });
 
         if !is_perfect_match {
             return None;
         }
 
-        // TODO:
-        // if autocomplete_range.left < caret.start_position && we have a valid autocomplete option at (autocomplete_range.left..caret.start_position) {
-        // then we're in a case like "lim|" -> "lims|" -> "limsup|"
-        // in that case, we don't want to overzealously apply the autocorrect
-        //  return None;
-        //}
+        // The caret jumped further ahead in the same row, like in "lim|s" -> "lims|".
+        // If a longer rule like "limsup" could still match, then the user probably isn't done yet.
+        let left_position = autocomplete_range.left_position();
+        if caret_start_position.row_focus.row_indices() == left_position.row_focus.row_indices()
+            && left_position.offset < caret_start_position.offset
+        {
+            let caret_offset = caret_start_position.offset.0;
+            let is_longer_match = self
+                .parser
+                .matches(
+                    &caret_start_position.row_focus.row().values,
+                    caret_offset,
+                    1,
+                )
+                .iter()
+                .any(|rule_match| {
+                    !rule_match.is_fuzzy()
+                        && caret_offset - rule_match.input_match_length == left_position.offset.0
+                });
+            if is_longer_match {
+                return None;
+            }
+        }
 
         self.splice_at_range(
             autocomplete_range.to_minimal(),
             autocorrect.rule.result.to_vec(),
         );
+        self.autocomplete_state
+            .remember_used(autocorrect.rule.parser);
+        Some(())
+    }
+
+    /// Finds the tab stop that the caret is in.
+    /// Once the caret leaves every tab stop, for example by clicking somewhere else, the snippet is done.
+    fn snippet_index_at_caret(&mut self) -> Option<usize> {
+        let snippet = self.snippet.as_ref()?;
+        let caret_position = match Caret::from_minimal(&self.input, &self.caret).into_selection() {
+            CaretSelection::Row(range) => Some(range.start_position()),
+            CaretSelection::Grid(_) => None,
+        };
+        let index = caret_position.and_then(|caret_position| {
+            snippet.ranges().position(|range| {
+                InputRowRange::from_minimal(self.input.root_focus(), range)
+                    .contains(&caret_position)
+            })
+        });
+        if index.is_none() {
+            self.snippet = None;
+        }
+        index
+    }
+    /// Linked tab stops of a snippet always have the same content.
+    /// So after editing one of them, the others get replaced with a copy, in a single undo step.
+    fn mirror_snippet(&mut self) -> Option<()> {
+        let edited_index = self.snippet_index_at_caret()?;
+        let snippet = self.snippet.as_ref()?;
+        let values =
+            InputRowRange::from_minimal(self.input.root_focus(), snippet.get(edited_index))
+                .values()
+                .to_vec();
+
+        let mut linked_ranges: Vec<_> = snippet
+            .linked(edited_index)
+            .into_iter()
+            .map(|index| snippet.get(index).clone())
+            .collect();
+        let mut caret = self.caret.clone();
+        let mut builder = EditorActionBuilder::new(self);
+        let mut has_edits = false;
+        while !linked_ranges.is_empty() {
+            let linked_range = linked_ranges.remove(0);
+            let basic_edit = {
+                let range = InputRowRange::from_minimal(builder.input.root_focus(), &linked_range);
+                if range.values() == values {
+                    continue;
+                }
+                BasicEdit::replace_range(&range, values.clone()).0
+            };
+            // The edits are applied right away, so the other ranges have to move along
+            caret.apply_edits(&basic_edit);
+            for other_range in linked_ranges.iter_mut() {
+                other_range.apply_edits(&basic_edit);
+            }
+            builder.apply_edits_now(basic_edit);
+            has_edits = true;
+        }
+        if has_edits {
+            builder.finish(caret);
+        } else {
+            builder.discard();
+        }
         Some(())
     }
 
@@ -340,16 +791,57 @@ impl MathEditor {
             CaretSelection::Row(_) => return None,
             CaretSelection::Grid(_) => return None,
         };
+        if is_in_text(&position) {
+            return None;
+        }
+        let position = position.to_minimal();
+
+        // Suggestions that start in the middle of a token of another rule are nonsense, like "infinity" for "lim|inf".
+        // Variable names are fine, since a keyword can be typed right after a variable, like in "xsqrt".
+        let parser = self.parser.clone();
+        let tokens: Vec<_> = tokens(self.get_syntax_tree(), &position.row_indices)
+            .into_iter()
+            .filter(|token| !parser.is_identifier(token))
+            .map(|token| token.range())
+            .collect();
+        let is_inside_token = |start: usize| {
+            tokens
+                .iter()
+                .any(|token| token.start < start && start < token.end)
+        };
+        let row = self
+            .input
+            .root_focus()
+            .walk_down_indices(&position.row_indices);
         let matches = self
             .parser
-            .matches(&position.row_focus.row().values, position.offset.0, 2);
+            .matches(&row.row().values, position.offset.0, 2)
+            .into_iter()
+            .filter(|rule_match| {
+                !is_inside_token(position.offset.0 - rule_match.input_match_length)
+            })
+            .collect();
 
-        self.autocomplete_state
-            .get_autocomplete(matches, position.to_minimal())
+        self.autocomplete_state.get_autocomplete(matches, position)
+    }
+    /// Lets the user opt out of rules that keep getting in the way, like "in" for someone who types a lot of variable names.
+    /// The rule still shows up in the autocomplete popup.
+    pub fn set_autocorrect_enabled(&mut self, keyword: &str, enabled: bool) {
+        self.autocorrect_settings.set_enabled(keyword, enabled);
     }
+    fn has_single_caret(&self) -> bool {
+        self.secondary_carets.is_empty()
+    }
+    /// The main caret comes first
     pub fn get_caret(&self) -> Vec<MinimalCaretSelection> {
-        let selection = Caret::from_minimal(&self.input, &self.caret).into_selection();
-        vec![selection.to_minimal()]
+        std::iter::once(&self.caret)
+            .chain(self.secondary_carets.iter())
+            .map(|caret| {
+                Caret::from_minimal(&self.input, caret)
+                    .into_selection()
+                    .to_minimal()
+            })
+            .collect()
     }
     pub fn get_input_tree(&self) -> &InputRow {
         &self.input.root
@@ -363,6 +855,11 @@ impl MathEditor {
         self.parsed = Some(parsed);
         self.parsed.as_ref().unwrap()
     }
+
+    pub fn get_diagnostics(&mut self) -> Vec<Diagnostic> {
+        let parser = self.parser.clone();
+        parser.get_diagnostics(self.get_syntax_tree())
+    }
     /// For setting some parsed MathML, or for inserting a result
     /// We have access to the syntax tree, so we know what sensible ranges are (e.g. "range after equals sign" or "range of root node")
     pub fn splice_at_range(&mut self, range: MinimalInputRowRange, values: Vec<InputNode>) {
@@ -371,7 +868,6 @@ impl MathEditor {
         // Move caret
         // Apply edit
         // Merge edit into undo stack
-        let caret_before = self.caret.clone();
         let mut caret = self.caret.clone();
         let mut builder = EditorActionBuilder::new(self);
         let (basic_edit, _) = BasicEdit::replace_range(
@@ -382,15 +878,41 @@ impl MathEditor {
         caret.end_position.apply_edits(&basic_edit);
         builder.add_edits(basic_edit);
         builder.finish(caret);
-        self.caret = caret_before;
     }
 }
 
+/// Text is prose, so nothing gets autocompleted or autocorrected
+fn is_in_text(position: &InputRowPosition) -> bool {
+    position
+        .row_focus
+        .clone()
+        .parent()
+        .map(|v| matches!(v.node(), InputNode::Container(InputNodeVariant::Text, _)))
+        .unwrap_or(false)
+}
+
+/// Puts the nodes into the row of a container that usually gets wrapped around a selection, like the radicand of a root
+fn wrap_nodes(mut result: Vec<InputNode>, nodes: Vec<InputNode>) -> Vec<InputNode> {
+    let row_index = match &result[..] {
+        [InputNode::Container(InputNodeVariant::Fraction, _)] => 0,
+        [InputNode::Container(InputNodeVariant::Root, _)] => 1,
+        _ => return result,
+    };
+    *result[0].row_mut(row_index) = InputRow::new(nodes);
+    result
+}
+
 pub struct AutocompleteState {
     /// Autocomplete rule that the main caret was last on
     current_autocomplete: Option<AutocompleteRule>,
+    /// Keywords of the rules that were applied recently, with the most recent one last
+    recently_used: Vec<String>,
 }
 
+/// How many recently used rules get a bonus. Each bonus is smaller than the score of a matched symbol,
+/// so a recently used rule wins against similar matches, but not against better ones.
+const RECENTLY_USED_LIMIT: usize = 8;
+
 impl Default for AutocompleteState {
     fn default() -> Self {
         Self::new()
@@ -401,6 +923,7 @@ impl AutocompleteState {
     pub fn new() -> Self {
         Self {
             current_autocomplete: None,
+            recently_used: vec![],
         }
     }
 
@@ -414,7 +937,9 @@ impl AutocompleteState {
             self.current_autocomplete = None;
             return None;
         }
+        // Stable sort, so equally good matches keep their order
         matches.sort();
+        matches.sort_by_cached_key(|v| std::cmp::Reverse(v.score() + self.recency_score(v.rule)));
 
         let selected_index = self
             .current_autocomplete
@@ -433,4 +958,21 @@ impl AutocompleteState {
     pub fn set_current_autocomplete(&mut self, rule: Option<AutocompleteRule>) {
         self.current_autocomplete = rule;
     }
+
+    pub fn remember_used(&mut self, keyword: String) {
+        self.recently_used.retain(|v| v != &keyword);
+        self.recently_used.push(keyword);
+        if self.recently_used.len() > RECENTLY_USED_LIMIT {
+            self.recently_used.remove(0);
+        }
+    }
+
+    fn recency_score(&self, rule: &AutocompleteRule) -> i32 {
+        self.recently_used
+            .iter()
+            .rev()
+            .position(|keyword| keyword == &rule.parser)
+            .map(|age| (RECENTLY_USED_LIMIT - age) as i32)
+            .unwrap_or(0)
+    }
 }
@@ -1,6 +1,14 @@
 mod caret_edit;
 mod movement;
+mod occurrences;
+mod operand;
+mod placeholders;
 pub mod primitive_edit;
+mod token;
 
 pub use caret_edit::*;
 pub use movement::*;
+pub use occurrences::*;
+pub use operand::*;
+pub use placeholders::*;
+pub use token::*;
@@ -21,6 +21,9 @@ impl Invertible for UndoAction {
 pub struct CaretEdit {
     pub caret_before: MinimalCaret,
     pub caret_after: MinimalCaret,
+    /// The other carets, when editing at multiple places at once
+    pub secondary_carets_before: Vec<MinimalCaret>,
+    pub secondary_carets_after: Vec<MinimalCaret>,
     pub edits: Vec<BasicEdit>,
 }
 
@@ -37,6 +40,8 @@ impl Invertible for CaretEdit {
         CaretEdit {
             caret_before: self.caret_after.clone(),
             caret_after: self.caret_before.clone(),
+            secondary_carets_before: self.secondary_carets_after.clone(),
+            secondary_carets_after: self.secondary_carets_before.clone(),
             edits: self.edits.iter().rev().map(|edit| edit.inverse()).collect(),
         }
     }
@@ -1,6 +1,8 @@
 use input_tree::{
     direction::{Direction, HorizontalDirection, VerticalDirection},
-    focus::{InputRowPosition, InputRowRange, MinimalInputRowPosition},
+    focus::{
+        InputFocusNode, InputFocusRow, InputRowPosition, InputRowRange, MinimalInputRowPosition,
+    },
     grid::{Grid, Index2D},
     node::{InputNode, InputNodeVariant},
     row::Offset,
@@ -121,6 +123,16 @@ impl NavigationSettings {
     ) -> Option<InputRowPosition<'a>> {
         let parent = caret.row_focus.clone().parent()?;
 
+        // When I'm in the top sup of a \sum^_, then pressing down should move me to the bottom sub.
+        if let Some(script) = self.adjacent_script(&parent, direction) {
+            let offset = if direction == VerticalDirection::Up {
+                Offset(script.len())
+            } else {
+                Offset(0)
+            };
+            return Some(InputRowPosition::new(script, offset));
+        }
+
         // Leaving subscript or superscript special cases
         match (parent.node(), direction) {
             (InputNode::Container(InputNodeVariant::Sub, _), VerticalDirection::Down)
@@ -131,8 +143,45 @@ impl NavigationSettings {
             _ => {}
         };
 
-        // TODO: subsup supsub special cases
-        // When I'm in the top sup of a \sum^_, then pressing down should move me to the bottom sup.
+        // Under, over and multiscripts containers are vertical stacks, but the base is stored first
+        if let InputNode::Container(
+            variant @ (InputNodeVariant::Under
+            | InputNodeVariant::Over
+            | InputNodeVariant::UnderOver
+            | InputNodeVariant::Multiscripts),
+            grid,
+        ) = parent.node()
+        {
+            let index = caret.row_focus.index_in_parent()?;
+            let rows = variant.rows_top_to_bottom(grid.width() * grid.height(), index);
+            let position = rows.iter().position(|v| *v == index)?;
+            let new_position = match direction {
+                VerticalDirection::Up => position.checked_sub(1),
+                VerticalDirection::Down => Some(position + 1),
+            };
+            return match new_position.and_then(|v| rows.get(v)) {
+                Some(new_index) => {
+                    let new_row = parent.child_at(*new_index).ok()?;
+                    let offset = if direction == VerticalDirection::Up {
+                        Offset(new_row.len())
+                    } else {
+                        Offset(0)
+                    };
+                    Some(InputRowPosition::new(new_row, offset))
+                }
+                // Leaving an annotation or a script puts the caret after the whole container
+                None if index != 0 => {
+                    let offset = Offset(parent.index_in_parent() + 1);
+                    Some(InputRowPosition::new(parent.parent(), offset))
+                }
+                // The base is at the top or bottom, so we keep moving in the parent
+                None => self.move_vertical(
+                    &InputRowPosition::new(parent.parent(), Offset(0)),
+                    direction,
+                    _caret_viewport_position,
+                ),
+            };
+        }
 
         // Grid movement
         let grid = match parent.node() {
@@ -175,6 +224,34 @@ impl NavigationSettings {
         }
     }
 
+    /// A sub directly next to the sup that we're leaving downwards, or the other way around.
+    /// Returns the row of that script.
+    fn adjacent_script<'a>(
+        &self,
+        script: &InputFocusNode<'a>,
+        direction: VerticalDirection,
+    ) -> Option<InputFocusRow<'a>> {
+        let target = match (script.node(), direction) {
+            (InputNode::Container(InputNodeVariant::Sup, _), VerticalDirection::Down) => {
+                InputNodeVariant::Sub
+            }
+            (InputNode::Container(InputNodeVariant::Sub, _), VerticalDirection::Up) => {
+                InputNodeVariant::Sup
+            }
+            _ => return None,
+        };
+        let row = script.clone().parent();
+        let index = script.index_in_parent();
+        [index.checked_sub(1), Some(index + 1)]
+            .into_iter()
+            .flatten()
+            .find(|index| {
+                matches!(row.node_at(*index), Some(InputNode::Container(variant, _)) if *variant == target)
+            })
+            .and_then(|index| row.clone().child_at(index))
+            .and_then(|node| node.child_at(0).ok())
+    }
+
     /// Move to the left or right, but always out of the current element, because we're at the very edge.
     /// Make sure to first check `self.isTouchingEdge(direction)` before calling this function.
     fn move_horizontal_beyond_edge<'a>(
@@ -184,13 +261,25 @@ impl NavigationSettings {
     ) -> Option<InputRowPosition<'a>> {
         let parent = caret.row_focus.clone().parent()?;
 
-        let adjacent_index = caret.row_focus.index_in_parent().map(|v| {
-            if direction == HorizontalDirection::Left {
-                v - 1
-            } else {
-                v + 1
+        // The rows are visited in the order that they appear on screen
+        let rows = match parent.node() {
+            InputNode::Container(variant, grid) => {
+                variant.rows_left_to_right(grid.width() * grid.height())
             }
-        });
+            InputNode::Symbol(_) => vec![],
+        };
+        let adjacent_index = caret
+            .row_focus
+            .index_in_parent()
+            .and_then(|v| rows.iter().position(|row| *row == v))
+            .and_then(|v| {
+                if direction == HorizontalDirection::Left {
+                    v.checked_sub(1)
+                } else {
+                    Some(v + 1)
+                }
+            })
+            .and_then(|v| rows.get(v).copied());
         let adjacent_child =
             adjacent_index.and_then(|adjacent_index| parent.clone().child_at(adjacent_index).ok());
         if let Some(adjacent_child) = adjacent_child {
@@ -224,13 +313,12 @@ impl NavigationSettings {
         let adjacent_child = caret.row_focus.clone().child_at(adjacent_index).unwrap();
 
         match adjacent_child.node() {
-            input_tree::node::InputNode::Container(_, grid) => {
+            input_tree::node::InputNode::Container(variant, grid) => {
+                let rows = variant.rows_left_to_right(grid.width() * grid.height());
                 let adjacent_row = if direction == HorizontalDirection::Left {
-                    adjacent_child
-                        .child_at(grid.width() * grid.height() - 1)
-                        .unwrap()
+                    adjacent_child.child_at(*rows.last().unwrap()).unwrap()
                 } else {
-                    adjacent_child.child_at(0).unwrap()
+                    adjacent_child.child_at(rows[0]).unwrap()
                 };
                 let offset = if direction == HorizontalDirection::Left {
                     Offset(adjacent_row.len())
@@ -0,0 +1,68 @@
+use std::cmp::Ordering;
+
+use input_tree::{
+    focus::{MinimalInputRowPosition, MinimalInputRowRange},
+    node::InputNode,
+    row::{InputRow, Offset, RowIndex, RowIndices},
+};
+
+use super::placeholders::cmp_positions;
+
+/// All places where the nodes appear, in document order.
+/// Occurrences never overlap, so "aa" appears twice in "aaaa".
+pub fn occurrences(root: &InputRow, needle: &[InputNode]) -> Vec<MinimalInputRowRange> {
+    let mut occurrences = vec![];
+    if !needle.is_empty() {
+        collect_occurrences(root, needle, &mut RowIndices::default(), &mut occurrences);
+    }
+    occurrences
+}
+
+fn collect_occurrences(
+    row: &InputRow,
+    needle: &[InputNode],
+    row_indices: &mut RowIndices,
+    occurrences: &mut Vec<MinimalInputRowRange>,
+) {
+    let mut index = 0;
+    while index < row.values.len() {
+        if row.values[index..].starts_with(needle) {
+            occurrences.push(MinimalInputRowRange {
+                row_indices: row_indices.clone(),
+                start: Offset(index),
+                end: Offset(index + needle.len()),
+            });
+            index += needle.len();
+            continue;
+        }
+        if let InputNode::Container(_, grid) = &row.values[index] {
+            for (row_index, child) in grid.values().enumerate() {
+                row_indices.push(RowIndex(index, row_index));
+                collect_occurrences(child, needle, row_indices, occurrences);
+                row_indices.pop();
+            }
+        }
+        index += 1;
+    }
+}
+
+/// The first occurrence after the position that isn't selected yet. Wraps around at the end.
+pub fn next_occurrence(
+    occurrences: &[MinimalInputRowRange],
+    position: &MinimalInputRowPosition,
+    selected: &[MinimalInputRowRange],
+) -> Option<MinimalInputRowRange> {
+    let is_taken = |occurrence: &MinimalInputRowRange| {
+        selected.iter().any(|range| {
+            range.row_indices == occurrence.row_indices
+                && range.start.min(range.end) == occurrence.start
+                && range.start.max(range.end) == occurrence.end
+        })
+    };
+    occurrences
+        .iter()
+        .filter(|v| cmp_positions(&v.row_indices, v.start, position) != Ordering::Less)
+        .chain(occurrences.iter())
+        .find(|v| !is_taken(v))
+        .cloned()
+}
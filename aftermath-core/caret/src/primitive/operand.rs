@@ -0,0 +1,86 @@
+use input_tree::{
+    focus::MinimalInputRowPosition,
+    row::{Offset, RowIndex},
+};
+use parser::syntax_tree::{LeafNodeType, SyntaxNode, SyntaxNodeChildren};
+
+/// Where the operand that ends at the position starts, according to the syntax tree.
+/// For example, it's the `2y` in `x+2y`, or the whole bracket group in `(a+b)`.
+/// Returns the position itself when there is no operand, like after a `+`.
+pub fn operand_start(syntax_tree: &SyntaxNode, position: &MinimalInputRowPosition) -> Offset {
+    let row_indices: Vec<RowIndex> = position.row_indices.iter().copied().collect();
+    let offset = position.offset.0;
+    let start = find_row(syntax_tree, &row_indices)
+        .filter(|row| offset > row.range().start)
+        .map(|row| node_operand_start(row, offset))
+        .unwrap_or(offset);
+    Offset(start)
+}
+
+/// Finds the syntax node of a row, by walking down the containers.
+pub(super) fn find_row<'a>(
+    node: &'a SyntaxNode,
+    row_indices: &[RowIndex],
+) -> Option<&'a SyntaxNode> {
+    let (first, rest) = match row_indices.split_first() {
+        Some(v) => v,
+        None => return Some(node),
+    };
+    match &node.children {
+        SyntaxNodeChildren::NewRows(rows) if node.range().start == first.0 => rows
+            .values()
+            .nth(first.1)
+            .and_then(|row| find_row(row, rest)),
+        SyntaxNodeChildren::Children(children) => children
+            .iter()
+            .find(|child| child.range().contains(&first.0))
+            .and_then(|child| find_row(child, row_indices)),
+        _ => None,
+    }
+}
+
+fn node_operand_start(node: &SyntaxNode, offset: usize) -> usize {
+    let range = node.range();
+    let children = match &node.children {
+        SyntaxNodeChildren::Children(children) => children,
+        SyntaxNodeChildren::Leaf(leaf) if leaf.node_type == LeafNodeType::Operator => {
+            return offset
+        }
+        SyntaxNodeChildren::Leaf(_) | SyntaxNodeChildren::NewRows(_) => return range.start,
+    };
+
+    // The node continues after the position, so the operand is in one of the children
+    if range.end != offset {
+        return children
+            .iter()
+            .find(|child| child.range().start < offset && offset <= child.range().end)
+            .map(|child| node_operand_start(child, offset))
+            .unwrap_or(offset);
+    }
+
+    // A missing operator between two operands, like in 2y, glues them together
+    let is_juxtaposition = |index: usize| 0 < index && index + 1 < children.len();
+    if let Some(index) = children
+        .iter()
+        .position(|child| child.range().is_empty())
+        .filter(|index| is_juxtaposition(*index))
+    {
+        let left = &children[index - 1];
+        return node_operand_start(left, left.range().end);
+    }
+
+    // Prefix and infix operators split the node, while brackets and postfix operators are part of the operand
+    let (last, rest) = match children.split_last() {
+        Some(v) => v,
+        None => return range.start,
+    };
+    if rest.iter().any(is_operator) && !is_operator(last) {
+        node_operand_start(last, offset)
+    } else {
+        range.start
+    }
+}
+
+fn is_operator(node: &SyntaxNode) -> bool {
+    matches!(&node.children, SyntaxNodeChildren::Leaf(leaf) if leaf.node_type == LeafNodeType::Operator)
+}
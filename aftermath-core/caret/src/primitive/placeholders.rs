@@ -0,0 +1,86 @@
+use std::cmp::Ordering;
+
+use input_tree::{
+    focus::{MinimalInputRowPosition, MinimalInputRowRange},
+    node::InputNode,
+    row::{InputRow, Offset, RowIndex, RowIndices},
+};
+
+/// The places that still have to be filled in, in document order.
+/// Those are the empty rows of containers, like the denominator of a new fraction, and explicit placeholder symbols.
+pub fn placeholders(root: &InputRow) -> Vec<MinimalInputRowRange> {
+    let mut placeholders = vec![];
+    collect_placeholders(root, &mut RowIndices::default(), &mut placeholders);
+    placeholders
+}
+
+fn collect_placeholders(
+    row: &InputRow,
+    row_indices: &mut RowIndices,
+    placeholders: &mut Vec<MinimalInputRowRange>,
+) {
+    if row.is_empty() && !row_indices.is_empty() {
+        placeholders.push(MinimalInputRowRange {
+            row_indices: row_indices.clone(),
+            start: Offset(0),
+            end: Offset(0),
+        });
+    }
+    for (index, node) in row.values.iter().enumerate() {
+        match node {
+            InputNode::Container(_, grid) => {
+                for (row_index, child) in grid.values().enumerate() {
+                    row_indices.push(RowIndex(index, row_index));
+                    collect_placeholders(child, row_indices, placeholders);
+                    row_indices.pop();
+                }
+            }
+            InputNode::Symbol(_) if node.is_placeholder() => {
+                placeholders.push(MinimalInputRowRange {
+                    row_indices: row_indices.clone(),
+                    start: Offset(index),
+                    end: Offset(index + 1),
+                });
+            }
+            InputNode::Symbol(_) => {}
+        }
+    }
+}
+
+/// The first placeholder after the position, or the very first one if there is none.
+pub fn next_placeholder(
+    placeholders: &[MinimalInputRowRange],
+    position: &MinimalInputRowPosition,
+) -> Option<MinimalInputRowRange> {
+    placeholders
+        .iter()
+        .find(|v| cmp_positions(&v.row_indices, v.start, position) == Ordering::Greater)
+        .or_else(|| placeholders.first())
+        .cloned()
+}
+
+/// The last placeholder before the position, or the very last one if there is none.
+pub fn previous_placeholder(
+    placeholders: &[MinimalInputRowRange],
+    position: &MinimalInputRowPosition,
+) -> Option<MinimalInputRowRange> {
+    placeholders
+        .iter()
+        .rev()
+        .find(|v| cmp_positions(&v.row_indices, v.end, position) == Ordering::Less)
+        .or_else(|| placeholders.last())
+        .cloned()
+}
+
+pub(super) fn cmp_positions(
+    row_indices: &RowIndices,
+    offset: Offset,
+    position: &MinimalInputRowPosition,
+) -> Ordering {
+    RowIndices::cmp_indices_and_offset(
+        row_indices,
+        &offset,
+        &position.row_indices,
+        &position.offset,
+    )
+}
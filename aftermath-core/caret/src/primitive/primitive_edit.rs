@@ -128,6 +128,17 @@ fn remove_at_caret_position(
         }
     }
 
+    // Delete one bracket of a group, which removes both of them
+    // (a+b)| becomes a+b|
+    if let Some((index, InputNode::Container(InputNodeVariant::Fenced(_, _), grid))) = adjacent_node
+    {
+        let offset = match direction {
+            HorizontalDirection::Left => grid.values().map(|row| row.len()).sum(),
+            HorizontalDirection::Right => 0,
+        };
+        return flatten_node(caret.row_focus.child_at(index).unwrap(), Offset(offset));
+    }
+
     // Move into next/previous node
     if adjacent_node.is_some() {
         return move_caret(caret_mover, caret, direction);
@@ -157,6 +168,44 @@ fn remove_at_caret_position(
                 );
             }
         }
+        InputNode::Container(
+            InputNodeVariant::Under | InputNodeVariant::Over | InputNodeVariant::UnderOver,
+            grid,
+        ) => {
+            // Flatten under/over that we are in the middle of, the base comes first
+            let rows_before = match direction {
+                HorizontalDirection::Left if index_in_parent > 0 => Some(index_in_parent),
+                HorizontalDirection::Right if index_in_parent + 1 < grid.values().count() => {
+                    Some(index_in_parent + 1)
+                }
+                _ => None,
+            };
+            if let Some(rows_before) = rows_before {
+                let offset = grid.values().take(rows_before).map(|row| row.len()).sum();
+                return flatten_node(parent, Offset(offset));
+            }
+        }
+        InputNode::Container(InputNodeVariant::Fenced(_, _), _) => {
+            // Deleting a bracket from the inside
+            let offset = match direction {
+                HorizontalDirection::Left => 0,
+                HorizontalDirection::Right => caret.row_focus.len(),
+            };
+            return flatten_node(parent, Offset(offset));
+        }
+        InputNode::Container(InputNodeVariant::Multiscripts, grid) => {
+            // Once every script is empty, only the base is left
+            let mut rows = grid.values();
+            let base = rows.next().unwrap();
+            if rows.all(|row| row.is_empty()) {
+                let offset = if index_in_parent != 0 || direction == HorizontalDirection::Right {
+                    base.len()
+                } else {
+                    0
+                };
+                return flatten_node(parent, Offset(offset));
+            }
+        }
         _ => {}
     };
 
@@ -0,0 +1,38 @@
+use std::ops::Range;
+
+use input_tree::row::{RowIndex, RowIndices};
+use parser::syntax_tree::{SyntaxNode, SyntaxNodeChildren};
+
+use super::operand::find_row;
+
+/// The ranges of the tokens in a row, according to the syntax tree.
+pub fn token_ranges(syntax_tree: &SyntaxNode, row_indices: &RowIndices) -> Vec<Range<usize>> {
+    tokens(syntax_tree, row_indices)
+        .iter()
+        .map(|token| token.range())
+        .collect()
+}
+
+/// The leaf nodes of a row, according to the syntax tree.
+/// Used for finding out whether a position is in the middle of a token, like between the "lim" and the "inf" of "liminf".
+pub fn tokens<'a>(syntax_tree: &'a SyntaxNode, row_indices: &RowIndices) -> Vec<&'a SyntaxNode> {
+    let row_indices: Vec<RowIndex> = row_indices.iter().copied().collect();
+    let mut tokens = vec![];
+    if let Some(row) = find_row(syntax_tree, &row_indices) {
+        collect_tokens(row, &mut tokens);
+    }
+    tokens
+}
+
+fn collect_tokens<'a>(node: &'a SyntaxNode, tokens: &mut Vec<&'a SyntaxNode>) {
+    match &node.children {
+        SyntaxNodeChildren::Leaf(_) => tokens.push(node),
+        SyntaxNodeChildren::Children(children) => {
+            for child in children {
+                collect_tokens(child, tokens);
+            }
+        }
+        // Those are in a different row
+        SyntaxNodeChildren::NewRows(_) => {}
+    }
+}
@@ -0,0 +1,108 @@
+use input_tree::{
+    editing::{editable::Editable, BasicEdit},
+    focus::{MinimalInputRowPosition, MinimalInputRowRange},
+    row::{Offset, RowIndex},
+};
+use parser::autocomplete::TabStop;
+
+/// The tab stops of a snippet that was inserted into the document.
+/// Keeps the ranges up to date while the user is filling them in.
+pub struct ActiveSnippet {
+    tab_stops: Vec<(String, MinimalInputRowRange)>,
+    current_index: usize,
+}
+
+impl ActiveSnippet {
+    /// Takes the tab stops of a snippet that was inserted at the given position.
+    pub fn new(tab_stops: &[TabStop], position: &MinimalInputRowPosition) -> Option<Self> {
+        if tab_stops.is_empty() {
+            return None;
+        }
+        let tab_stops = tab_stops
+            .iter()
+            .map(|tab_stop| {
+                (
+                    tab_stop.name.clone(),
+                    to_absolute(&tab_stop.range, position),
+                )
+            })
+            .collect();
+        Some(Self {
+            tab_stops,
+            current_index: 0,
+        })
+    }
+
+    pub fn current(&self) -> &MinimalInputRowRange {
+        &self.tab_stops[self.current_index].1
+    }
+
+    /// Returns None after the last tab stop, which means that the snippet is done.
+    pub fn next_tab_stop(&mut self) -> Option<&MinimalInputRowRange> {
+        if self.current_index + 1 >= self.tab_stops.len() {
+            return None;
+        }
+        self.current_index += 1;
+        Some(self.current())
+    }
+
+    /// Stays on the first tab stop.
+    pub fn previous_tab_stop(&mut self) -> &MinimalInputRowRange {
+        self.current_index = self.current_index.saturating_sub(1);
+        self.current()
+    }
+
+    pub fn get(&self, index: usize) -> &MinimalInputRowRange {
+        &self.tab_stops[index].1
+    }
+
+    pub fn ranges(&self) -> impl Iterator<Item = &MinimalInputRowRange> {
+        self.tab_stops.iter().map(|(_, range)| range)
+    }
+
+    /// The other tab stops that have the same name.
+    pub fn linked(&self, index: usize) -> Vec<usize> {
+        let name = &self.tab_stops[index].0;
+        self.tab_stops
+            .iter()
+            .enumerate()
+            .filter(|(other_index, (other_name, _))| *other_index != index && other_name == name)
+            .map(|(other_index, _)| other_index)
+            .collect()
+    }
+}
+
+impl Editable for ActiveSnippet {
+    fn apply_edit(&mut self, edit: &BasicEdit) {
+        for (_, range) in self.tab_stops.iter_mut() {
+            range.apply_edit(edit);
+        }
+    }
+}
+
+/// Tab stops are relative to the inserted nodes, so they get moved to where the nodes ended up.
+fn to_absolute(
+    range: &MinimalInputRowRange,
+    position: &MinimalInputRowPosition,
+) -> MinimalInputRowRange {
+    let mut row_indices = range.row_indices.iter();
+    match row_indices.next() {
+        None => MinimalInputRowRange {
+            row_indices: position.row_indices.clone(),
+            start: Offset(position.offset.0 + range.start.0),
+            end: Offset(position.offset.0 + range.end.0),
+        },
+        Some(RowIndex(index, row_index)) => {
+            let mut absolute = position.row_indices.clone();
+            absolute.push(RowIndex(position.offset.0 + index, *row_index));
+            for row_index in row_indices {
+                absolute.push(*row_index);
+            }
+            MinimalInputRowRange {
+                row_indices: absolute,
+                start: range.start,
+                end: range.end,
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use input_tree::focus::{MinimalInputRowPosition, MinimalInputRowRange};
+use parser::rule_collections::definition_rules::{Definition, DefinitionRules, Reference};
+use serde::{Deserialize, Serialize};
+
+use crate::math_editor::MathEditor;
+
+/// Identifies an editor in a document. Editors are ordered by their ID,
+/// so an earlier editor wins when a name is defined twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct EditorId(pub usize);
+
+/// Collects the definitions of all editors in a document.
+/// The editors should share one parser, and the definition rules must be one of its modules.
+pub struct SymbolTable {
+    definition_rules: Rc<DefinitionRules>,
+    editors: BTreeMap<EditorId, EditorSymbols>,
+}
+
+struct EditorSymbols {
+    definition: Option<Definition>,
+    references: Vec<Reference>,
+}
+
+impl SymbolTable {
+    pub fn new(definition_rules: Rc<DefinitionRules>) -> Self {
+        Self {
+            definition_rules,
+            editors: BTreeMap::new(),
+        }
+    }
+
+    /// Has to be called whenever the content of an editor changes.
+    pub fn update(&mut self, editor_id: EditorId, editor: &mut MathEditor) {
+        let syntax_tree = editor.get_syntax_tree();
+        let symbols = EditorSymbols {
+            definition: self.definition_rules.definition(syntax_tree),
+            references: self.definition_rules.references(syntax_tree),
+        };
+        self.editors.insert(editor_id, symbols);
+    }
+
+    pub fn remove(&mut self, editor_id: EditorId) {
+        self.editors.remove(&editor_id);
+    }
+
+    /// Finds the definition of a name in the whole document.
+    pub fn resolve(&self, name: &str) -> Option<(EditorId, &Definition)> {
+        self.editors.iter().find_map(|(editor_id, symbols)| {
+            symbols
+                .definition
+                .as_ref()
+                .filter(|definition| definition.name == name)
+                .map(|definition| (*editor_id, definition))
+        })
+    }
+
+    /// The references in an editor that are neither defined in the document,
+    /// nor a parameter of the editor's own function definition.
+    pub fn undefined_names(&self, editor_id: EditorId) -> Vec<&Reference> {
+        let Some(symbols) = self.editors.get(&editor_id) else {
+            return vec![];
+        };
+        symbols
+            .references
+            .iter()
+            .filter(|reference| !symbols.is_parameter(&reference.name))
+            .filter(|reference| self.resolve(&reference.name).is_none())
+            .collect()
+    }
+
+    /// Finds the reference at the position, and returns where it is defined.
+    /// Parameters are local, so they do not have a definition in the symbol table.
+    pub fn go_to_definition(
+        &self,
+        editor_id: EditorId,
+        position: &MinimalInputRowPosition,
+    ) -> Option<(EditorId, MinimalInputRowRange)> {
+        let symbols = self.editors.get(&editor_id)?;
+        let reference = symbols.references.iter().find(|reference| {
+            reference.range.row_indices == position.row_indices
+                && reference.range.start <= position.offset
+                && position.offset <= reference.range.end
+        })?;
+        if symbols.is_parameter(&reference.name) {
+            return None;
+        }
+        self.resolve(&reference.name)
+            .map(|(editor_id, definition)| (editor_id, definition.name_range.clone()))
+    }
+}
+
+impl EditorSymbols {
+    fn is_parameter(&self, name: &str) -> bool {
+        self.definition
+            .as_ref()
+            .and_then(|definition| definition.parameters.as_ref())
+            .map(|parameters| parameters.iter().any(|v| v == name))
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use std::rc::Rc;
+
+use caret::{math_editor::MathEditor, primitive::MoveMode};
+use common::{click, create_editor_with_parser, create_parser, type_symbols};
+use input_tree::{
+    direction::{Direction, VerticalDirection},
+    input_row,
+};
+use parser::{
+    locale::Locale,
+    rule_collections::{
+        arithmetic_rules::ArithmeticRules, calculus_rules::CalculusRules,
+        comparison_rules::ComparisonRules, core_rules::CoreRules, logic_rules::LogicRules,
+    },
+};
+
+fn create_editor() -> MathEditor {
+    let parser = create_parser(|modules, built_in| {
+        vec![
+            Rc::new(CoreRules::new(modules, built_in)),
+            Rc::new(ArithmeticRules::new(modules, built_in, &Locale::default())),
+            Rc::new(ComparisonRules::new(modules)),
+            Rc::new(CalculusRules::new(modules, built_in)),
+            Rc::new(LogicRules::new(modules, built_in)),
+        ]
+    });
+    create_editor_with_parser(&parser, &[])
+}
+
+#[test]
+fn autocorrect_when_leaving_token() {
+    let mut editor = create_editor();
+    type_symbols(&mut editor, &["a", "<", "="]);
+    click(&mut editor, 0);
+
+    assert_eq!(editor.get_input_tree(), &input_row! {(row "a", "≤")});
+}
+
+#[test]
+fn no_autocorrect_while_editing_token() {
+    let mut editor = create_editor();
+    type_symbols(&mut editor, &["a", "<", "="]);
+    editor.move_caret(Direction::Left, MoveMode::Char);
+
+    assert_eq!(editor.get_input_tree(), &input_row! {(row "a", "<", "=")});
+}
+
+#[test]
+fn no_autocorrect_while_longer_rule_matches() {
+    let mut editor = create_editor();
+    type_symbols(&mut editor, &["u"]);
+    click(&mut editor, 0);
+    type_symbols(&mut editor, &["p", "r", "o", "d"]);
+    // "produ" could still become "product"
+    editor.move_caret(Direction::Right, MoveMode::Char);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "p", "r", "o", "d", "u")}
+    );
+}
+
+#[test]
+fn autocorrect_when_no_longer_rule_matches() {
+    let mut editor = create_editor();
+    type_symbols(&mut editor, &["x"]);
+    click(&mut editor, 0);
+    type_symbols(&mut editor, &["p", "r", "o", "d"]);
+    editor.move_caret(Direction::Right, MoveMode::Char);
+
+    assert_eq!(editor.get_input_tree(), &input_row! {(row "∏", "x")});
+}
+
+#[test]
+fn no_autocomplete_inside_token() {
+    let mut editor = create_editor();
+    type_symbols(&mut editor, &["l", "i", "m", "i", "n", "f"]);
+
+    let autocomplete = editor.get_autocomplete().unwrap();
+    let (_, matches, _) = autocomplete.destructure();
+    // "liminf" still matches, but the "inf" at the end of the "liminf" token doesn't
+    assert!(matches.iter().any(|v| v.rule.parser == "liminf"));
+    assert!(matches.iter().all(|v| v.rule.parser != "infinity"));
+}
+
+#[test]
+fn autocomplete_after_variable() {
+    let mut editor = create_editor();
+    type_symbols(&mut editor, &["x", "s", "q", "r", "t"]);
+
+    let autocomplete = editor.get_autocomplete().unwrap();
+    assert_eq!(autocomplete.get_selected().rule.parser, "sqrt");
+}
+
+#[test]
+fn autocomplete_after_number_and_variable() {
+    let mut editor = create_editor();
+    type_symbols(&mut editor, &["2", "x", "s", "q", "r", "t"]);
+
+    let autocomplete = editor.get_autocomplete().unwrap();
+    assert_eq!(autocomplete.get_selected().rule.parser, "sqrt");
+}
+
+#[test]
+fn autocomplete_after_variable_in_sum() {
+    let mut editor = create_editor();
+    type_symbols(&mut editor, &["a", "+", "b", "s", "u", "m"]);
+
+    let autocomplete = editor.get_autocomplete().unwrap();
+    assert_eq!(autocomplete.get_selected().rule.parser, "sum");
+}
+
+#[test]
+fn disabled_autocorrect() {
+    let mut editor = create_editor();
+    editor.set_autocorrect_enabled("<=", false);
+    type_symbols(&mut editor, &["a", "<", "="]);
+    assert!(editor.get_autocomplete().is_some());
+    click(&mut editor, 0);
+
+    assert_eq!(editor.get_input_tree(), &input_row! {(row "a", "<", "=")});
+
+    editor.set_autocorrect_enabled("<=", true);
+    click(&mut editor, 3);
+    click(&mut editor, 0);
+    assert_eq!(editor.get_input_tree(), &input_row! {(row "a", "≤")});
+}
+
+#[test]
+fn fuzzy_autocomplete() {
+    let mut editor = create_editor();
+    type_symbols(&mut editor, &["i", "n", "t", "g"]);
+
+    let autocomplete = editor.get_autocomplete().unwrap();
+    let selected = autocomplete.get_selected();
+    assert_eq!(selected.rule.parser, "integral");
+    assert_eq!(selected.rule.category, "Calculus");
+    assert!(selected.rule.description.is_some());
+    // Fuzzy matches are only suggestions
+    click(&mut editor, 0);
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "i", "n", "t", "g")}
+    );
+}
+
+#[test]
+fn recently_used_autocomplete_comes_first() {
+    let mut editor = create_editor();
+    type_symbols(&mut editor, &["l", "i"]);
+    let autocomplete = editor.get_autocomplete().unwrap();
+    assert_eq!(autocomplete.get_selected().rule.parser, "lim");
+
+    while editor
+        .get_autocomplete()
+        .unwrap()
+        .get_selected()
+        .rule
+        .parser
+        != "liminf"
+    {
+        editor.move_in_autocomplete(VerticalDirection::Down);
+    }
+    editor.finish_autocomplete(true);
+    type_symbols(&mut editor, &["+", "l", "i"]);
+
+    let autocomplete = editor.get_autocomplete().unwrap();
+    assert_eq!(autocomplete.get_selected().rule.parser, "liminf");
+}
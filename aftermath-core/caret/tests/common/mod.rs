@@ -0,0 +1,115 @@
+// Every test file only uses some of the helpers
+#![allow(dead_code)]
+
+use std::rc::Rc;
+
+use caret::{caret::MinimalCaretSelection, math_editor::MathEditor, primitive::MoveMode};
+use input_tree::{
+    focus::{MinimalInputRowPosition, MinimalInputRowRange},
+    row::{Offset, RowIndex, RowIndices},
+};
+use parser::{
+    locale::Locale,
+    parse_module::ParseModule,
+    parse_modules::{ParseModuleCollection, ParseModules},
+    parser::MathParser,
+    rule_collections::{
+        arithmetic_rules::ArithmeticRules, built_in_rules::BuiltInRules, core_rules::CoreRules,
+    },
+};
+
+/// A parser with the built-in rules and the given modules.
+pub fn create_parser(
+    make_modules: impl FnOnce(&mut ParseModules, &Rc<BuiltInRules>) -> Vec<Rc<dyn ParseModule>>,
+) -> Rc<MathParser> {
+    let mut modules = ParseModules::new();
+    let built_in = Rc::new(BuiltInRules::new(&mut modules));
+    let mut parse_modules: Vec<Rc<dyn ParseModule>> = vec![built_in.clone()];
+    parse_modules.extend(make_modules(&mut modules, &built_in));
+    Rc::new(MathParser::new(ParseModuleCollection::new(
+        built_in,
+        parse_modules,
+    )))
+}
+
+/// An editor with the core and arithmetic rules, where the symbols have already been typed in.
+pub fn create_editor(symbols: &[&str]) -> MathEditor {
+    let parser = create_parser(|modules, built_in| {
+        vec![
+            Rc::new(CoreRules::new(modules, built_in)),
+            Rc::new(ArithmeticRules::new(modules, built_in, &Locale::default())),
+        ]
+    });
+    create_editor_with_parser(&parser, symbols)
+}
+
+pub fn create_editor_with_parser(parser: &Rc<MathParser>, symbols: &[&str]) -> MathEditor {
+    let mut editor = MathEditor::new(parser.clone());
+    if !symbols.is_empty() {
+        editor.insert_at_caret(symbols.iter().map(|v| v.to_string()).collect());
+    }
+    editor
+}
+
+/// Types the symbols one by one, like a user would.
+pub fn type_symbols(editor: &mut MathEditor, symbols: &[&str]) {
+    for symbol in symbols {
+        editor.insert_at_caret(vec![symbol.to_string()]);
+    }
+}
+
+pub fn row_indices(row_indices: &[(usize, usize)]) -> RowIndices {
+    RowIndices::new(
+        row_indices
+            .iter()
+            .map(|(index, row)| RowIndex(*index, *row))
+            .collect(),
+    )
+}
+
+pub fn position(row_indices: &[(usize, usize)], offset: usize) -> MinimalInputRowPosition {
+    MinimalInputRowPosition {
+        row_indices: self::row_indices(row_indices),
+        offset: Offset(offset),
+    }
+}
+
+pub fn root_position(offset: usize) -> MinimalInputRowPosition {
+    position(&[], offset)
+}
+
+pub fn range(row_indices: &[(usize, usize)], start: usize, end: usize) -> MinimalInputRowRange {
+    MinimalInputRowRange {
+        row_indices: self::row_indices(row_indices),
+        start: Offset(start),
+        end: Offset(end),
+    }
+}
+
+pub fn root_range(start: usize, end: usize) -> MinimalInputRowRange {
+    range(&[], start, end)
+}
+
+/// A caret selection in the root row.
+pub fn row_range(start: usize, end: usize) -> MinimalCaretSelection {
+    MinimalCaretSelection::Row(root_range(start, end))
+}
+
+/// The range of the only caret.
+pub fn caret_range(editor: &MathEditor) -> MinimalInputRowRange {
+    match &editor.get_caret()[..] {
+        [MinimalCaretSelection::Row(range)] => range.clone(),
+        _ => panic!("Expected a row selection"),
+    }
+}
+
+pub fn select(editor: &mut MathEditor, start: usize, end: usize) {
+    editor.start_selection(root_position(start), MoveMode::Char);
+    editor.extend_selection(root_position(end));
+    editor.finish_selection();
+}
+
+pub fn click(editor: &mut MathEditor, offset: usize) {
+    editor.start_selection(root_position(offset), MoveMode::Char);
+    editor.finish_selection();
+}
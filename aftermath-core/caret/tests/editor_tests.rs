@@ -0,0 +1,425 @@
+mod common;
+
+use std::rc::Rc;
+
+use caret::{
+    math_editor::MathEditor,
+    primitive::{placeholders, primitive_edit::CaretRemoveMode, MoveMode},
+};
+use common::{
+    caret_range, create_editor, create_editor_with_parser, create_parser, range, root_position,
+    select, type_symbols,
+};
+use input_tree::{
+    direction::Direction,
+    input_nodes, input_row,
+    row::{Offset, RowIndex, RowIndices},
+};
+use parser::{
+    autocomplete::{AutocompleteRule, TabStop},
+    parse_module::{ParseModule, ParseRule},
+    rule_collections::{calculus_rules::CalculusRules, core_rules::CoreRules},
+};
+
+/// Only has snippets where every tab stop is linked
+struct TwiceRules {
+    autocomplete_rules: Vec<AutocompleteRule>,
+}
+
+impl ParseModule for TwiceRules {
+    fn get_module_name(&self) -> &str {
+        "Twice"
+    }
+
+    fn get_rules(&self) -> &[ParseRule] {
+        &[]
+    }
+
+    fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
+        &self.autocomplete_rules
+    }
+}
+
+fn create_snippet_editor(symbols: &[&str]) -> MathEditor {
+    let parser = create_parser(|modules, built_in| {
+        let core = Rc::new(CoreRules::new(modules, built_in));
+        let calculus = Rc::new(CalculusRules::new(modules, built_in));
+        let twice = Rc::new(TwiceRules {
+            autocomplete_rules: vec![
+                AutocompleteRule::new("twice", input_nodes! {"⬚", "·", (sub (row))})
+                    .with_tab_stops(vec![
+                        TabStop::new("x", RowIndices::default(), Offset(0), Offset(1)),
+                        TabStop::new(
+                            "x",
+                            RowIndices::new(vec![RowIndex(2, 0)]),
+                            Offset(0),
+                            Offset(0),
+                        ),
+                    ]),
+                AutocompleteRule::new("thrice", input_nodes! {"⬚", (sub (row)), (sup (row))})
+                    .with_tab_stops(vec![
+                        TabStop::new("x", RowIndices::default(), Offset(0), Offset(1)),
+                        TabStop::new(
+                            "x",
+                            RowIndices::new(vec![RowIndex(1, 0)]),
+                            Offset(0),
+                            Offset(0),
+                        ),
+                        TabStop::new(
+                            "x",
+                            RowIndices::new(vec![RowIndex(2, 0)]),
+                            Offset(0),
+                            Offset(0),
+                        ),
+                    ]),
+            ],
+        });
+        vec![core, calculus, twice]
+    });
+    create_editor_with_parser(&parser, symbols)
+}
+
+#[test]
+fn opening_bracket_wraps_selection() {
+    let mut editor = create_editor(&["2", "a", "+", "b"]);
+    select(&mut editor, 1, 4);
+    editor.insert_at_caret(vec!["(".to_string()]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "2", (fenced "(" ")" (row "a", "+", "b")))}
+    );
+}
+
+#[test]
+fn opening_bracket_without_selection() {
+    let mut editor = create_editor(&["a"]);
+    editor.insert_at_caret(vec!["(".to_string()]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "a", (fenced "(" ")" (row)))}
+    );
+}
+
+#[test]
+fn closing_bracket_leaves_fence() {
+    let mut editor = create_editor(&["f"]);
+    for value in ["(", "x", ")", "+", "1"] {
+        editor.insert_at_caret(vec![value.to_string()]);
+    }
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "f", (fenced "(" ")" (row "x")), "+", "1")}
+    );
+}
+
+#[test]
+fn delete_closing_bracket() {
+    let mut editor = create_editor(&["2", "a", "+", "b"]);
+    select(&mut editor, 1, 4);
+    editor.insert_at_caret(vec!["(".to_string()]);
+    editor.remove_at_caret(CaretRemoveMode::Left, MoveMode::Char);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "2", "a", "+", "b")}
+    );
+}
+
+#[test]
+fn placeholders_in_document_order() {
+    let input = input_row! {
+        (row "a", (frac (row), (row "x", "⬚")), (sup (row)))
+    };
+
+    assert_eq!(
+        placeholders(&input),
+        vec![
+            range(&[(1, 0)], 0, 0),
+            range(&[(1, 1)], 1, 2),
+            range(&[(2, 0)], 0, 0)
+        ]
+    );
+}
+
+#[test]
+fn splice_moves_caret_along() {
+    let mut editor = create_editor(&["a", "b", "c"]);
+    editor.splice_at_range(range(&[], 0, 3), input_nodes! {"x"});
+    assert_eq!(caret_range(&editor), range(&[], 0, 0));
+
+    editor.insert_at_caret(vec!["y".to_string()]);
+    assert_eq!(editor.get_input_tree(), &input_row! {(row "y", "x")});
+}
+
+#[test]
+fn tab_through_placeholders() {
+    let mut editor = create_editor(&[]);
+    editor.splice_at_range(
+        range(&[], 0, 0),
+        input_nodes! {(frac (row), (row "⬚")), "+", (sup (row))},
+    );
+    editor.start_selection(root_position(0), MoveMode::Char);
+    editor.finish_selection();
+
+    editor.move_to_next_placeholder().unwrap();
+    assert_eq!(caret_range(&editor), range(&[(0, 0)], 0, 0));
+    editor.move_to_next_placeholder().unwrap();
+    assert_eq!(caret_range(&editor), range(&[(0, 1)], 0, 1));
+    editor.move_to_next_placeholder().unwrap();
+    assert_eq!(caret_range(&editor), range(&[(2, 0)], 0, 0));
+    // Wraps around
+    editor.move_to_next_placeholder().unwrap();
+    assert_eq!(caret_range(&editor), range(&[(0, 0)], 0, 0));
+    editor.move_to_previous_placeholder().unwrap();
+    assert_eq!(caret_range(&editor), range(&[(2, 0)], 0, 0));
+
+    // Typing replaces a selected placeholder symbol
+    editor.move_to_previous_placeholder().unwrap();
+    editor.insert_at_caret(vec!["2".to_string()]);
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row (frac (row), (row "2")), "+", (sup (row)))}
+    );
+}
+
+#[test]
+fn no_placeholders() {
+    let mut editor = create_editor(&["a", "+", "b"]);
+    assert!(editor.move_to_next_placeholder().is_none());
+}
+
+#[test]
+fn integral_snippet() {
+    let mut editor = create_snippet_editor(&["i", "n", "t", "e", "g", "r", "a", "l"]);
+    editor.finish_autocomplete(true).unwrap();
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "∫", (sub (row)), (sup (row)), "⬚", "d", "⬚")}
+    );
+
+    // Lower bound, upper bound, integrand and then the variable
+    assert_eq!(caret_range(&editor), range(&[(1, 0)], 0, 0));
+    editor.insert_at_caret(vec!["0".to_string()]);
+    editor.move_to_next_placeholder().unwrap();
+    assert_eq!(caret_range(&editor), range(&[(2, 0)], 0, 0));
+    editor.insert_at_caret(vec!["1".to_string()]);
+    editor.move_to_next_placeholder().unwrap();
+    assert_eq!(caret_range(&editor), range(&[], 3, 4));
+    editor.insert_at_caret(vec!["x".to_string(), "+".to_string(), "1".to_string()]);
+    editor.move_to_next_placeholder().unwrap();
+    assert_eq!(caret_range(&editor), range(&[], 7, 8));
+    editor.move_to_previous_placeholder().unwrap();
+    assert_eq!(caret_range(&editor), range(&[], 3, 6));
+    editor.move_to_next_placeholder().unwrap();
+    editor.insert_at_caret(vec!["x".to_string()]);
+
+    // Past the last tab stop, there are no placeholders left
+    assert!(editor.move_to_next_placeholder().is_none());
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "∫", (sub (row "0")), (sup (row "1")), "x", "+", "1", "d", "x")}
+    );
+}
+
+#[test]
+fn linked_tab_stops_are_mirrored() {
+    let mut editor = create_snippet_editor(&["t", "w", "i", "c", "e"]);
+    editor.finish_autocomplete(true).unwrap();
+    assert_eq!(caret_range(&editor), range(&[], 0, 1));
+
+    editor.insert_at_caret(vec!["a".to_string()]);
+    editor.insert_at_caret(vec!["b".to_string()]);
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "a", "b", "·", (sub (row "a", "b")))}
+    );
+
+    editor.move_to_next_placeholder().unwrap();
+    assert_eq!(caret_range(&editor), range(&[(3, 0)], 0, 2));
+    editor.remove_at_caret(CaretRemoveMode::Left, MoveMode::Char);
+    editor.insert_at_caret(vec!["c".to_string()]);
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "c", "·", (sub (row "c")))}
+    );
+}
+
+#[test]
+fn mirrored_tab_stops_are_one_undo_step() {
+    let mut editor = create_snippet_editor(&["t", "h", "r", "i", "c", "e"]);
+    editor.finish_autocomplete(true).unwrap();
+    editor.insert_at_caret(vec!["a".to_string()]);
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "a", (sub (row "a")), (sup (row "a")))}
+    );
+
+    editor.undo().unwrap();
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "a", (sub (row)), (sup (row)))}
+    );
+}
+
+#[test]
+fn leaving_a_snippet_ends_it() {
+    let mut editor = create_snippet_editor(&["t", "w", "i", "c", "e"]);
+    editor.finish_autocomplete(true).unwrap();
+    editor.insert_at_caret(vec!["a".to_string()]);
+
+    // Moving past the subscript leaves the snippet, so tab no longer jumps back into it
+    select(&mut editor, 3, 3);
+    assert!(editor.move_to_next_placeholder().is_none());
+    assert!(editor.move_to_previous_placeholder().is_none());
+
+    // And typing is not mirrored any more
+    editor.insert_at_caret(vec!["b".to_string()]);
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "a", "·", (sub (row "a")), "b")}
+    );
+}
+
+#[test]
+fn slash_pulls_operand_into_fraction() {
+    let mut editor = create_editor(&["x", "+", "2", "y"]);
+    type_symbols(&mut editor, &["/"]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "x", "+", (frac (row "2", "y"), (row)))}
+    );
+    assert_eq!(caret_range(&editor), range(&[(2, 1)], 0, 0));
+}
+
+#[test]
+fn slash_pulls_bracket_group_into_fraction() {
+    let mut editor = create_editor(&["(", "a", "+", "b", ")"]);
+    type_symbols(&mut editor, &["/", "2"]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row (frac (row "(", "a", "+", "b", ")"), (row "2")))}
+    );
+}
+
+#[test]
+fn slash_after_operator() {
+    let mut editor = create_editor(&["x", "+"]);
+    type_symbols(&mut editor, &["/"]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "x", "+", (frac (row), (row)))}
+    );
+    assert_eq!(caret_range(&editor), range(&[(2, 1)], 0, 0));
+}
+
+#[test]
+fn slash_in_nested_row() {
+    let mut editor = create_editor(&["e"]);
+    type_symbols(&mut editor, &["^", "x", "+", "1", "/"]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "e", (sup (row "x", "+", (frac (row "1"), (row)))))}
+    );
+    assert_eq!(caret_range(&editor), range(&[(1, 0), (2, 1)], 0, 0));
+}
+
+#[test]
+fn slash_wraps_selection() {
+    let mut editor = create_editor(&["a", "+", "b", "+", "c"]);
+    select(&mut editor, 2, 5);
+    type_symbols(&mut editor, &["/"]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "a", "+", (frac (row "b", "+", "c"), (row)))}
+    );
+    assert_eq!(caret_range(&editor), range(&[(2, 1)], 0, 0));
+}
+
+#[test]
+fn slash_twice_types_literal_slash() {
+    let mut editor = create_editor(&["x", "+", "2", "y"]);
+    type_symbols(&mut editor, &["/", "/", "3"]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "x", "+", "2", "y", "/", "3")}
+    );
+    assert_eq!(caret_range(&editor), range(&[], 6, 6));
+
+    // The literal slash is parsed as a division
+    assert!(editor.get_diagnostics().is_empty());
+}
+
+#[test]
+fn sup_and_sub_wrap_selection() {
+    let mut editor = create_editor(&["x", "n", "+", "1"]);
+    select(&mut editor, 1, 4);
+    type_symbols(&mut editor, &["^"]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "x", (sup (row "n", "+", "1")))}
+    );
+    assert_eq!(caret_range(&editor), range(&[(1, 0)], 3, 3));
+
+    select(&mut editor, 0, 1);
+    type_symbols(&mut editor, &["_"]);
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row (sub (row "x")), (sup (row "n", "+", "1")))}
+    );
+}
+
+#[test]
+fn sup_and_sub_are_created_immediately() {
+    let mut editor = create_editor(&["x"]);
+    type_symbols(&mut editor, &["^"]);
+    assert_eq!(caret_range(&editor), range(&[(1, 0)], 0, 0));
+    type_symbols(&mut editor, &["2"]);
+    editor.move_caret(Direction::Right, MoveMode::Char);
+    type_symbols(&mut editor, &["_", "i"]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "x", (sup (row "2")), (sub (row "i")))}
+    );
+}
+
+#[test]
+fn sqrt_wraps_selection() {
+    let mut editor = create_editor(&["a", "+", "b"]);
+    select(&mut editor, 0, 3);
+    type_symbols(&mut editor, &["s", "q", "r", "t"]);
+    editor.finish_autocomplete(true).unwrap();
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row (root (row), (row "a", "+", "b")))}
+    );
+}
+
+#[test]
+fn sqrt_forgets_selection_after_leaving() {
+    let mut editor = create_editor(&["a", "+", "b"]);
+    select(&mut editor, 1, 3);
+    type_symbols(&mut editor, &["s"]);
+    editor.move_caret(Direction::Left, MoveMode::Char);
+    editor.move_caret(Direction::Left, MoveMode::Char);
+    editor.move_caret(Direction::Right, MoveMode::Char);
+    editor.move_caret(Direction::Right, MoveMode::Char);
+    type_symbols(&mut editor, &["q", "r", "t"]);
+    editor.finish_autocomplete(true).unwrap();
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "a", (root (row), (row)))}
+    );
+}
@@ -0,0 +1,189 @@
+mod common;
+
+use caret::primitive::{
+    primitive_edit::{remove_at_caret, CaretRemoveMode},
+    MoveMode, NavigationSettings,
+};
+use common::position;
+use input_tree::{
+    direction::Direction,
+    editing::editable::Editable,
+    focus::{InputRowPosition, MinimalInputRowPosition},
+    input_row,
+    input_tree::InputTree,
+};
+
+fn move_caret(
+    input: &InputTree,
+    start: MinimalInputRowPosition,
+    direction: Direction,
+) -> Option<MinimalInputRowPosition> {
+    let start = InputRowPosition::from_minimal(input.root_focus(), &start);
+    NavigationSettings::new()
+        .move_caret_range((&start).into(), direction, MoveMode::Char)
+        .map(|v| v.to_minimal())
+}
+
+#[test]
+fn move_vertically_in_over() {
+    // The base is stored first, but the annotation is on top
+    let input = InputTree::new(input_row! {
+        (row "a", (over (row "x", "y"), (row "^")))
+    });
+
+    assert_eq!(
+        move_caret(&input, position(&[(1, 0)], 1), Direction::Up),
+        Some(position(&[(1, 1)], 1))
+    );
+    assert_eq!(
+        move_caret(&input, position(&[(1, 1)], 0), Direction::Down),
+        Some(position(&[(1, 0)], 0))
+    );
+    // Leaving the annotation
+    assert_eq!(
+        move_caret(&input, position(&[(1, 1)], 0), Direction::Up),
+        Some(position(&[], 2))
+    );
+}
+
+#[test]
+fn move_vertically_in_under_over() {
+    let input = InputTree::new(input_row! {
+        (row (underover (row "→"), (row "g"), (row "f")))
+    });
+
+    assert_eq!(
+        move_caret(&input, position(&[(0, 0)], 0), Direction::Down),
+        Some(position(&[(0, 1)], 0))
+    );
+    assert_eq!(
+        move_caret(&input, position(&[(0, 0)], 0), Direction::Up),
+        Some(position(&[(0, 2)], 1))
+    );
+    assert_eq!(
+        move_caret(&input, position(&[(0, 1)], 1), Direction::Down),
+        Some(position(&[], 1))
+    );
+}
+
+#[test]
+fn delete_at_start_of_annotation() {
+    let mut input = InputTree::new(input_row! {
+        (row "a", (under (row "l", "i", "m"), (row "x")))
+    });
+    let caret = InputRowPosition::from_minimal(input.root_focus(), &position(&[(1, 1)], 0));
+    let (edits, new_position) = remove_at_caret(
+        &NavigationSettings::new(),
+        &(&caret).into(),
+        CaretRemoveMode::Left,
+    )
+    .unwrap();
+    for edit in edits.iter() {
+        input.apply_edit(edit);
+    }
+
+    assert_eq!(input.root, input_row! {(row "a", "l", "i", "m", "x")});
+    assert_eq!(new_position, position(&[], 4));
+}
+
+#[test]
+fn move_from_sup_to_sub() {
+    let input = InputTree::new(input_row! {
+        (row "∑", (sup (row "n")), (sub (row "i")))
+    });
+
+    assert_eq!(
+        move_caret(&input, position(&[(1, 0)], 1), Direction::Down),
+        Some(position(&[(2, 0)], 0))
+    );
+    assert_eq!(
+        move_caret(&input, position(&[(2, 0)], 0), Direction::Up),
+        Some(position(&[(1, 0)], 1))
+    );
+}
+
+#[test]
+fn move_vertically_in_multiscripts() {
+    // ¹⁴₆C
+    let input = InputTree::new(input_row! {
+        (row (multiscripts (row "C"), (row), (row), (row "6"), (row "1", "4")))
+    });
+
+    assert_eq!(
+        move_caret(&input, position(&[(0, 3)], 0), Direction::Up),
+        Some(position(&[(0, 4)], 2))
+    );
+    assert_eq!(
+        move_caret(&input, position(&[(0, 0)], 0), Direction::Down),
+        Some(position(&[(0, 1)], 0))
+    );
+    assert_eq!(
+        move_caret(&input, position(&[(0, 4)], 0), Direction::Up),
+        Some(position(&[], 1))
+    );
+}
+
+#[test]
+fn move_horizontally_through_multiscripts() {
+    let input = InputTree::new(input_row! {
+        (row (multiscripts (row "C"), (row), (row), (row "6"), (row "1", "4")))
+    });
+
+    // Entering from the left starts with the prescripts
+    assert_eq!(
+        move_caret(&input, position(&[], 0), Direction::Right),
+        Some(position(&[(0, 3)], 0))
+    );
+    assert_eq!(
+        move_caret(&input, position(&[(0, 4)], 2), Direction::Right),
+        Some(position(&[(0, 0)], 0))
+    );
+    assert_eq!(
+        move_caret(&input, position(&[(0, 2)], 0), Direction::Right),
+        Some(position(&[], 1))
+    );
+    assert_eq!(
+        move_caret(&input, position(&[], 1), Direction::Left),
+        Some(position(&[(0, 2)], 0))
+    );
+}
+
+#[test]
+fn delete_empty_multiscripts() {
+    let mut input = InputTree::new(input_row! {
+        (row "a", (multiscripts (row "Γ"), (row), (row), (row), (row)))
+    });
+    let caret = InputRowPosition::from_minimal(input.root_focus(), &position(&[(1, 2)], 0));
+    let (edits, new_position) = remove_at_caret(
+        &NavigationSettings::new(),
+        &(&caret).into(),
+        CaretRemoveMode::Left,
+    )
+    .unwrap();
+    for edit in edits.iter() {
+        input.apply_edit(edit);
+    }
+
+    assert_eq!(input.root, input_row! {(row "a", "Γ")});
+    assert_eq!(new_position, position(&[], 2));
+}
+
+#[test]
+fn delete_opening_bracket_from_inside() {
+    let mut input = InputTree::new(input_row! {
+        (row "2", (fenced "(" ")" (row "a", "+", "b")))
+    });
+    let caret = InputRowPosition::from_minimal(input.root_focus(), &position(&[(1, 0)], 0));
+    let (edits, new_position) = remove_at_caret(
+        &NavigationSettings::new(),
+        &(&caret).into(),
+        CaretRemoveMode::Left,
+    )
+    .unwrap();
+    for edit in edits.iter() {
+        input.apply_edit(edit);
+    }
+
+    assert_eq!(input.root, input_row! {(row "2", "a", "+", "b")});
+    assert_eq!(new_position, position(&[], 1));
+}
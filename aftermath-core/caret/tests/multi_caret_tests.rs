@@ -0,0 +1,162 @@
+mod common;
+
+use caret::{
+    caret::MinimalCaretSelection,
+    primitive::{primitive_edit::CaretRemoveMode, MoveMode},
+};
+use common::{create_editor, position, range, root_position, root_range, row_range};
+use input_tree::{direction::Direction, input_nodes, input_row};
+
+#[test]
+fn insert_at_all_carets() {
+    let mut editor = create_editor(&["a", "+", "b"]);
+    editor.start_selection(root_position(0), MoveMode::Char);
+    editor.add_caret(root_position(3));
+    editor.insert_at_caret(vec!["x".to_string()]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "x", "a", "+", "b", "x")}
+    );
+    assert_eq!(editor.get_caret(), vec![row_range(5, 5), row_range(1, 1)]);
+}
+
+#[test]
+fn undo_edits_at_all_carets() {
+    let mut editor = create_editor(&["a", "+", "b"]);
+    editor.start_selection(root_position(1), MoveMode::Char);
+    editor.add_caret(root_position(3));
+    editor.insert_at_caret(vec!["x".to_string()]);
+    editor.undo();
+
+    assert_eq!(editor.get_input_tree(), &input_row! {(row "a", "+", "b")});
+    assert_eq!(editor.get_caret(), vec![row_range(3, 3), row_range(1, 1)]);
+}
+
+#[test]
+fn remove_at_all_carets() {
+    let mut editor = create_editor(&["a", "b", "+", "c", "d"]);
+    editor.start_selection(root_position(2), MoveMode::Char);
+    editor.add_caret(root_position(5));
+    editor.remove_at_caret(CaretRemoveMode::Left, MoveMode::Char);
+
+    assert_eq!(editor.get_input_tree(), &input_row! {(row "a", "+", "c")});
+    assert_eq!(editor.get_caret(), vec![row_range(3, 3), row_range(1, 1)]);
+}
+
+#[test]
+fn overlapping_carets_are_merged() {
+    let mut editor = create_editor(&["a", "b"]);
+    editor.start_selection(root_position(1), MoveMode::Char);
+    editor.add_caret(root_position(2));
+    editor.remove_at_caret(CaretRemoveMode::Left, MoveMode::Char);
+
+    assert_eq!(editor.get_input_tree(), &input_row! {(row)});
+    assert_eq!(editor.get_caret(), vec![row_range(0, 0)]);
+}
+
+#[test]
+fn adding_the_same_caret_twice() {
+    let mut editor = create_editor(&["a", "b"]);
+    editor.add_caret(root_position(2));
+    editor.add_caret(root_position(0));
+    editor.add_caret(root_position(0));
+
+    assert_eq!(editor.get_caret(), vec![row_range(0, 0), row_range(2, 2)]);
+}
+
+#[test]
+fn move_all_carets() {
+    let mut editor = create_editor(&["a", "+", "b"]);
+    editor.start_selection(root_position(0), MoveMode::Char);
+    editor.add_caret(root_position(2));
+    editor.move_caret(Direction::Right, MoveMode::Char);
+
+    assert_eq!(editor.get_caret(), vec![row_range(3, 3), row_range(1, 1)]);
+}
+
+#[test]
+fn clicking_removes_other_carets() {
+    let mut editor = create_editor(&["a", "+", "b"]);
+    editor.add_caret(root_position(0));
+    editor.start_selection(root_position(1), MoveMode::Char);
+    editor.finish_selection();
+
+    assert_eq!(editor.get_caret(), vec![row_range(1, 1)]);
+}
+
+#[test]
+fn select_next_occurrence() {
+    let mut editor = create_editor(&["x", "+", "y", "+", "x"]);
+    editor.start_selection(root_position(1), MoveMode::Char);
+
+    // First the token at the caret gets selected
+    editor.select_next_occurrence();
+    assert_eq!(editor.get_caret(), vec![row_range(0, 1)]);
+
+    editor.select_next_occurrence();
+    assert_eq!(editor.get_caret(), vec![row_range(4, 5), row_range(0, 1)]);
+
+    // Every occurrence already has a caret
+    editor.select_next_occurrence();
+    assert_eq!(editor.get_caret(), vec![row_range(4, 5), row_range(0, 1)]);
+
+    editor.insert_at_caret(vec!["z".to_string()]);
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "z", "+", "y", "+", "z")}
+    );
+}
+
+#[test]
+fn select_next_occurrence_in_container() {
+    let mut editor = create_editor(&["x", "+"]);
+    editor.insert_at_caret(vec!["^".to_string()]);
+    editor.insert_at_caret(vec!["x".to_string()]);
+    editor.start_selection(root_position(0), MoveMode::Char);
+    editor.extend_selection(root_position(1));
+    editor.finish_selection();
+    editor.select_next_occurrence();
+
+    let caret = editor.get_caret();
+    assert_eq!(caret.len(), 2);
+    assert_eq!(caret[0], MinimalCaretSelection::Row(range(&[(2, 0)], 0, 1)));
+}
+
+#[test]
+fn touching_selections_stay_separate() {
+    let mut editor = create_editor(&["a", "+", "a", "+"]);
+    editor.start_selection(root_position(0), MoveMode::Char);
+    editor.extend_selection(root_position(2));
+    editor.finish_selection();
+    editor.select_next_occurrence();
+
+    assert_eq!(editor.get_caret(), vec![row_range(2, 4), row_range(0, 2)]);
+
+    editor.insert_at_caret(vec!["b".to_string()]);
+    assert_eq!(editor.get_input_tree(), &input_row! {(row "b", "b")});
+}
+
+#[test]
+fn grid_selections_are_skipped() {
+    let mut editor = create_editor(&[]);
+    editor.splice_at_range(
+        root_range(0, 0),
+        input_nodes! {"a", (table 2 x 1 (row "b"), (row "c"))},
+    );
+    editor.start_selection(position(&[(1, 0)], 0), MoveMode::Char);
+    editor.extend_selection(position(&[(1, 1)], 1));
+    editor.finish_selection();
+    editor.add_caret(root_position(1));
+    assert_eq!(editor.get_caret().len(), 2);
+
+    editor.insert_at_caret(vec!["x".to_string()]);
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row "a", "x", (table 2 x 1 (row "b"), (row "c")))}
+    );
+    assert_eq!(editor.get_caret().len(), 2);
+
+    editor.select_with_caret(Direction::Left, MoveMode::Char);
+    assert_eq!(editor.get_caret().len(), 2);
+}
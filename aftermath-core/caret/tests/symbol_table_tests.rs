@@ -0,0 +1,128 @@
+mod common;
+
+use std::rc::Rc;
+
+use caret::symbol_table::{EditorId, SymbolTable};
+use common::{create_editor_with_parser, position, root_position, root_range};
+use input_tree::{input_nodes, node::InputNode, row::InputRow};
+use parser::{
+    locale::Locale,
+    parser::MathParser,
+    rule_collections::{
+        arithmetic_rules::ArithmeticRules, collections_rules::CollectionsRules,
+        comparison_rules::ComparisonRules, core_rules::CoreRules,
+        definition_rules::DefinitionRules, function_rules::FunctionRules,
+    },
+};
+
+fn create_parser() -> (Rc<MathParser>, Rc<DefinitionRules>) {
+    let mut definition = None;
+    let parser = common::create_parser(|modules, built_in| {
+        let locale = Locale::default();
+        let core = Rc::new(CoreRules::new(modules, built_in));
+        let arithmetic = Rc::new(ArithmeticRules::new(modules, built_in, &locale));
+        let collections = Rc::new(CollectionsRules::new(modules, &locale));
+        let comparison = Rc::new(ComparisonRules::new(modules));
+        let definition_rules = Rc::new(DefinitionRules::new(modules, built_in));
+        let function = Rc::new(FunctionRules::new(modules, built_in));
+        definition = Some(definition_rules.clone());
+        vec![
+            core,
+            arithmetic,
+            collections,
+            comparison,
+            definition_rules,
+            function,
+        ]
+    });
+    (
+        parser,
+        definition.expect("The definition rules are always created"),
+    )
+}
+
+#[test]
+fn test_function_definition_across_editors() {
+    let (parser, definition_rules) = create_parser();
+    let mut symbol_table = SymbolTable::new(definition_rules);
+    let mut definition =
+        create_editor_with_parser(&parser, &["f", "(", "x", ")", "=", "x", "*", "x"]);
+    let mut usage =
+        create_editor_with_parser(&parser, &["f", "(", "3", ")", "+", "g", "(", "x", ")"]);
+    symbol_table.update(EditorId(0), &mut definition);
+    symbol_table.update(EditorId(1), &mut usage);
+
+    let (editor_id, resolved) = symbol_table.resolve("f").unwrap();
+    assert_eq!(editor_id, EditorId(0));
+    assert_eq!(resolved.parameters, Some(vec!["x".to_string()]));
+    assert!(symbol_table.undefined_names(EditorId(0)).is_empty());
+    assert_eq!(
+        symbol_table
+            .undefined_names(EditorId(1))
+            .iter()
+            .map(|v| v.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["g", "x"]
+    );
+
+    let position = root_position(1);
+    assert_eq!(
+        symbol_table.go_to_definition(EditorId(1), &position),
+        Some((EditorId(0), root_range(0, 1)))
+    );
+}
+
+#[test]
+fn test_equation_is_not_definition() {
+    let (parser, definition_rules) = create_parser();
+    let mut symbol_table = SymbolTable::new(definition_rules);
+    let mut equation = create_editor_with_parser(&parser, &["x", "=", "3"]);
+    let mut evaluated = create_editor_with_parser(&parser, &["f", "(", "2", ")", "=", "4"]);
+    symbol_table.update(EditorId(0), &mut equation);
+    symbol_table.update(EditorId(1), &mut evaluated);
+
+    assert!(symbol_table.resolve("x").is_none());
+    assert!(symbol_table.resolve("f").is_none());
+}
+
+#[test]
+fn test_constant_definition_in_nested_row() {
+    let (parser, definition_rules) = create_parser();
+    let mut symbol_table = SymbolTable::new(definition_rules);
+    let mut definition = create_editor_with_parser(&parser, &["a", "≔", "2"]);
+    let mut usage = create_editor_with_parser(&parser, &["1", "+", "a"]);
+    usage.splice_at_range(
+        root_range(3, 3),
+        vec![InputNode::sup(InputRow::new(InputNode::symbols(vec!["a"])))],
+    );
+    symbol_table.update(EditorId(0), &mut definition);
+    symbol_table.update(EditorId(1), &mut usage);
+
+    assert!(symbol_table.undefined_names(EditorId(1)).is_empty());
+    let position = position(&[(3, 0)], 0);
+    assert_eq!(
+        symbol_table.go_to_definition(EditorId(1), &position),
+        Some((EditorId(0), root_range(0, 1)))
+    );
+
+    symbol_table.remove(EditorId(0));
+    assert_eq!(symbol_table.undefined_names(EditorId(1)).len(), 2);
+}
+
+#[test]
+fn test_function_definition_with_fenced_brackets() {
+    let (parser, definition_rules) = create_parser();
+    let mut symbol_table = SymbolTable::new(definition_rules);
+    let mut definition = create_editor_with_parser(&parser, &["f"]);
+    definition.splice_at_range(
+        root_range(1, 1),
+        input_nodes! {(fenced "(" ")" (row "x", ",", "y")), "=", "x"},
+    );
+    symbol_table.update(EditorId(0), &mut definition);
+
+    let (_, resolved) = symbol_table.resolve("f").unwrap();
+    assert_eq!(
+        resolved.parameters,
+        Some(vec!["x".to_string(), "y".to_string()])
+    );
+}
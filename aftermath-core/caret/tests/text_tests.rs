@@ -0,0 +1,52 @@
+mod common;
+
+use std::rc::Rc;
+
+use caret::{math_editor::MathEditor, primitive::MoveMode};
+use common::{create_editor_with_parser, create_parser, position, root_range};
+use input_tree::{input_nodes, input_row};
+use parser::rule_collections::comparison_rules::ComparisonRules;
+
+fn create_editor() -> MathEditor {
+    let parser = create_parser(|modules, _| vec![Rc::new(ComparisonRules::new(modules))]);
+    create_editor_with_parser(&parser, &[])
+}
+
+#[test]
+fn no_autocomplete_in_text() {
+    let mut editor = create_editor();
+    editor.splice_at_range(root_range(0, 0), input_nodes! {(text (row))});
+    editor.start_selection(position(&[(0, 0)], 0), MoveMode::Char);
+    editor.finish_selection();
+    editor.insert_at_caret(vec!["<".into(), "=".into()]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row (text (row "<", "=")))}
+    );
+    assert!(editor.get_autocomplete().is_none());
+}
+
+#[test]
+fn autocomplete_outside_of_text() {
+    let mut editor = create_editor();
+    editor.insert_at_caret(vec!["<".into(), "=".into()]);
+
+    assert!(editor.get_autocomplete().is_some());
+}
+
+#[test]
+fn no_forced_autocorrect_in_text() {
+    let mut editor = create_editor();
+    editor.splice_at_range(root_range(0, 0), input_nodes! {(text (row))});
+    editor.start_selection(position(&[(0, 0)], 0), MoveMode::Char);
+    editor.finish_selection();
+    editor.insert_at_caret(vec!["a".into()]);
+    editor.insert_at_caret(vec!["/".into()]);
+    editor.insert_at_caret(vec!["^".into()]);
+
+    assert_eq!(
+        editor.get_input_tree(),
+        &input_row! {(row (text (row "a", "/", "^")))}
+    );
+}
@@ -14,7 +14,7 @@ impl InputTree {
         InputTree { root }
     }
 
-    pub fn root_focus(&self) -> InputFocusRow {
+    pub fn root_focus(&self) -> InputFocusRow<'_> {
         InputFocusRow::from_root(&self.root)
     }
 
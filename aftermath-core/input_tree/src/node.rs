@@ -25,7 +25,6 @@ pub enum InputNode {
     Symbol(String),
 }
 
-// Could be extended with constructs like <mmultiscripts>
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(
     feature = "wasm",
@@ -46,10 +45,55 @@ pub enum InputNodeVariant {
     /// The selection joining part makes it behave as expected.
     /// And the rendering part makes it look like you're selecting the table.
     Table,
-    // More commands like "under", "over" could be added in the future
+    /// A row with an annotation below it, like the $x \to 0$ under a limit.
+    /// The rows are stored in MathML order, so the base comes first.
+    Under,
+    /// A row with an annotation above it, like an accent or an overbrace.
+    /// The rows are stored in MathML order, so the base comes first.
+    Over,
+    /// A row with annotations below and above it, like a stacked arrow.
+    /// The rows are stored in MathML order: base, under, over.
+    UnderOver,
+    /// A base with scripts on both sides, like $^{14}_{6}C$ or $\Gamma^i_{jk}$.
+    /// The scripts on one side stay aligned, unlike a sub that is followed by a sup.
+    /// The rows are stored in MathML order: base, sub, sup, presub, presup.
+    Multiscripts,
+    /// Prose inside of a formula, like the "for all" in $f(x) = 0 \text{ for all } x$.
+    /// The row is not parsed as math, so spaces are kept and nothing gets autocorrected.
+    Text,
+    /// A group with brackets around it, like $(x+1)$ or $|x|$.
+    /// The brackets are part of the container, so they are always balanced and can be stretched to fit the contents.
+    /// They don't have to match, like in the half-open interval $[0, 1)$.
+    Fenced(Fence, Fence),
+}
+
+/// A bracket on one side of a fenced group
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "wasm",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
+pub enum Fence {
+    /// ( and )
+    Round,
+    /// [ and ]
+    Square,
+    /// { and }
+    Curly,
+    /// ⟨ and ⟩
+    Angle,
+    /// | on both sides
+    Vertical,
+    /// ‖ on both sides
+    DoubleVertical,
 }
 
 impl InputNode {
+    /// An explicit hole that has to be filled in.
+    /// It is a normal symbol, so it can be typed over, copied and serialized like any other symbol.
+    pub const PLACEHOLDER: &'static str = "⬚";
+
     pub fn fraction(values: [InputRow; 2]) -> Self {
         Self::container_with_type(
             InputNodeVariant::Fraction,
@@ -80,6 +124,48 @@ impl InputNode {
         )
     }
 
+    pub fn under(values: [InputRow; 2]) -> Self {
+        Self::container_with_type(
+            InputNodeVariant::Under,
+            GridVec::from_one_dimensional(values.to_vec(), 1),
+        )
+    }
+
+    pub fn over(values: [InputRow; 2]) -> Self {
+        Self::container_with_type(
+            InputNodeVariant::Over,
+            GridVec::from_one_dimensional(values.to_vec(), 1),
+        )
+    }
+
+    pub fn under_over(values: [InputRow; 3]) -> Self {
+        Self::container_with_type(
+            InputNodeVariant::UnderOver,
+            GridVec::from_one_dimensional(values.to_vec(), 1),
+        )
+    }
+
+    pub fn multiscripts(values: [InputRow; 5]) -> Self {
+        Self::container_with_type(
+            InputNodeVariant::Multiscripts,
+            GridVec::from_one_dimensional(values.to_vec(), 1),
+        )
+    }
+
+    pub fn text(value: InputRow) -> Self {
+        Self::container_with_type(
+            InputNodeVariant::Text,
+            GridVec::from_one_dimensional(vec![value], 1),
+        )
+    }
+
+    pub fn fenced(open: Fence, close: Fence, value: InputRow) -> Self {
+        Self::container_with_type(
+            InputNodeVariant::Fenced(open, close),
+            GridVec::from_one_dimensional(vec![value], 1),
+        )
+    }
+
     pub fn table(values: Vec<InputRow>, width: usize) -> Self {
         Self::container_with_type(
             InputNodeVariant::Table,
@@ -90,6 +176,14 @@ impl InputNode {
         Self::Symbol(value.into())
     }
 
+    pub fn placeholder() -> Self {
+        Self::Symbol(Self::PLACEHOLDER.into())
+    }
+
+    pub fn is_placeholder(&self) -> bool {
+        matches!(self, InputNode::Symbol(value) if value == Self::PLACEHOLDER)
+    }
+
     pub fn symbols<T: Into<String>>(values: Vec<T>) -> Vec<Self> {
         values
             .into_iter()
@@ -156,6 +250,33 @@ impl InputNodeVariant {
             InputNodeVariant::Sup => false,
             InputNodeVariant::Sub => false,
             InputNodeVariant::Table => true,
+            InputNodeVariant::Under => false,
+            InputNodeVariant::Over => false,
+            InputNodeVariant::UnderOver => false,
+            InputNodeVariant::Multiscripts => false,
+            InputNodeVariant::Text => false,
+            InputNodeVariant::Fenced(_, _) => false,
+        }
+    }
+
+    /// The rows that are stacked on top of the given row or below it, ordered from top to bottom.
+    /// Only differs from the stored order for containers with an annotation above the base, and for scripts.
+    pub fn rows_top_to_bottom(&self, row_count: usize, row_index: usize) -> Vec<usize> {
+        match self {
+            InputNodeVariant::Over => vec![1, 0],
+            InputNodeVariant::UnderOver => vec![2, 0, 1],
+            InputNodeVariant::Multiscripts if row_index >= 3 => vec![4, 3],
+            InputNodeVariant::Multiscripts => vec![2, 0, 1],
+            _ => (0..row_count).collect(),
+        }
+    }
+
+    /// The order in which the caret visits the rows when moving to the right.
+    /// Only differs from the stored order for containers with scripts in front of the base.
+    pub fn rows_left_to_right(&self, row_count: usize) -> Vec<usize> {
+        match self {
+            InputNodeVariant::Multiscripts => vec![3, 4, 0, 1, 2],
+            _ => (0..row_count).collect(),
         }
     }
 }
@@ -168,6 +289,55 @@ impl fmt::Display for InputNodeVariant {
             InputNodeVariant::Sup => write!(f, "sup"),
             InputNodeVariant::Sub => write!(f, "sub"),
             InputNodeVariant::Table => write!(f, "table"),
+            InputNodeVariant::Under => write!(f, "under"),
+            InputNodeVariant::Over => write!(f, "over"),
+            InputNodeVariant::UnderOver => write!(f, "underover"),
+            InputNodeVariant::Multiscripts => write!(f, "multiscripts"),
+            InputNodeVariant::Text => write!(f, "text"),
+            InputNodeVariant::Fenced(open, close) => {
+                write!(f, "fenced \"{}\" \"{}\"", open.opening(), close.closing())
+            }
+        }
+    }
+}
+
+impl Fence {
+    const ALL: [Fence; 6] = [
+        Fence::Round,
+        Fence::Square,
+        Fence::Curly,
+        Fence::Angle,
+        Fence::Vertical,
+        Fence::DoubleVertical,
+    ];
+
+    pub fn opening(&self) -> &'static str {
+        match self {
+            Fence::Round => "(",
+            Fence::Square => "[",
+            Fence::Curly => "{",
+            Fence::Angle => "⟨",
+            Fence::Vertical => "|",
+            Fence::DoubleVertical => "‖",
         }
     }
+
+    pub fn closing(&self) -> &'static str {
+        match self {
+            Fence::Round => ")",
+            Fence::Square => "]",
+            Fence::Curly => "}",
+            Fence::Angle => "⟩",
+            Fence::Vertical => "|",
+            Fence::DoubleVertical => "‖",
+        }
+    }
+
+    pub fn from_opening(symbol: &str) -> Option<Fence> {
+        Self::ALL.into_iter().find(|v| v.opening() == symbol)
+    }
+
+    pub fn from_closing(symbol: &str) -> Option<Fence> {
+        Self::ALL.into_iter().find(|v| v.closing() == symbol)
+    }
 }
@@ -18,6 +18,30 @@ macro_rules! input_node {
   ((sub $a:tt)) => {
       $crate::node::InputNode::sub($crate::input_row!($a))
   };
+  ((under $a:tt, $b:tt)) => {
+      $crate::node::InputNode::under([$crate::input_row!($a), $crate::input_row!($b)])
+  };
+  ((over $a:tt, $b:tt)) => {
+      $crate::node::InputNode::over([$crate::input_row!($a), $crate::input_row!($b)])
+  };
+  ((underover $a:tt, $b:tt, $c:tt)) => {
+      $crate::node::InputNode::under_over([$crate::input_row!($a), $crate::input_row!($b), $crate::input_row!($c)])
+  };
+  ((multiscripts $a:tt, $b:tt, $c:tt, $d:tt, $e:tt)) => {
+      $crate::node::InputNode::multiscripts([
+        $crate::input_row!($a), $crate::input_row!($b), $crate::input_row!($c), $crate::input_row!($d), $crate::input_row!($e)
+      ])
+  };
+  ((text $a:tt)) => {
+      $crate::node::InputNode::text($crate::input_row!($a))
+  };
+  ((fenced $open:literal $close:literal $a:tt)) => {
+      $crate::node::InputNode::fenced(
+        $crate::node::Fence::from_opening($open).expect("Unknown opening bracket"),
+        $crate::node::Fence::from_closing($close).expect("Unknown closing bracket"),
+        $crate::input_row!($a)
+      )
+  };
   ((table $x:literal x $y:literal $e:tt $(,$es:tt)* $(,)?)) => {{
       let width: usize = $x;
       let height: usize = $y;
@@ -55,3 +55,29 @@ fn remove_shrink_cursor_range() {
     assert_eq!(cursor.end, Offset(1));
     assert_eq!(cursor.row_indices(), &RowIndices::new(vec![]));
 }
+
+#[test]
+fn insert_before_container_moves_child_range() {
+    let mut input = InputTree::new(InputRow::new(vec![InputNode::sup(InputRow::new(vec![
+        InputNode::symbol("a"),
+    ]))]));
+    let insert_edit = RowEdit {
+        edit_type: EditType::Insert,
+        position: InputRowPosition::new(input.root_focus(), Offset(0)).to_minimal(),
+        values: vec![InputNode::symbol("b")],
+    }
+    .into();
+
+    let mut serialized_cursor = MinimalInputRowRange {
+        row_indices: RowIndices::new(vec![RowIndex(0, 0)]),
+        start: Offset(0),
+        end: Offset(1),
+    };
+    input.apply_edit(&insert_edit);
+    serialized_cursor.apply_edit(&insert_edit);
+
+    let cursor = InputRowRange::from_minimal(input.root_focus(), &serialized_cursor);
+    assert_eq!(cursor.start, Offset(0));
+    assert_eq!(cursor.end, Offset(1));
+    assert_eq!(cursor.row_indices(), &RowIndices::new(vec![RowIndex(1, 0)]));
+}
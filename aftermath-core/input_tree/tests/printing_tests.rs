@@ -58,3 +58,52 @@ fn macro_and_print() {
         r#"(row "a" (sub 1x1 (row "2")) "+" (frac 1x2 (row (sup 1x1 (row (sub 1x1 (row "b"))))) (row (root 2x1 (row "3") (row "3")) (sup 1x1 (row "1")))))"#
     )
 }
+
+#[test]
+fn macro_and_print_under_over() {
+    let input = input_row! {
+        (row (under (row "l", "i", "m"), (row "x")), (over (row "x"), (row "^")),
+            (underover (row "→"), (row), (row "f")))
+    };
+
+    assert_eq!(
+        input.to_string(),
+        r#"(row (under 1x2 (row "l" "i" "m") (row "x")) (over 1x2 (row "x") (row "^")) (underover 1x3 (row "→") (row) (row "f")))"#
+    )
+}
+
+#[test]
+fn macro_and_print_multiscripts() {
+    let input = input_row! {
+        (row (multiscripts (row "Γ"), (row "j", "k"), (row "i"), (row), (row)))
+    };
+
+    assert_eq!(
+        input.to_string(),
+        r#"(row (multiscripts 1x5 (row "Γ") (row "j" "k") (row "i") (row) (row)))"#
+    )
+}
+
+#[test]
+fn macro_and_print_text() {
+    let input = input_row! {
+        (row "x", (text (row " ", "f", "o", "r", " ")))
+    };
+
+    assert_eq!(
+        input.to_string(),
+        r#"(row "x" (text 1x1 (row " " "f" "o" "r" " ")))"#
+    )
+}
+
+#[test]
+fn macro_and_print_fenced() {
+    let input = input_row! {
+        (row (fenced "[" ")" (row "0", ",", "1")))
+    };
+
+    assert_eq!(
+        input.to_string(),
+        r#"(row (fenced "[" ")" 1x1 (row "0" "," "1")))"#
+    )
+}
@@ -1,4 +1,6 @@
+use input_tree::focus::MinimalInputRowRange;
 use input_tree::node::InputNode;
+use input_tree::row::{Offset, RowIndices};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -12,17 +14,70 @@ pub struct AutocompleteRule {
     pub parser: String,
 
     pub result: Vec<InputNode>,
+
+    /// Turns the result into a snippet. The caret starts at the first tab stop, and Tab moves to the next one.
+    #[serde(default)]
+    pub tab_stops: Vec<TabStop>,
+
+    /// What the autocomplete popup shows. Defaults to the parser.
+    #[serde(default)]
+    pub label: Option<String>,
+
+    /// A short explanation for the autocomplete popup, like "Integral with bounds".
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Name of the module that the rule comes from. Filled in by the parser if it's empty.
+    #[serde(default)]
+    pub category: String,
+
+    /// Added to the score of a match. One more matched symbol is worth [`AutocompleteRuleMatch::SYMBOL_SCORE`].
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// A place in the result of a snippet that the caret can jump to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "wasm",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
+pub struct TabStop {
+    /// Tab stops with the same name are linked. Editing one of them mirrors the changes to the others.
+    pub name: String,
+    /// Relative to the result. An empty `row_indices` means that the offsets point into the result itself,
+    /// otherwise the first row index points at one of the nodes of the result.
+    pub range: MinimalInputRowRange,
+}
+
+impl TabStop {
+    pub fn new(
+        name: impl Into<String>,
+        row_indices: RowIndices,
+        start: Offset,
+        end: Offset,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            range: MinimalInputRowRange {
+                row_indices,
+                start,
+                end,
+            },
+        }
+    }
 }
 
 pub trait AutocompleteMatcher {
     /// Takes an input that goes until the caret.
     /// Then returns all matches that could be made with these rules.
     /// Of course including prefix-matches.
-    /// TODO:
-    /// When we have text like "sin|(3)", with | being the caret, then we probably should avoid suggesting "into" as an autocomplete.
-    /// So after `matches` happens, we should probably remove all autocompletes that start in the middle of an existing parsed token.
-    /// See https://github.com/stefnotch/aftermath-editor/blob/82a09aa3fead16baeca44f60b044275d3ef3a304/src/editing/editing-autocomplete.ts#L16 for
-    /// some relevant code.
+    /// This doesn't know about tokens, so for "sin|(3)", with | being the caret, it would also suggest "into".
+    /// The editor removes those matches that start in the middle of a token from a different rule, but keeps the ones that end a variable name.
+    ///
+    /// If the input isn't a prefix of a rule, then it can still be a fuzzy match.
+    /// That's when the input appears in the same order in the rule, like "intg" for "integral".
     fn matches<'b>(
         &'b self,
         input: &[InputNode],
@@ -39,11 +94,35 @@ pub struct AutocompleteRuleMatch<'a> {
     /// How much of the input was matched, starting from the end where the caret is and going backwards.
     /// Used for underlining the input.
     pub input_match_length: usize,
+    /// How much of the rule value was skipped by a fuzzy match. Zero for a prefix match.
+    pub skipped_length: usize,
 }
 
 impl<'a> AutocompleteRuleMatch<'a> {
+    pub const SYMBOL_SCORE: i32 = 16;
+    const SKIPPED_SCORE: i32 = 4;
+
+    /// Fuzzy matches are never complete, since the user didn't actually type the keyword.
     pub fn is_complete_match(&self) -> bool {
-        self.rule_match_length == self.rule.parser.len()
+        !self.is_fuzzy() && self.rule_match_length == self.rule.parser.len()
+    }
+
+    pub fn is_fuzzy(&self) -> bool {
+        self.skipped_length > 0
+    }
+
+    /// Higher is better. Matching more of the input is what counts the most,
+    /// then come matches without gaps, and then rules that are almost fully typed out.
+    pub fn score(&self) -> i32 {
+        let remaining_length = self
+            .rule
+            .parser
+            .len()
+            .saturating_sub(self.rule_match_length);
+        self.input_match_length as i32 * Self::SYMBOL_SCORE
+            - self.skipped_length as i32 * Self::SKIPPED_SCORE
+            - remaining_length as i32
+            + self.rule.priority
     }
 }
 
@@ -53,9 +132,18 @@ impl<'a> PartialOrd for AutocompleteRuleMatch<'a> {
     }
 }
 
+/// Better matches come first. Equally good matches are sorted by their keyword,
+/// so that the order doesn't depend on the order of the rules.
 impl<'a> Ord for AutocompleteRuleMatch<'a> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.rule_match_length.cmp(&other.rule_match_length)
+        other
+            .score()
+            .cmp(&self.score())
+            .then_with(|| self.rule.parser.cmp(&other.rule.parser))
+            .then_with(|| self.rule.category.cmp(&other.rule.category))
+            .then_with(|| self.rule_match_length.cmp(&other.rule_match_length))
+            .then_with(|| self.input_match_length.cmp(&other.input_match_length))
+            .then_with(|| self.skipped_length.cmp(&other.skipped_length))
     }
 }
 
@@ -64,10 +152,85 @@ impl AutocompleteRule {
         Self {
             parser: parser.into(),
             result,
+            tab_stops: vec![],
+            label: None,
+            description: None,
+            category: String::new(),
+            priority: 0,
         }
     }
+
+    pub fn with_tab_stops(mut self, tab_stops: Vec<TabStop>) -> Self {
+        self.tab_stops = tab_stops;
+        self
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.category = category.into();
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn get_label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.parser)
+    }
+
+    /// Finds the longest suffix of the input that appears in the same order in the parser.
+    /// The first symbol has to match the start of the parser, so that "tg" doesn't suggest "integral".
+    fn fuzzy_match(
+        &self,
+        input: &[InputNode],
+        min_input_length: usize,
+    ) -> Option<AutocompleteRuleMatch<'_>> {
+        let symbols: Vec<&str> = input
+            .iter()
+            .rev()
+            .map_while(|node| match node {
+                InputNode::Symbol(symbol) => Some(symbol.as_str()),
+                _ => None,
+            })
+            .collect();
+        let min_input_length = min_input_length.max(FUZZY_MIN_INPUT_LENGTH);
+        (min_input_length..=symbols.len())
+            .rev()
+            .find_map(|input_length| {
+                let mut parser_index = 0;
+                let mut skipped_length = 0;
+                for (i, symbol) in symbols[..input_length].iter().rev().enumerate() {
+                    let found = self.parser[parser_index..].find(symbol)?;
+                    if i == 0 && found != 0 {
+                        return None;
+                    }
+                    skipped_length += found;
+                    parser_index += found + symbol.len();
+                }
+                Some(AutocompleteRuleMatch {
+                    rule: self,
+                    rule_match_length: parser_index,
+                    input_match_length: input_length,
+                    skipped_length,
+                })
+            })
+    }
 }
 
+/// Shorter fuzzy matches would pop up for almost every variable name.
+const FUZZY_MIN_INPUT_LENGTH: usize = 3;
+
 /// Returns the indices of all occurences of needle in haystack.
 /// Can return overlapping ranges.
 fn indices_of(needle: &str, haystack: &str) -> Vec<usize> {
@@ -128,10 +291,16 @@ impl AutocompleteMatcher for AutocompleteRule {
                     rule: self,
                     rule_match_length: parser_end_index + 1,
                     input_match_length: symbol_count,
+                    skipped_length: 0,
                 })
             }
         }
 
+        // Fuzzy matching is only a fallback, for when the input isn't a prefix of the rule
+        if matches.is_empty() {
+            matches.extend(self.fuzzy_match(input, min_rule_match_length));
+        }
+
         matches
     }
 }
@@ -0,0 +1,15 @@
+use input_tree::focus::MinimalInputRowRange;
+use serde::{Deserialize, Serialize};
+
+/// A problem in a formula that parses fine, like adding metres to seconds.
+/// Parse modules report them, see `ParseModule::get_diagnostics`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "wasm",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
+pub struct Diagnostic {
+    pub range: MinimalInputRowRange,
+    pub message: String,
+}
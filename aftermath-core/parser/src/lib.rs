@@ -1,4 +1,6 @@
 pub mod autocomplete;
+pub mod diagnostic;
+pub mod locale;
 pub mod make_parser;
 pub mod math_parser;
 pub mod parse_module;
@@ -8,4 +10,6 @@ pub mod parser_debug_error;
 pub mod parser_extensions;
 pub mod rule_collection;
 pub mod rule_collections;
+pub mod scope_analysis;
 pub mod syntax_tree;
+pub mod type_checker;
@@ -0,0 +1,34 @@
+/// Notation that differs between countries, like the decimal separator.
+/// Every parser is created with one locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Locale {
+    pub decimal_separator: DecimalSeparator,
+    /// Whether 1.5e-3 is a number. Off by default, since 2e-3 usually means 2·e-3.
+    pub e_notation: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecimalSeparator {
+    /// 3.14 and (1, 2)
+    #[default]
+    Dot,
+    /// 3,14 and (1; 2)
+    Comma,
+}
+
+impl Locale {
+    pub fn decimal_separator(&self) -> &'static str {
+        match self.decimal_separator {
+            DecimalSeparator::Dot => ".",
+            DecimalSeparator::Comma => ",",
+        }
+    }
+
+    /// The tuple separator must not clash with the decimal separator.
+    pub fn tuple_separator(&self) -> &'static str {
+        match self.decimal_separator {
+            DecimalSeparator::Dot => ",",
+            DecimalSeparator::Comma => ";",
+        }
+    }
+}
@@ -1,9 +1,12 @@
-use chumsky::{primitive::end, Parser};
+use chumsky::{
+    primitive::{choice, end},
+    Parser,
+};
 
 use crate::{
     parser::pratt_parser::{call_pratt_parser, Strength},
     parser_extensions::{just_symbol, just_symbols},
-    rule_collection::{BoxedTokenParser, RcPrattParserType},
+    rule_collection::{BoxedNothingParser, BoxedTokenParser, RcPrattParserType},
     syntax_tree::{LeafNodeType, SyntaxNodeBuilder, SyntaxNodeChildren, SyntaxNodeNameId},
 };
 
@@ -90,6 +93,14 @@ pub fn just_operator_parser(operator: impl VecOrString) -> impl MakeParser {
     }
 }
 
+/// The closing brackets, which also end rules that would otherwise go until the end of the row.
+/// For example, an integral without a differential ends at the closing bracket in (∫ f).
+pub fn closing_brackets_parser<'a>() -> BoxedNothingParser<'a, 'a> {
+    choice((just_symbol(")"), just_symbol("}")))
+        .map(|_| ())
+        .boxed()
+}
+
 pub fn make_brackets_parser(
     operator_rule_name: SyntaxNodeNameId,
     starting_bracket: impl Into<String>,
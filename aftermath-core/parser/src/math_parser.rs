@@ -85,16 +85,16 @@ impl Cached for CachedMathParser {
                         let make_parser = match rule {
                             ParseRule::Atom(_, make_parser)
                             | ParseRule::Prefix(_, _, make_parser)
-                            | ParseRule::LeftInfix(_, _, make_parser)
-                            | ParseRule::RightInfix(_, _, make_parser)
+                            | ParseRule::LeftInfix(_, _, make_parser, _)
+                            | ParseRule::RightInfix(_, _, make_parser, _)
                             | ParseRule::Postfix(_, _, make_parser) => make_parser,
                             ParseRule::NameOnly(_) => unreachable!(),
                         };
                         let rule_name = match rule {
                             ParseRule::Atom(rule_name, _) => *rule_name,
                             ParseRule::Prefix(_, _, _)
-                            | ParseRule::LeftInfix(_, _, _)
-                            | ParseRule::RightInfix(_, _, _)
+                            | ParseRule::LeftInfix(_, _, _, _)
+                            | ParseRule::RightInfix(_, _, _, _)
                             | ParseRule::Postfix(_, _, _) => operator_rule_name,
                             ParseRule::NameOnly(_) => unreachable!(),
                         };
@@ -178,8 +178,8 @@ impl Cached for CachedMathParser {
                     ParseRule::Atom(_, _) => rule_parser.boxed(),
                     ParseRule::Postfix(_, _, _) => rule_parser.boxed(),
                     ParseRule::Prefix(_, _, _)
-                    | ParseRule::LeftInfix(_, _, _)
-                    | ParseRule::RightInfix(_, _, _) => rule_parser
+                    | ParseRule::LeftInfix(_, _, _, _)
+                    | ParseRule::RightInfix(_, _, _, _) => rule_parser
                         .then(
                             chumsky::prelude::choice((sub_parser, sup_parser))
                                 .repeated()
@@ -204,18 +204,22 @@ impl Cached for CachedMathParser {
                             PrefixBuilderImpl { name: *rule_name },
                         ))
                     }
-                    ParseRule::LeftInfix(rule_name, strength, _) => {
+                    ParseRule::LeftInfix(rule_name, strength, _, builder) => {
                         infix_parsers.push(pratt_parser::left_infix(
                             rule_parser,
                             *strength,
-                            InfixBuilderImpl { name: *rule_name },
+                            builder
+                                .clone()
+                                .unwrap_or_else(|| Rc::new(InfixBuilderImpl { name: *rule_name })),
                         ))
                     }
-                    ParseRule::RightInfix(rule_name, strength, _) => {
+                    ParseRule::RightInfix(rule_name, strength, _, builder) => {
                         infix_parsers.push(pratt_parser::right_infix(
                             rule_parser,
                             *strength,
-                            InfixBuilderImpl { name: *rule_name },
+                            builder
+                                .clone()
+                                .unwrap_or_else(|| Rc::new(InfixBuilderImpl { name: *rule_name })),
                         ))
                     }
                     ParseRule::Postfix(rule_name, strength, _) => {
@@ -1,7 +1,13 @@
 use std::rc::Rc;
 
 use crate::{
-    autocomplete::AutocompleteRule, make_parser::MakeParser, syntax_tree::SyntaxNodeNameId,
+    autocomplete::AutocompleteRule,
+    diagnostic::Diagnostic,
+    make_parser::MakeParser,
+    parser::pratt_parser::InfixBuilder,
+    rule_collection::RcInfixBuilder,
+    syntax_tree::{SyntaxNode, SyntaxNodeNameId},
+    type_checker::TypeSignature,
 };
 
 pub trait ParseModule {
@@ -12,6 +18,16 @@ pub trait ParseModule {
 
     fn get_autocomplete_rules(&self) -> &[AutocompleteRule];
 
+    /// The types of the rules, for the type checker. Rules without a signature are not checked.
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &[]
+    }
+
+    /// Checks that go beyond parsing, like the dimensional analysis. Gets the whole syntax tree.
+    fn get_diagnostics(&self, _node: &SyntaxNode) -> Vec<Diagnostic> {
+        vec![]
+    }
+
     fn boxed(self) -> BoxedParseModule
     where
         Self: Sized + 'static,
@@ -25,8 +41,19 @@ pub enum ParseRule {
     NameOnly(SyntaxNodeNameId),
     Atom(SyntaxNodeNameId, Box<dyn MakeParser>),
     Prefix(SyntaxNodeNameId, u16, Box<dyn MakeParser>),
-    LeftInfix(SyntaxNodeNameId, u16, Box<dyn MakeParser>),
-    RightInfix(SyntaxNodeNameId, u16, Box<dyn MakeParser>),
+    /// Infix rules can build their own node, otherwise it has the children (left, operator, right).
+    LeftInfix(
+        SyntaxNodeNameId,
+        u16,
+        Box<dyn MakeParser>,
+        Option<RcInfixBuilder>,
+    ),
+    RightInfix(
+        SyntaxNodeNameId,
+        u16,
+        Box<dyn MakeParser>,
+        Option<RcInfixBuilder>,
+    ),
     Postfix(SyntaxNodeNameId, u16, Box<dyn MakeParser>),
 }
 impl ParseRule {
@@ -35,8 +62,8 @@ impl ParseRule {
             ParseRule::NameOnly(name) => Some(name),
             ParseRule::Atom(name, _) => Some(name),
             ParseRule::Prefix(name, _, _) => Some(name),
-            ParseRule::LeftInfix(name, _, _) => Some(name),
-            ParseRule::RightInfix(name, _, _) => Some(name),
+            ParseRule::LeftInfix(name, _, _, _) => Some(name),
+            ParseRule::RightInfix(name, _, _, _) => Some(name),
             ParseRule::Postfix(name, _, _) => Some(name),
         }
     }
@@ -63,7 +90,17 @@ pub fn left_infix_rule(
     priority: u16,
     parser: impl MakeParser + 'static,
 ) -> ParseRule {
-    ParseRule::LeftInfix(name, priority, Box::new(parser))
+    ParseRule::LeftInfix(name, priority, Box::new(parser), None)
+}
+
+/// Like [`left_infix_rule`], but the node is built by the given builder.
+pub fn left_infix_rule_with_builder(
+    name: SyntaxNodeNameId,
+    priority: u16,
+    parser: impl MakeParser + 'static,
+    builder: impl InfixBuilder<SyntaxNode, SyntaxNode> + 'static,
+) -> ParseRule {
+    ParseRule::LeftInfix(name, priority, Box::new(parser), Some(Rc::new(builder)))
 }
 
 pub fn right_infix_rule(
@@ -71,7 +108,7 @@ pub fn right_infix_rule(
     priority: u16,
     parser: impl MakeParser + 'static,
 ) -> ParseRule {
-    ParseRule::RightInfix(name, priority, Box::new(parser))
+    ParseRule::RightInfix(name, priority, Box::new(parser), None)
 }
 
 pub fn postfix_rule(
@@ -8,7 +8,9 @@ use input_tree::node::InputNode;
 
 use crate::{
     autocomplete::{AutocompleteMatcher, AutocompleteRule},
+    diagnostic::Diagnostic,
     math_parser::CachedMathParser,
+    parse_module::ParseModule,
     parse_modules::ParseModuleCollection,
     rule_collections::built_in_rules::BuiltInRules,
     syntax_tree::SyntaxNode,
@@ -18,6 +20,8 @@ pub struct MathParser {
     parser_cache: chumsky::cache::Cache<CachedMathParser>,
     built_in: Rc<BuiltInRules>,
     autocomplete_rules: Vec<AutocompleteRule>,
+    /// Kept for the diagnostics
+    modules: Vec<Rc<dyn ParseModule>>,
 }
 
 impl MathParser {
@@ -25,17 +29,26 @@ impl MathParser {
         let autocomplete_rules = parse_modules
             .get_modules()
             .iter()
-            .flat_map(|v| v.get_autocomplete_rules())
+            .flat_map(|module| {
+                module.get_autocomplete_rules().iter().map(|rule| {
+                    if rule.category.is_empty() {
+                        rule.clone().with_category(module.get_module_name())
+                    } else {
+                        rule.clone()
+                    }
+                })
+            })
             .rev()
-            .cloned()
             .collect();
         let built_in = parse_modules.get_built_in().clone();
+        let modules = parse_modules.get_modules().to_vec();
 
         let parser_cache = chumsky::cache::Cache::new(CachedMathParser::new(parse_modules));
         Self {
             parser_cache,
             built_in,
             autocomplete_rules,
+            modules,
         }
     }
 
@@ -50,6 +63,19 @@ impl MathParser {
 
         result.unwrap_or_else(|| self.built_in.nothing_node(0))
     }
+
+    /// The diagnostics of every module, for a syntax tree that this parser created.
+    pub fn get_diagnostics(&self, node: &SyntaxNode) -> Vec<Diagnostic> {
+        self.modules
+            .iter()
+            .flat_map(|module| module.get_diagnostics(node))
+            .collect()
+    }
+
+    /// Whether the node is a variable name, like the "xy" in "2xy".
+    pub fn is_identifier(&self, node: &SyntaxNode) -> bool {
+        node.name == self.built_in.identifier_rule_name
+    }
 }
 
 impl AutocompleteMatcher for MathParser {
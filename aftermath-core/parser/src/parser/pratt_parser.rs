@@ -112,6 +112,8 @@ where
     min_binding_power: (u16, Strength),
     /// To check if we're at the end of the pratt parse.
     ending_parser: EndingParser,
+    /// The ending of the surrounding expression, which also ends this pratt parse.
+    outer_ending_parser: Option<EndingParser>,
 }
 
 impl<'a, I, O, E, Symbols, EndingParser> Clone
@@ -125,10 +127,25 @@ where
             internal: self.internal.clone(),
             min_binding_power: self.min_binding_power,
             ending_parser: self.ending_parser.clone(),
+            outer_ending_parser: self.outer_ending_parser.clone(),
         }
     }
 }
 
+/// Where a pratt parse stops.
+struct PrattEnding<'e, EndingParser> {
+    ending_parser: &'e EndingParser,
+    /// For example the closing bracket in (∫ f).
+    outer_ending_parser: Option<&'e EndingParser>,
+}
+
+impl<'e, EndingParser> PrattEnding<'e, EndingParser> {
+    /// See [`call_pratt_parser_with_outer_ending`]
+    fn takes_priority(&self) -> bool {
+        self.outer_ending_parser.is_some()
+    }
+}
+
 enum PrattParseResult<T> {
     Expression(T),
     End(T),
@@ -204,25 +221,28 @@ where
     fn is_at_end<'parse>(
         &self,
         inp: &mut InputRef<'a, 'parse, I, E>,
-        ending_parser: &EndingParser,
+        ending: &PrattEnding<EndingParser>,
     ) -> bool {
         if inp.is_at_end() {
             return true;
         }
-        inp.can_parse(ending_parser)
+        inp.can_parse(ending.ending_parser)
+            || ending
+                .outer_ending_parser
+                .is_some_and(|outer_ending| inp.can_parse(outer_ending))
     }
 
     fn parse_unknown<'parse>(
         &self,
         inp: &mut InputRef<'a, 'parse, I, E>,
-        ending_parser: &EndingParser,
+        ending: &PrattEnding<EndingParser>,
     ) -> O {
         let start_offset = inp.input_position();
 
         let unknown_input = inp.next_maybe().unwrap(); // TODO: Don't just unwrap here
         let mut unknown_atom = (self.error_handler.make_unknown_atom)(start_offset, unknown_input);
         loop {
-            if self.is_at_end(inp, ending_parser)
+            if self.is_at_end(inp, ending)
                 || inp.can_parse_iter(&self.symbols.prefix_ops)
                 || inp.can_parse(&self.symbols.atom)
                 || inp.can_parse_iter(&self.symbols.infix_ops)
@@ -262,7 +282,7 @@ where
     /// and the fallbacks
     /// - Infix: rewind, then ParseOperator(None, strength);
     /// - Postfix: rewind, then ParseOperator(None, strength);
-    /// - End: rewind, return End; (could also be moved down in this list)
+    /// - End: rewind, return End; (comes first if the ending takes priority)
     /// - Unknown: skip until End or Prefix/AtomParser/Infix/Postfix, then ParseExpression(strength) or ParseOperator(left, strength)
     /// the unknown token case is also why I even need the "End" case.
     ///
@@ -272,46 +292,47 @@ where
     /// and the fallbacks
     /// - Prefix: rewind, missing operator with strength, ParseExpression(strength), then ParseOperator(left, strength)
     /// - AtomParser: same
-    /// - End: rewind, return End;
+    /// - End: rewind, return End; (comes before Prefix if the ending takes priority)
     /// - Unknown: skip until End or Prefix/AtomParser/Infix/Postfix, then ParseExpression(strength) or ParseOperator(left, strength)
     ///
     fn pratt_parse(
         &self,
         inp: &mut InputRef<'a, '_, I, E>,
         min_binding_power: (u16, Strength),
-        ending_parser: &EndingParser,
+        ending: &PrattEnding<EndingParser>,
     ) -> PrattParseResult<O> {
         // Iterative-ish version of the above
-        let mut left = if inp.is_at_end() {
-            return PrattParseResult::End((self.error_handler.make_missing_atom)(
-                inp.input_position(),
-            ));
-        } else if let Some((value, op)) = inp.parse_iter(&self.symbols.prefix_ops) {
-            let right = self
-                .pratt_parse(inp, op.binding_power.strength_right(), ending_parser)
-                .map(|right| op.build.build(value, right));
-            match right {
-                PrattParseResult::Expression(value) => value,
-                PrattParseResult::End(value) => {
-                    return PrattParseResult::End(value);
+        let mut left =
+            if inp.is_at_end() || (ending.takes_priority() && self.is_at_end(inp, ending)) {
+                return PrattParseResult::End((self.error_handler.make_missing_atom)(
+                    inp.input_position(),
+                ));
+            } else if let Some((value, op)) = inp.parse_iter(&self.symbols.prefix_ops) {
+                let right = self
+                    .pratt_parse(inp, op.binding_power.strength_right(), ending)
+                    .map(|right| op.build.build(value, right));
+                match right {
+                    PrattParseResult::Expression(value) => value,
+                    PrattParseResult::End(value) => {
+                        return PrattParseResult::End(value);
+                    }
                 }
+            } else if let Ok(v) = inp.parse_safe(&self.symbols.atom) {
+                v
             }
-        } else if let Ok(v) = inp.parse_safe(&self.symbols.atom) {
-            v
-        }
-        // Failure cases with graceful recovery
-        else if inp.can_parse_iter(&self.symbols.infix_ops)
-            || inp.can_parse_iter(&self.symbols.postfix_ops)
-        {
-            (self.error_handler.make_missing_atom)(inp.input_position())
-        } else if self.is_at_end(inp, ending_parser) {
-            // Don't try to parse more if we're at the end
-            return PrattParseResult::End((self.error_handler.make_missing_atom)(
-                inp.input_position(),
-            ));
-        } else {
-            self.parse_unknown(inp, ending_parser)
-        };
+            // Failure cases with graceful recovery
+            else if inp.can_parse_iter(&self.symbols.infix_ops)
+                || inp.can_parse_iter(&self.symbols.postfix_ops)
+            {
+                (self.error_handler.make_missing_atom)(inp.input_position())
+            } else if self.is_at_end(inp, ending) {
+                // Don't try to parse more if we're at the end
+                return PrattParseResult::End((self.error_handler.make_missing_atom)(
+                    inp.input_position(),
+                ));
+            } else {
+                self.parse_unknown(inp, ending)
+            };
 
         loop {
             let pre_op = inp.save();
@@ -324,7 +345,7 @@ where
                     return PrattParseResult::Expression(left);
                 }
                 let right = self
-                    .pratt_parse(inp, op.binding_power.strength_right(), ending_parser)
+                    .pratt_parse(inp, op.binding_power.strength_right(), ending)
                     .map(|right| op.build.build(value, (left, right)));
                 match right {
                     PrattParseResult::Expression(value) => left = value,
@@ -340,7 +361,9 @@ where
                 left = op.build.build(value, left);
             }
             // Failure cases with graceful recovery
-            else if inp.can_parse_iter(&self.symbols.prefix_ops)
+            else if ending.takes_priority() && self.is_at_end(inp, ending) {
+                return PrattParseResult::End(left);
+            } else if inp.can_parse_iter(&self.symbols.prefix_ops)
                 || inp.can_parse(&self.symbols.atom)
             {
                 let start_offset = inp.input_position();
@@ -358,7 +381,7 @@ where
                         self.error_handler
                             .missing_operator_binding_power
                             .strength_right(),
-                        ending_parser,
+                        ending,
                     )
                     .map(|right| {
                         (self.error_handler.make_missing_operator)(start_offset, (left, right))
@@ -369,13 +392,13 @@ where
                         return PrattParseResult::End(value);
                     }
                 };
-            } else if self.is_at_end(inp, ending_parser) {
+            } else if self.is_at_end(inp, ending) {
                 // Don't try to parse more if we're at the end
                 return PrattParseResult::End(left);
             } else {
                 // Unknown
                 let start_offset = inp.input_position();
-                let right = self.parse_unknown(inp, ending_parser);
+                let right = self.parse_unknown(inp, ending);
                 left = (self.error_handler.make_missing_operator)(start_offset, (left, right));
             }
         }
@@ -430,10 +453,14 @@ where
     fn parse(&self, inp: &mut InputRef<'a, '_, I, E>) -> Result<O, E::Error> {
         // TODO: A single "(Error::MissingToken)" should become "(BuiltIn::Nothing)"
 
-        let result =
-            self.internal
-                .inner()
-                .pratt_parse(inp, self.min_binding_power, &self.ending_parser);
+        let ending = PrattEnding {
+            ending_parser: &self.ending_parser,
+            outer_ending_parser: self.outer_ending_parser.as_ref(),
+        };
+        let result = self
+            .internal
+            .inner()
+            .pratt_parse(inp, self.min_binding_power, &ending);
         match result {
             PrattParseResult::Expression(v) => Ok(v),
             PrattParseResult::End(v) => Ok(v),
@@ -457,6 +484,30 @@ where
         internal: parser_internal,
         min_binding_power,
         ending_parser,
+        outer_ending_parser: None,
+    })
+}
+
+/// Like [`call_pratt_parser`], but the parse also stops at the outer ending.
+/// For example, an integral without a differential ends at the closing bracket in (∫ f).
+///
+/// The ending is checked before the atoms, since it can overlap with them (e.g. the dx of an integral).
+pub fn call_pratt_parser_with_outer_ending<'a, I, O, E, Symbols, EndingParser>(
+    parser_internal: RcOrWeak<PrattParser<'a, I, O, E, Symbols, EndingParser>>,
+    min_binding_power: (u16, Strength),
+    ending_parser: EndingParser,
+    outer_ending_parser: EndingParser,
+) -> PrattParserCaller<'a, I, O, E, Symbols, EndingParser>
+where
+    I: Input<'a>,
+    EndingParser: Parser<'a, I, (), E>,
+    E: ParserExtra<'a, I>,
+{
+    Ext(PrattParserCaller_ {
+        internal: parser_internal,
+        min_binding_power,
+        ending_parser,
+        outer_ending_parser: Some(outer_ending_parser),
     })
 }
 
@@ -481,6 +532,7 @@ where
         internal: RcOrWeak::Owned(strong_ref),
         min_binding_power: (0, Strength::Weak),
         ending_parser,
+        outer_ending_parser: None,
     })
 }
 
@@ -712,6 +764,12 @@ pub trait InfixBuilder<Op, O> {
     fn build(&self, op: Op, children: (O, O)) -> O;
 }
 
+impl<Op, O, T: InfixBuilder<Op, O> + ?Sized> InfixBuilder<Op, O> for Rc<T> {
+    fn build(&self, op: Op, children: (O, O)) -> O {
+        (**self).build(op, children)
+    }
+}
+
 pub trait PrefixBuilder<Op, O> {
     fn build(&self, op: Op, right: O) -> O;
 }
@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use input_tree::node::InputNode;
 
 use crate::{
@@ -43,7 +45,7 @@ pub type PrattParserType<'a, 'b> = PrattParser<
         BoxedNodeParser<'a, 'b>,
         BoxedNodeParser<'a, 'b>,
         BoxedNodeParser<'a, 'b>,
-        InfixBuilderImpl,
+        RcInfixBuilder,
         PrefixBuilderImpl,
         PostfixBuilderImpl,
         SyntaxNode,
@@ -53,6 +55,8 @@ pub type PrattParserType<'a, 'b> = PrattParser<
 
 pub type RcPrattParserType<'a, 'b> = RcOrWeak<PrattParserType<'a, 'b>>;
 
+pub type RcInfixBuilder = Rc<dyn InfixBuilder<SyntaxNode, SyntaxNode>>;
+
 pub struct InfixBuilderImpl {
     pub name: SyntaxNodeNameId,
 }
@@ -3,7 +3,12 @@ pub mod built_in_rules;
 pub mod calculus_rules;
 pub mod collections_rules;
 pub mod comparison_rules;
+pub mod complex_rules;
 pub mod core_rules;
+pub mod declarative_rules;
+pub mod definition_rules;
 pub mod function_rules;
+pub mod linear_algebra_rules;
 pub mod logic_rules;
 pub mod string_rules;
+pub mod unit_rules;
@@ -1,31 +1,45 @@
-use crate::make_parser::just_operator_parser;
+use std::rc::Rc;
+
+use crate::locale::Locale;
+use crate::make_parser::{just_operator_parser, MakeParser, MakeParserFn};
 use crate::parse_module::*;
 use crate::parse_modules::ParseModules;
 use crate::parser_extensions::just_symbol;
-use crate::syntax_tree::{LeafNodeType, SyntaxNodeBuilder};
+use crate::rule_collection::{BasicParserExtra, ParserInput};
+use crate::syntax_tree::{
+    LeafNodeType, SyntaxNode, SyntaxNodeBuilder, SyntaxNodeChildren, SyntaxNodeNameId,
+};
+use crate::type_checker::{Type, TypeSignature};
 use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
-use chumsky::{prelude::*, Parser};
+use chumsky::{prelude::*, span::SimpleSpan, Parser};
+use input_tree::grid::GridVec;
 use input_tree::input_nodes;
-use input_tree::node::InputNode;
+use input_tree::node::{InputNode, InputNodeVariant};
+use input_tree::row::InputRow;
+
+use super::built_in_rules::BuiltInRules;
 
 /// Rules for basic arithmetic.
 pub struct ArithmeticRules {
     module_name: String,
     rules: Vec<ParseRule>,
     autocomplete_rules: Vec<AutocompleteRule>,
+    type_signatures: Vec<TypeSignature>,
 }
 
 impl ArithmeticRules {
-    pub fn new(modules: &mut ParseModules) -> Self {
-        let rules = Self::get_rules(modules);
+    pub fn new(modules: &mut ParseModules, built_in_rules: &BuiltInRules, locale: &Locale) -> Self {
+        let rules = Self::get_rules(modules, built_in_rules, locale);
         let autocomplete_rules = Self::get_autocomplete_rules();
+        let type_signatures = Self::get_type_signatures(modules);
         Self {
             module_name: "Arithmetic".into(),
             rules,
             autocomplete_rules,
+            type_signatures,
         }
     }
-    fn rule_name(name: &str) -> PathIdentifier {
+    pub(crate) fn rule_name(name: &str) -> PathIdentifier {
         PathIdentifier::new(vec!["Arithmetic".into(), name.into()])
     }
 }
@@ -42,33 +56,25 @@ impl ParseModule for ArithmeticRules {
     fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
         &self.autocomplete_rules
     }
+
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &self.type_signatures
+    }
 }
 
 impl ArithmeticRules {
-    fn get_rules(modules: &mut ParseModules) -> Vec<ParseRule> {
+    fn get_rules(
+        modules: &mut ParseModules,
+        built_in_rules: &BuiltInRules,
+        locale: &Locale,
+    ) -> Vec<ParseRule> {
+        let number_rule_name = modules.with_rule_name(Self::rule_name("Number"));
         vec![
+            atom_rule(number_rule_name, make_number_parser(locale)),
+            // Takes priority over a normal number followed by a multiplication
             atom_rule(
-                modules.with_rule_name(Self::rule_name("Number")),
-                crate::make_parser::MakeParserFn(|_| {
-                    let digits = select! {
-                      InputNode::Symbol(a) if a.chars().all(|v| v.is_ascii_digit()) => a,
-                    }
-                    .repeated()
-                    .at_least(1)
-                    .collect::<Vec<_>>();
-
-                    digits
-                        .then(just_symbol(".").then(digits).or_not())
-                        .map(|(mut a, b)| {
-                            if let Some((c, d)) = b {
-                                a.push(c);
-                                a.extend(d);
-                            }
-                            a
-                        })
-                        .map(|v| SyntaxNodeBuilder::new_leaf_node(v, LeafNodeType::Symbol))
-                        .boxed()
-                }),
+                modules.with_rule_name(Self::rule_name("ScientificNumber")),
+                make_scientific_number_parser(built_in_rules, number_rule_name, locale),
             ),
             left_infix_rule(
                 modules.with_rule_name(Self::rule_name("Add")),
@@ -95,6 +101,11 @@ impl ArithmeticRules {
                 200,
                 just_operator_parser("*"),
             ),
+            left_infix_rule(
+                modules.with_rule_name(Self::rule_name("Multiply")),
+                200,
+                just_operator_parser("·"),
+            ),
             left_infix_rule(
                 modules.with_rule_name(Self::rule_name("Divide")),
                 200,
@@ -115,8 +126,315 @@ impl ArithmeticRules {
 
     fn get_autocomplete_rules() -> Vec<crate::autocomplete::AutocompleteRule> {
         vec![
-            AutocompleteRule::new("/", input_nodes! {(fraction (row), (row))}),
-            AutocompleteRule::new("sqrt", input_nodes! {(root (row), (row))}),
+            AutocompleteRule::new("sqrt", input_nodes! {(root (row), (row))})
+                .with_description("Square root"),
         ]
     }
+
+    fn get_type_signatures(modules: &mut ParseModules) -> Vec<TypeSignature> {
+        use Type::*;
+        let add = modules.with_rule_name(Self::rule_name("Add"));
+        let subtract = modules.with_rule_name(Self::rule_name("Subtract"));
+        let multiply = modules.with_rule_name(Self::rule_name("Multiply"));
+        let divide = modules.with_rule_name(Self::rule_name("Divide"));
+        let mut signatures = vec![
+            TypeSignature::new(
+                modules.with_rule_name(Self::rule_name("Number")),
+                vec![],
+                Number,
+            ),
+            TypeSignature::variadic(
+                modules.with_rule_name(Self::rule_name("ScientificNumber")),
+                vec![Number],
+                Number,
+            ),
+            TypeSignature::new(
+                modules.with_rule_name(Self::rule_name("Factorial")),
+                vec![Number],
+                Number,
+            ),
+            TypeSignature::new(multiply, vec![Number, Number], Number),
+            TypeSignature::new(multiply, vec![Number, Vector], Vector),
+            TypeSignature::new(multiply, vec![Vector, Number], Vector),
+            TypeSignature::new(multiply, vec![Number, Matrix], Matrix),
+            TypeSignature::new(multiply, vec![Matrix, Number], Matrix),
+            TypeSignature::new(multiply, vec![Matrix, Matrix], Matrix),
+            TypeSignature::new(multiply, vec![Matrix, Vector], Vector),
+            TypeSignature::new(divide, vec![Number, Number], Number),
+            TypeSignature::new(divide, vec![Vector, Number], Vector),
+            TypeSignature::new(divide, vec![Matrix, Number], Matrix),
+        ];
+        // Both the infix and the prefix versions
+        for name in [add, subtract] {
+            for v in [Number, Vector, Matrix] {
+                signatures.push(TypeSignature::new(name, vec![v, v], v));
+                signatures.push(TypeSignature::new(name, vec![v], v));
+            }
+        }
+        signatures
+    }
+}
+
+/// Separators for digit groups, like in 1 000 000 or 1_000_000
+const DIGIT_GROUP_SEPARATORS: [&str; 3] = ["\u{2009}", "\u{202F}", "_"];
+
+/// The signs of an exponent, like in 1.5e-3
+const SIGNS: [&str; 2] = ["+", "-"];
+
+/// The multiplication in 1.5·10⁻³
+const SCIENTIFIC_TIMES: [&str; 3] = ["·", "⋅", "×"];
+
+/// A number is parsed into its symbols and a normalized value.
+pub(crate) type NumberParts = (Vec<String>, String);
+
+/// Digits in the given radix, optionally separated into groups.
+/// The normalized value leaves out the separators.
+fn grouped_digits_parser<'a>(
+    radix: u32,
+) -> impl Parser<'a, ParserInput<'a>, NumberParts, BasicParserExtra> {
+    let digits = move || {
+        select! {
+          InputNode::Symbol(a) if !a.is_empty() && a.chars().all(|v| v.is_digit(radix)) => a,
+        }
+        .repeated()
+        .at_least(1)
+        .collect::<Vec<_>>()
+    };
+    let separator = select! {
+      InputNode::Symbol(a) if DIGIT_GROUP_SEPARATORS.contains(&a.as_str()) => a,
+    };
+
+    digits()
+        .then(separator.then(digits()).repeated().collect::<Vec<_>>())
+        .map(|(first, groups)| {
+            let mut value = first.concat();
+            let mut symbols = first;
+            for (separator, group) in groups {
+                value.push_str(&group.concat());
+                symbols.push(separator);
+                symbols.extend(group);
+            }
+            (symbols, value)
+        })
+}
+
+/// A decimal number without an exponent, like 3.14 or 3,14
+fn decimal_parser<'a>(
+    decimal_separator: &'static str,
+) -> impl Parser<'a, ParserInput<'a>, NumberParts, BasicParserExtra> {
+    grouped_digits_parser(10)
+        .then(
+            just_symbol(decimal_separator)
+                .then(grouped_digits_parser(10))
+                .or_not(),
+        )
+        .map(|((mut symbols, mut value), fraction)| {
+            if let Some((separator, (fraction_symbols, fraction_value))) = fraction {
+                symbols.push(separator);
+                symbols.extend(fraction_symbols);
+                value.push('.');
+                value.push_str(&fraction_value);
+            }
+            (symbols, value)
+        })
+}
+
+/// The e-3 in 1.5e-3
+fn exponent_parser<'a>() -> impl Parser<'a, ParserInput<'a>, NumberParts, BasicParserExtra> {
+    select! {
+      InputNode::Symbol(a) if a == "e" || a == "E" => a,
+    }
+    .then(
+        select! {
+          InputNode::Symbol(a) if SIGNS.contains(&a.as_str()) => a,
+        }
+        .or_not(),
+    )
+    .then(grouped_digits_parser(10))
+    .map(|((e, sign), (digits_symbols, digits))| {
+        let mut value = "e".to_string();
+        value.push_str(normalize_sign(sign.as_deref()));
+        value.push_str(&digits);
+        let mut symbols = vec![e];
+        symbols.extend(sign);
+        symbols.extend(digits_symbols);
+        (symbols, value)
+    })
+}
+
+fn normalize_sign(sign: Option<&str>) -> &'static str {
+    match sign {
+        Some("-") => "-",
+        _ => "",
+    }
+}
+
+/// Numbers with a prefix, like 0x1F or 0b1010. Normalized to a decimal value.
+fn radix_number_parser<'a>(
+    prefix: &'static str,
+    radix: u32,
+) -> impl Parser<'a, ParserInput<'a>, NumberParts, BasicParserExtra> {
+    just_symbol("0")
+        .then(select! {
+          InputNode::Symbol(a) if a.eq_ignore_ascii_case(prefix) => a,
+        })
+        .then(grouped_digits_parser(radix))
+        .map(move |((zero, prefix), (digits_symbols, digits))| {
+            let mut symbols = vec![zero, prefix];
+            symbols.extend(digits_symbols);
+            (symbols, radix_to_decimal(&digits, radix))
+        })
+}
+
+/// Converts digits of any length, without overflowing.
+fn radix_to_decimal(digits: &str, radix: u32) -> String {
+    // Little endian decimal digits
+    let mut decimal: Vec<u32> = vec![0];
+    for digit in digits.chars().filter_map(|v| v.to_digit(radix)) {
+        let mut carry = digit;
+        for value in decimal.iter_mut() {
+            let next = *value * radix + carry;
+            *value = next % 10;
+            carry = next / 10;
+        }
+        while carry > 0 {
+            decimal.push(carry % 10);
+            carry /= 10;
+        }
+    }
+    let decimal: String = decimal
+        .iter()
+        .rev()
+        .filter_map(|v| char::from_digit(*v, 10))
+        .collect();
+    match decimal.trim_start_matches('0') {
+        "" => "0".to_string(),
+        v => v.to_string(),
+    }
+}
+
+/// Any number that can be written without containers.
+/// TODO: Repeating decimals, like 0.3 with an overline
+pub(crate) fn number_parser<'a>(
+    decimal_separator: &'static str,
+    e_notation: bool,
+) -> impl Parser<'a, ParserInput<'a>, NumberParts, BasicParserExtra> {
+    choice((
+        radix_number_parser("x", 16),
+        radix_number_parser("b", 2),
+        decimal_parser(decimal_separator)
+            .then(exponent_parser().filter(move |_| e_notation).or_not())
+            .map(|((mut symbols, mut value), exponent)| {
+                if let Some((exponent_symbols, exponent_value)) = exponent {
+                    symbols.extend(exponent_symbols);
+                    value.push_str(&exponent_value);
+                }
+                (symbols, value)
+            }),
+    ))
+}
+
+fn make_number_parser(locale: &Locale) -> impl MakeParser {
+    let decimal_separator = locale.decimal_separator();
+    let e_notation = locale.e_notation;
+    MakeParserFn(move |_| {
+        number_parser(decimal_separator, e_notation)
+            .map(|(symbols, value)| number_node(symbols, value))
+            .boxed()
+    })
+}
+
+pub(crate) fn number_node(symbols: Vec<String>, value: String) -> SyntaxNodeBuilder {
+    SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol).set_value(value.into_bytes())
+}
+
+/// The exponent of 10⁻³, if the superscript is a plain integer.
+pub(crate) fn signed_integer(grid: &GridVec<InputRow>) -> Option<String> {
+    let mut rows = grid.values();
+    let row = rows.next()?;
+    if rows.next().is_some() {
+        return None;
+    }
+    let mut symbols = row.values.iter().map(|v| match v {
+        InputNode::Symbol(a) => Some(a.as_str()),
+        _ => None,
+    });
+    let mut value = String::new();
+    let mut next = symbols.next()??;
+    if SIGNS.contains(&next) {
+        value.push_str(normalize_sign(Some(next)));
+        next = symbols.next()??;
+    }
+    for digit in std::iter::once(Some(next)).chain(symbols) {
+        let digit = digit?;
+        if !digit.chars().all(|v| v.is_ascii_digit()) {
+            return None;
+        }
+        value.push_str(digit);
+    }
+    Some(value)
+}
+
+/// A number like 1.5·10⁻³, where the exponent is a superscript.
+/// Keeps the structure of the notation, and stores the normalized value.
+fn make_scientific_number_parser(
+    built_in_rules: &BuiltInRules,
+    number_rule_name: SyntaxNodeNameId,
+    locale: &Locale,
+) -> impl MakeParser {
+    let operator_rule_name = built_in_rules.operator_rule_name;
+    let sup_rule_name = built_in_rules.sup_rule_name;
+    let sup_parser = Rc::new(built_in_rules.make_container_parser(InputNodeVariant::Sup));
+    let decimal_separator = locale.decimal_separator();
+    MakeParserFn(move |parser| {
+        let mantissa = decimal_parser(decimal_separator).map_with_span(
+            move |(symbols, value), span: SimpleSpan| {
+                (
+                    number_node(symbols, value.clone()).build(number_rule_name, span.into_range()),
+                    value,
+                )
+            },
+        );
+        let times = select! {
+          InputNode::Symbol(a) if SCIENTIFIC_TIMES.contains(&a.as_str()) => a,
+        }
+        .map_with_span(move |v, span: SimpleSpan| {
+            SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Operator)
+                .build(operator_rule_name, span.into_range())
+        });
+        let ten = just_symbol("1").then(just_symbol("0")).map_with_span(
+            move |(one, zero), span: SimpleSpan| {
+                number_node(vec![one, zero], "10".to_string())
+                    .build(number_rule_name, span.into_range())
+            },
+        );
+        let exponent = select_ref! {
+          InputNode::Container(InputNodeVariant::Sup, grid) if signed_integer(grid).is_some() => signed_integer(grid).unwrap(),
+        }
+        .rewind()
+        .then(
+            sup_parser
+                .build(parser)
+                .map_with_span(move |v, span: SimpleSpan| v.build(operator_rule_name, span.into_range())),
+        );
+
+        mantissa
+            .then(times)
+            .then(ten)
+            .then(exponent)
+            .map(
+                move |((((mantissa, mantissa_value), times), ten), (exponent_value, sup))| {
+                    let power = SyntaxNode::new(
+                        sup_rule_name,
+                        ten.range().start..sup.range().end,
+                        SyntaxNodeChildren::Children(vec![ten, sup]),
+                    );
+                    SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(vec![
+                        mantissa, times, power,
+                    ]))
+                    .set_value(format!("{}e{}", mantissa_value, exponent_value).into_bytes())
+                },
+            )
+            .boxed()
+    })
 }
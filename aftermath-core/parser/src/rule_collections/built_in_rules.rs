@@ -1,39 +1,63 @@
 use std::ops::Range;
 
+use crate::make_parser::just_symbol_parser;
 use crate::parse_module::*;
 use crate::parse_modules::ParseModules;
 use crate::parser::pratt_parser::{call_pratt_parser, Strength};
-use crate::rule_collection::BoxedNodeParser;
+use crate::rule_collection::{BoxedNodeParser, RcPrattParserType};
 use crate::syntax_tree::{
     LeafNodeType, SyntaxLeafNode, SyntaxNode, SyntaxNodeBuilder, SyntaxNodeChildren,
     SyntaxNodeNameId,
 };
+use crate::type_checker::{Type, TypeSignature};
 use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
 use chumsky::{prelude::*, Parser};
-use input_tree::grid::{Grid, GridVec};
+use input_tree::grid::{Grid, GridVec, Index2D};
 use input_tree::input_nodes;
-use input_tree::node::{InputNode, InputNodeVariant};
+use input_tree::node::{Fence, InputNode, InputNodeVariant};
+use input_tree::row::InputRow;
+
+/// Accents and braces above a base, with the name of their rule. The base is the first row of the container.
+const OVER_ACCENTS: [(&str, &str); 7] = [
+    ("Hat", "^"),
+    ("Bar", "‾"),
+    ("Vec", "→"),
+    ("Dot", "˙"),
+    ("DoubleDot", "¨"),
+    ("Tilde", "~"),
+    ("OverBrace", "⏞"),
+];
+
+/// Lines and braces below a base, with the name of their rule.
+const UNDER_ACCENTS: [(&str, &str); 2] = [("UnderBar", "_"), ("UnderBrace", "⏟")];
 
 pub struct BuiltInRules {
     module_name: String,
     rules: Vec<ParseRule>,
     autocomplete_rules: Vec<AutocompleteRule>,
+    type_signatures: Vec<TypeSignature>,
     /// Whenever a syntax tree has an operator, this can be used to wrap the operator leaf.
     /// Do note that some operators are relevant for the AST, such as a "function call" being an operator with arguments.
     pub operator_rule_name: SyntaxNodeNameId,
     /// Can have Whitespace nodes at the start and/or end.
-    whitespaces_rule_name: SyntaxNodeNameId,
+    pub whitespaces_rule_name: SyntaxNodeNameId,
     /// Whenever we encounter a space between tokens, this will be used.
-    whitespace_rule_name: SyntaxNodeNameId,
+    pub whitespace_rule_name: SyntaxNodeNameId,
     /// Whenever we encounter a subscript after an operator, this will be used.
     pub sub_rule_name: SyntaxNodeNameId,
     /// Whenever we encounter a superscript after an operator, this will be used.
     pub sup_rule_name: SyntaxNodeNameId,
-    error_missing_operator_name: SyntaxNodeNameId,
-    error_missing_token_name: SyntaxNodeNameId,
+    /// Variables are defined by the core rules, but the editor has to recognize them even without those.
+    pub identifier_rule_name: SyntaxNodeNameId,
+    /// Used for tables, including the ones that are part of a bigger construct.
+    pub table_rule_name: SyntaxNodeNameId,
+    /// Two operands without an operator between them, like 2x.
+    pub error_missing_operator_name: SyntaxNodeNameId,
+    /// An operand that was expected but not found.
+    pub error_missing_token_name: SyntaxNodeNameId,
     error_unknown_token_name: SyntaxNodeNameId,
     /// An empty node, this happens when a row is empty.
-    nothing_name: SyntaxNodeNameId,
+    pub nothing_name: SyntaxNodeNameId,
 }
 
 impl BuiltInRules {
@@ -43,6 +67,7 @@ impl BuiltInRules {
         let whitespace_rule_name = modules.with_rule_name(BuiltInRules::rule_name("Whitespace"));
         let sub_rule_name = modules.with_rule_name(BuiltInRules::rule_name("Sub"));
         let sup_rule_name = modules.with_rule_name(BuiltInRules::rule_name("Sup"));
+        let table_rule_name = modules.with_rule_name(BuiltInRules::rule_name("Table"));
         let error_missing_operator_name =
             modules.with_rule_name(BuiltInRules::error_rule_name("MissingOperator"));
         let error_missing_token_name =
@@ -50,18 +75,24 @@ impl BuiltInRules {
         let error_unknown_token_name =
             modules.with_rule_name(BuiltInRules::error_rule_name("UnknownToken"));
         let nothing_name = modules.with_rule_name(BuiltInRules::rule_name("Nothing"));
+        let identifier_rule_name =
+            modules.with_rule_name(PathIdentifier::new(vec!["Core".into(), "Variable".into()]));
 
         let rules = vec![];
         let autocomplete_rules = Self::get_autocomplete_rules();
+        let type_signatures = Self::get_type_signatures(modules);
         let mut self_obj = Self {
             module_name: "BuiltIn".into(),
             rules,
             autocomplete_rules,
+            type_signatures,
             operator_rule_name,
             whitespaces_rule_name,
             whitespace_rule_name,
             sub_rule_name,
             sup_rule_name,
+            identifier_rule_name,
+            table_rule_name,
             error_missing_operator_name,
             error_missing_token_name,
             error_unknown_token_name,
@@ -92,6 +123,10 @@ impl ParseModule for BuiltInRules {
     fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
         &self.autocomplete_rules
     }
+
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &self.type_signatures
+    }
 }
 
 impl BuiltInRules {
@@ -171,7 +206,7 @@ impl BuiltInRules {
         }
     }
 
-    fn nothing_node_with_name(name: SyntaxNodeNameId, position: usize) -> SyntaxNode {
+    pub(crate) fn nothing_node_with_name(name: SyntaxNodeNameId, position: usize) -> SyntaxNode {
         SyntaxNode::new(
             name,
             position..position,
@@ -183,32 +218,135 @@ impl BuiltInRules {
         &self,
         container_type: InputNodeVariant,
     ) -> impl crate::make_parser::MakeParser {
-        let nothing_node_name = self.nothing_name.clone();
+        let nothing_node_name = self.nothing_name;
         crate::make_parser::MakeParserFn(move |parser| {
-            let nothing_node_name = nothing_node_name.clone();
             select_ref! {
               InputNode::Container(c_type, a) if c_type == &container_type => a,
             }
+            .map(move |v| {
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::NewRows(Self::parse_rows(
+                    &parser,
+                    v,
+                    nothing_node_name,
+                )))
+            })
+            .boxed()
+        })
+    }
+
+    /// Parses a fenced group with exactly the given brackets.
+    /// The brackets are stored in the value of the node, so that they can be rendered.
+    pub fn make_fenced_parser(
+        &self,
+        open: Fence,
+        close: Fence,
+    ) -> impl crate::make_parser::MakeParser {
+        self.make_any_fenced_parser(move |o, c| o == open && c == close)
+    }
+
+    fn make_any_fenced_parser(
+        &self,
+        accepts: impl Fn(Fence, Fence) -> bool + Clone + 'static,
+    ) -> impl crate::make_parser::MakeParser {
+        let nothing_node_name = self.nothing_name;
+        crate::make_parser::MakeParserFn(move |parser| {
+            let accepts = accepts.clone();
+            select_ref! {
+              InputNode::Container(InputNodeVariant::Fenced(open, close), a) if accepts(*open, *close) => (*open, *close, a),
+            }
+            .map(move |(open, close, v)| {
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::NewRows(Self::parse_rows(
+                    &parser,
+                    v,
+                    nothing_node_name,
+                )))
+                .set_value(format!("{}{}", open.opening(), close.closing()).into_bytes())
+            })
+            .boxed()
+        })
+    }
+
+    /// An accent like the bar in x̄, where the annotation row is only the accent symbol.
+    /// The accent becomes an operator, instead of being parsed as an unknown token.
+    fn make_accent_parser(
+        &self,
+        container_type: InputNodeVariant,
+        accent: &'static str,
+    ) -> impl crate::make_parser::MakeParser {
+        let nothing_node_name = self.nothing_name;
+        let operator_rule_name = self.operator_rule_name;
+        crate::make_parser::MakeParserFn(move |parser| {
+            let accent_row = InputRow::new(vec![InputNode::Symbol(accent.into())]);
+            select_ref! {
+              InputNode::Container(c_type, a) if c_type == &container_type && a.values().nth(1) == Some(&accent_row) => a,
+            }
+            .map(move |v| {
+                let mut rows = Self::parse_rows(&parser, v, nothing_node_name);
+                let operator =
+                    SyntaxNodeBuilder::new_leaf_node(vec![accent.into()], LeafNodeType::Operator)
+                        .build(operator_rule_name, 0..1);
+                let index = Index2D::new(0, 1, rows.width());
+                rows.set(index, operator);
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::NewRows(rows))
+            })
+            .boxed()
+        })
+    }
+
+    /// Every row of a container is parsed on its own
+    fn parse_rows<'a>(
+        parser: &RcPrattParserType<'a, 'a>,
+        grid: &'a GridVec<InputRow>,
+        nothing_node_name: SyntaxNodeNameId,
+    ) -> GridVec<SyntaxNode> {
+        GridVec::from_one_dimensional(
+            grid.values()
+                .map(|row| {
+                    let p: BoxedNodeParser =
+                        call_pratt_parser(parser.clone(), (0, Strength::Weak), end().boxed())
+                            .boxed();
+                    let parsed = p.parse(&row.values);
+                    let (output, errors) = parsed.into_output_errors();
+                    // The pratt parser turns mistakes into error nodes, so this is a bug in a parse rule.
+                    // A broken row should not take down the whole editor.
+                    if !errors.is_empty() {
+                        log::warn!("Errors while parsing a row: {:?}", errors);
+                    }
+                    output.unwrap_or_else(|| Self::nothing_node_with_name(nothing_node_name, 0))
+                })
+                .collect(),
+            grid.width(),
+        )
+    }
+    /// Text is not parsed as math, so the whole row becomes a single leaf, including the spaces.
+    fn make_text_parser(
+        &self,
+        text_content_name: SyntaxNodeNameId,
+    ) -> impl crate::make_parser::MakeParser {
+        let nothing_node_name = self.nothing_name;
+        crate::make_parser::MakeParserFn(move |_| {
+            select_ref! {
+              InputNode::Container(InputNodeVariant::Text, a) if a.values().all(|row| row.values.iter().all(|v| matches!(v, InputNode::Symbol(_)))) => a,
+            }
             .map(move |v| {
                 let new_grid = GridVec::from_one_dimensional(
                     v.values()
                         .map(|row| {
-                            let p: BoxedNodeParser = call_pratt_parser(
-                                parser.clone(),
-                                (0, Strength::Weak),
-                                end().boxed(),
-                            )
-                            .boxed();
-                            let parsed = p.parse(&row.values);
-                            let (output, errors) = parsed.into_output_errors();
-                            let output = output.unwrap_or_else(|| {
-                                Self::nothing_node_with_name(nothing_node_name.clone(), 0)
-                            });
-                            // TODO: This should never happen
-                            if !errors.is_empty() {
-                                panic!("Errors: {:?}", errors);
+                            let symbols = row
+                                .values
+                                .iter()
+                                .filter_map(|v| match v {
+                                    InputNode::Symbol(symbol) => Some(symbol.clone()),
+                                    InputNode::Container(_, _) => None,
+                                })
+                                .collect::<Vec<_>>();
+                            if symbols.is_empty() {
+                                Self::nothing_node_with_name(nothing_node_name, 0)
+                            } else {
+                                let len = symbols.len();
+                                SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol)
+                                    .build(text_content_name, 0..len)
                             }
-                            output
                         })
                         .collect(),
                     v.width(),
@@ -219,7 +357,8 @@ impl BuiltInRules {
         })
     }
     fn make_rules(&self, modules: &mut ParseModules) -> Vec<ParseRule> {
-        vec![
+        let text_content_name = modules.with_rule_name(Self::rule_name("TextContent"));
+        let mut rules = vec![
             name_only_rule(self.operator_rule_name),
             name_only_rule(self.whitespaces_rule_name),
             name_only_rule(self.whitespace_rule_name),
@@ -248,16 +387,113 @@ impl BuiltInRules {
                 self.make_container_parser(InputNodeVariant::Sub),
             ),
             atom_rule(
-                modules.with_rule_name(Self::rule_name("Table")),
+                self.table_rule_name,
                 self.make_container_parser(InputNodeVariant::Table),
             ),
-        ]
+            // Accents, braces with labels and stacked arrows. Specific meanings, like a limit, are parsed by other modules.
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("Under")),
+                self.make_container_parser(InputNodeVariant::Under),
+            ),
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("Over")),
+                self.make_container_parser(InputNodeVariant::Over),
+            ),
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("UnderOver")),
+                self.make_container_parser(InputNodeVariant::UnderOver),
+            ),
+            // Scripts on both sides of a base, like isotopes or tensor indices
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("Multiscripts")),
+                self.make_container_parser(InputNodeVariant::Multiscripts),
+            ),
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("Placeholder")),
+                just_symbol_parser(InputNode::PLACEHOLDER),
+            ),
+            // Any brackets, the modules have more specific rules for the common ones
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("Fenced")),
+                self.make_any_fenced_parser(|_, _| true),
+            ),
+            name_only_rule(text_content_name),
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("Text")),
+                self.make_text_parser(text_content_name),
+            ),
+            // Prose that connects two formulas, like x = 0 for all x. Binds weaker than anything else.
+            left_infix_rule(
+                modules.with_rule_name(Self::rule_name("TextBetween")),
+                5,
+                self.make_text_parser(text_content_name),
+            ),
+        ];
+        // Later rules take priority, so the accents are tried before the plain containers
+        for (name, accent) in OVER_ACCENTS {
+            rules.push(atom_rule(
+                modules.with_rule_name(Self::rule_name(name)),
+                self.make_accent_parser(InputNodeVariant::Over, accent),
+            ));
+        }
+        for (name, accent) in UNDER_ACCENTS {
+            rules.push(atom_rule(
+                modules.with_rule_name(Self::rule_name(name)),
+                self.make_accent_parser(InputNodeVariant::Under, accent),
+            ));
+        }
+        rules
     }
 
     fn get_autocomplete_rules() -> Vec<AutocompleteRule> {
         vec![
-            AutocompleteRule::new("^", input_nodes! {(sup (row))}),
-            AutocompleteRule::new("_", input_nodes! {(sub (row))}),
+            AutocompleteRule::new("^", input_nodes! {(sup (row))}).with_description("Superscript"),
+            AutocompleteRule::new("_", input_nodes! {(sub (row))}).with_description("Subscript"),
+            AutocompleteRule::new("underset", input_nodes! {(under (row), (row))})
+                .with_description("Something written below"),
+            AutocompleteRule::new("overset", input_nodes! {(over (row), (row))})
+                .with_description("Something written above"),
+            AutocompleteRule::new("hat", input_nodes! {(over (row), (row "^"))})
+                .with_description("Hat accent"),
+            AutocompleteRule::new("bar", input_nodes! {(over (row), (row "‾"))})
+                .with_description("Bar accent"),
+            AutocompleteRule::new("vec", input_nodes! {(over (row), (row "→"))})
+                .with_description("Vector arrow"),
+            AutocompleteRule::new("overdot", input_nodes! {(over (row), (row "˙"))})
+                .with_description("Dot accent"),
+            AutocompleteRule::new("ddot", input_nodes! {(over (row), (row "¨"))})
+                .with_description("Double dot accent"),
+            AutocompleteRule::new("tilde", input_nodes! {(over (row), (row "~"))})
+                .with_description("Tilde accent"),
+            AutocompleteRule::new("overbrace", input_nodes! {(over (row), (row "⏞"))})
+                .with_description("Brace above"),
+            AutocompleteRule::new("underline", input_nodes! {(under (row), (row "_"))})
+                .with_description("Line below"),
+            AutocompleteRule::new("underbrace", input_nodes! {(under (row), (row "⏟"))})
+                .with_description("Brace below"),
+            AutocompleteRule::new("text", input_nodes! {(text (row))})
+                .with_description("Text inside a formula"),
+            AutocompleteRule::new(
+                "multiscripts",
+                input_nodes! {(multiscripts (row), (row), (row), (row), (row))},
+            ),
+        ]
+    }
+
+    fn get_type_signatures(modules: &mut ParseModules) -> Vec<TypeSignature> {
+        use Type::*;
+        let fraction = modules.with_rule_name(Self::rule_name("Fraction"));
+        let root = modules.with_rule_name(Self::rule_name("Root"));
+        let sup = modules.with_rule_name(Self::rule_name("Sup"));
+        let text = modules.with_rule_name(Self::rule_name("Text"));
+        vec![
+            TypeSignature::new(text, vec![Unknown], String),
+            TypeSignature::new(fraction, vec![Number, Number], Number),
+            // The index of a square root is empty
+            TypeSignature::new(root, vec![Unknown, Number], Number),
+            TypeSignature::new(sup, vec![Number, Number], Number),
+            TypeSignature::new(sup, vec![Matrix, Number], Matrix),
+            TypeSignature::new(sup, vec![Set, Number], Set),
         ]
     }
 }
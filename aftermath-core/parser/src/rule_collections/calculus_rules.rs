@@ -1,26 +1,52 @@
-use crate::make_parser::{just_operator_parser, just_symbol_parser};
+use std::rc::Rc;
+
+use crate::make_parser::{
+    closing_brackets_parser, just_operator_parser, just_symbol_parser, MakeParser, MakeParserFn,
+};
 
 use crate::parse_module::*;
 use crate::parse_modules::ParseModules;
-use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
+use crate::parser::pratt_parser::{
+    call_pratt_parser, call_pratt_parser_with_outer_ending, Strength,
+};
+use crate::parser_extensions::just_symbol;
+use crate::rule_collection::{BasicParserExtra, BoxedNodeParser, BoxedTokenParser, ParserInput};
+use crate::syntax_tree::{
+    LeafNodeType, SyntaxNode, SyntaxNodeBuilder, SyntaxNodeChildren, SyntaxNodeNameId,
+};
+use crate::type_checker::{Type, TypeSignature};
+use crate::{
+    autocomplete::{AutocompleteRule, TabStop},
+    syntax_tree::PathIdentifier,
+};
 
+use chumsky::{prelude::*, span::SimpleSpan, Parser};
+use input_tree::grid::{Grid, GridVec, Index2D};
 use input_tree::input_nodes;
+use input_tree::node::{InputNode, InputNodeVariant};
+use input_tree::row::{InputRow, Offset, RowIndex, RowIndices};
+
+use super::built_in_rules::BuiltInRules;
+use super::core_rules::identifier_parser;
 
 /// Rules for basic calculus.
 pub struct CalculusRules {
     module_name: String,
     rules: Vec<ParseRule>,
     autocomplete_rules: Vec<AutocompleteRule>,
+    type_signatures: Vec<TypeSignature>,
 }
 
 impl CalculusRules {
-    pub fn new(modules: &mut ParseModules) -> Self {
-        let rules = Self::get_rules(modules);
+    pub fn new(modules: &mut ParseModules, built_in_rules: &BuiltInRules) -> Self {
+        let rules = Self::get_rules(modules, built_in_rules);
         let autocomplete_rules = Self::get_autocomplete_rules();
+        let type_signatures = Self::get_type_signatures(modules);
         Self {
             module_name: "Calculus".into(),
             rules,
             autocomplete_rules,
+            type_signatures,
         }
     }
     fn rule_name(name: &str) -> PathIdentifier {
@@ -40,10 +66,18 @@ impl ParseModule for CalculusRules {
     fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
         &self.autocomplete_rules
     }
+
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &self.type_signatures
+    }
 }
 impl CalculusRules {
-    fn get_rules(modules: &mut ParseModules) -> Vec<ParseRule> {
-        vec![
+    fn get_rules(modules: &mut ParseModules, built_in_rules: &BuiltInRules) -> Vec<ParseRule> {
+        let derivative_rule_name = modules.with_rule_name(Self::rule_name("Derivative"));
+        let differential_rule_name = modules.with_rule_name(Self::rule_name("Differential"));
+        let variable_rule_name =
+            modules.with_rule_name(PathIdentifier::new(vec!["Core".into(), "Variable".into()]));
+        let mut rules = vec![
             atom_rule(
                 modules.with_rule_name(Self::rule_name("Infinity")),
                 just_symbol_parser("∞"),
@@ -63,28 +97,781 @@ impl CalculusRules {
                 100,
                 just_operator_parser(vec!['l', 'i', 'm', 'i', 'n', 'f']),
             ),
+            // The x → 0 can also be written under the limit, instead of in a subscript
             prefix_rule(
-                modules.with_rule_name(Self::rule_name("Sum")),
+                modules.with_rule_name(Self::rule_name("Lim")),
                 100,
-                just_operator_parser('∑'),
+                make_under_limit_parser(built_in_rules, "lim"),
             ),
             prefix_rule(
+                modules.with_rule_name(Self::rule_name("LimSup")),
+                100,
+                make_under_limit_parser(built_in_rules, "limsup"),
+            ),
+            prefix_rule(
+                modules.with_rule_name(Self::rule_name("LimInf")),
+                100,
+                make_under_limit_parser(built_in_rules, "liminf"),
+            ),
+            // The x → 0 under a limit
+            left_infix_rule(
+                modules.with_rule_name(Self::rule_name("Approaches")),
+                50,
+                just_operator_parser("→"),
+            ),
+            atom_rule(
                 modules.with_rule_name(Self::rule_name("Integral")),
+                make_integral_parser(built_in_rules, differential_rule_name, variable_rule_name),
+            ),
+            // Leibniz notation, takes priority over a normal fraction
+            atom_rule(
+                derivative_rule_name,
+                make_leibniz_parser(built_in_rules, differential_rule_name, false),
+            ),
+            // A d/dx on its own is an operator that applies to whatever comes after it
+            prefix_rule(
+                derivative_rule_name,
                 100,
-                just_operator_parser('∫'),
+                make_leibniz_parser(built_in_rules, differential_rule_name, true),
             ),
-        ]
+            name_only_rule(differential_rule_name),
+            // Lagrange notation, the order is stored in the operator
+            postfix_rule(derivative_rule_name, 900, make_primes_parser()),
+            // Newton notation, a variable with dots above it
+            atom_rule(derivative_rule_name, make_newton_parser()),
+        ];
+        let bound_index_rule_name = modules.with_rule_name(Self::rule_name("BoundIndex"));
+        rules.push(name_only_rule(bound_index_rule_name));
+        for (symbol, name) in BIG_OPERATORS {
+            rules.push(atom_rule(
+                modules.with_rule_name(Self::rule_name(name)),
+                make_big_operator_parser(
+                    symbol,
+                    built_in_rules,
+                    bound_index_rule_name,
+                    variable_rule_name,
+                ),
+            ));
+        }
+        rules
     }
 
     fn get_autocomplete_rules() -> Vec<AutocompleteRule> {
         vec![
-            AutocompleteRule::new("infinity", input_nodes! {"∞"}),
-            AutocompleteRule::new("lim", input_nodes! {"l", "i", "m"}),
-            AutocompleteRule::new("limsup", input_nodes! {"l", "i", "m", "s", "u", "p"}),
-            AutocompleteRule::new("liminf", input_nodes! {"l", "i", "m", "i", "n", "f"}),
-            AutocompleteRule::new("sum", input_nodes! {"∑"}),
-            AutocompleteRule::new("integral", input_nodes! {"∫"}),
-            AutocompleteRule::new("integrate", input_nodes! {"∫"}),
+            AutocompleteRule::new("infinity", input_nodes! {"∞"})
+                .with_label("infinity ∞")
+                .with_description("Infinity"),
+            AutocompleteRule::new("lim", input_nodes! {"l", "i", "m"}).with_description("Limit"),
+            AutocompleteRule::new("limsup", input_nodes! {"l", "i", "m", "s", "u", "p"})
+                .with_description("Limit superior"),
+            AutocompleteRule::new("liminf", input_nodes! {"l", "i", "m", "i", "n", "f"})
+                .with_description("Limit inferior"),
+            AutocompleteRule::new("->", input_nodes! {"→"})
+                .with_label("-> →")
+                .with_description("Tends to"),
+            AutocompleteRule::new("sum", input_nodes! {"∑"})
+                .with_label("sum ∑")
+                .with_description("Sum"),
+            AutocompleteRule::new("prod", input_nodes! {"∏"})
+                .with_label("prod ∏")
+                .with_description("Product"),
+            AutocompleteRule::new("product", input_nodes! {"∏"})
+                .with_label("product ∏")
+                .with_description("Product"),
+            AutocompleteRule::new("coprod", input_nodes! {"∐"})
+                .with_label("coprod ∐")
+                .with_description("Coproduct"),
+            AutocompleteRule::new("bigcup", input_nodes! {"⋃"})
+                .with_label("bigcup ⋃")
+                .with_description("Union of sets"),
+            AutocompleteRule::new("bigcap", input_nodes! {"⋂"})
+                .with_label("bigcap ⋂")
+                .with_description("Intersection of sets"),
+            // Tab goes from the lower bound to the upper bound, then the integrand and finally the variable
+            AutocompleteRule::new(
+                "integral",
+                input_nodes! {"∫", (sub (row)), (sup (row)), "⬚", "d", "⬚"},
+            )
+            .with_description("Integral with bounds")
+            .with_tab_stops(vec![
+                TabStop::new(
+                    "lower",
+                    RowIndices::new(vec![RowIndex(1, 0)]),
+                    Offset(0),
+                    Offset(0),
+                ),
+                TabStop::new(
+                    "upper",
+                    RowIndices::new(vec![RowIndex(2, 0)]),
+                    Offset(0),
+                    Offset(0),
+                ),
+                TabStop::new("integrand", RowIndices::default(), Offset(3), Offset(4)),
+                TabStop::new("variable", RowIndices::default(), Offset(5), Offset(6)),
+            ]),
+            AutocompleteRule::new("integrate", input_nodes! {"∫"})
+                .with_description("Integral sign"),
+            AutocompleteRule::new("iint", input_nodes! {"∬"})
+                .with_label("iint ∬")
+                .with_description("Double integral"),
+            AutocompleteRule::new("iiint", input_nodes! {"∭"})
+                .with_label("iiint ∭")
+                .with_description("Triple integral"),
+            AutocompleteRule::new("oint", input_nodes! {"∮"})
+                .with_label("oint ∮")
+                .with_description("Contour integral"),
         ]
     }
+
+    /// The bounds and the bound variables can be anything, only the results are known.
+    fn get_type_signatures(modules: &mut ParseModules) -> Vec<TypeSignature> {
+        use Type::*;
+        let mut signatures = vec![TypeSignature::new(
+            modules.with_rule_name(Self::rule_name("Infinity")),
+            vec![],
+            Number,
+        )];
+        for name in ["Lim", "LimSup", "LimInf", "Integral"] {
+            signatures.push(TypeSignature::variadic(
+                modules.with_rule_name(Self::rule_name(name)),
+                vec![Unknown],
+                Number,
+            ));
+        }
+        for (_, name) in BIG_OPERATORS {
+            let result = match name {
+                "BigUnion" | "BigIntersection" => Set,
+                _ => Number,
+            };
+            signatures.push(TypeSignature::variadic(
+                modules.with_rule_name(Self::rule_name(name)),
+                vec![Unknown],
+                result,
+            ));
+        }
+        signatures
+    }
+}
+
+/// Symbols that start a differential, like the d in dx
+const DIFFERENTIAL_SYMBOLS: [&str; 3] = ["d", "∂", "ⅆ"];
+
+/// Checks for a fraction that looks like d/dx, d²y/dx² or ∂f/∂x
+/// and returns the order of the derivative.
+/// The order is `None` if it isn't a plain number, like in dⁿ/dxⁿ.
+fn leibniz_order(grid: &GridVec<InputRow>) -> Option<Option<u8>> {
+    let mut rows = grid.values();
+    let numerator = &rows.next()?.values;
+    let denominator = &rows.next()?.values;
+
+    let differential = match numerator.first() {
+        Some(InputNode::Symbol(d)) if DIFFERENTIAL_SYMBOLS.contains(&d.as_str()) => d,
+        _ => return None,
+    };
+    let is_differential = match denominator.as_slice() {
+        [InputNode::Symbol(d), InputNode::Symbol(variable)]
+        | [InputNode::Symbol(d), InputNode::Symbol(variable), InputNode::Container(InputNodeVariant::Sup, _)] => {
+            d == differential && variable.chars().all(unicode_ident::is_xid_continue)
+        }
+        _ => false,
+    };
+    if !is_differential {
+        return None;
+    }
+
+    match numerator.get(1) {
+        Some(InputNode::Container(InputNodeVariant::Sup, sup)) => Some(
+            sup.values()
+                .next()
+                .and_then(|row| {
+                    row.values
+                        .iter()
+                        .map(|v| match v {
+                            InputNode::Symbol(v) => Some(v.as_str()),
+                            _ => None,
+                        })
+                        .collect::<Option<String>>()
+                })
+                .and_then(|v| v.parse::<u8>().ok()),
+        ),
+        _ => Some(Some(1)),
+    }
+}
+
+/// Parses a row that starts with a differential symbol, like "d²y" or "dx".
+fn make_differential_row_parser<'a>(
+    parser: crate::rule_collection::RcPrattParserType<'a, 'a>,
+    names: DifferentialNames,
+    sup_parser: &dyn MakeParser,
+) -> BoxedNodeParser<'a, 'a> {
+    let DifferentialNames {
+        operator_rule_name,
+        sup_rule_name,
+        differential_rule_name,
+    } = names;
+
+    let differential = select! {
+      InputNode::Symbol(d) if DIFFERENTIAL_SYMBOLS.contains(&d.as_str()) => d,
+    }
+    .map_with_span(move |d, span: SimpleSpan| {
+        SyntaxNodeBuilder::new_leaf_node(vec![d], LeafNodeType::Operator)
+            .build(operator_rule_name, span.into_range())
+    });
+    let sup = sup_parser
+        .build(parser.clone())
+        .map_with_span(move |v, span: SimpleSpan| v.build(operator_rule_name, span.into_range()));
+    let differential = differential.then(sup.or_not()).map_with_span(
+        move |(d, sup), span: SimpleSpan| match sup {
+            Some(sup) => SyntaxNode::new(
+                sup_rule_name,
+                span.into_range(),
+                SyntaxNodeChildren::Children(vec![d, sup]),
+            ),
+            None => d,
+        },
+    );
+
+    let rest = choice((
+        end().map(|_| None),
+        call_pratt_parser(parser, (0, Strength::Weak), end().boxed()).map(Some),
+    ));
+
+    differential
+        .then(rest)
+        .map_with_span(move |(d, rest), span: SimpleSpan| {
+            let mut children = vec![d];
+            children.extend(rest);
+            SyntaxNode::new(
+                differential_rule_name,
+                span.into_range(),
+                SyntaxNodeChildren::Children(children),
+            )
+        })
+        .boxed()
+}
+
+#[derive(Clone, Copy)]
+struct DifferentialNames {
+    operator_rule_name: SyntaxNodeNameId,
+    sup_rule_name: SyntaxNodeNameId,
+    differential_rule_name: SyntaxNodeNameId,
+}
+
+/// Checks if the numerator of a Leibniz fraction is only the differential, like in d/dx or d²/dx².
+fn is_leibniz_operator(grid: &GridVec<InputRow>) -> bool {
+    matches!(
+        grid.values().next().map(|row| row.values.as_slice()),
+        Some([_] | [_, InputNode::Container(InputNodeVariant::Sup, _)])
+    )
+}
+
+fn make_leibniz_parser(
+    built_in_rules: &BuiltInRules,
+    differential_rule_name: SyntaxNodeNameId,
+    is_operator: bool,
+) -> impl MakeParser {
+    let names = DifferentialNames {
+        operator_rule_name: built_in_rules.operator_rule_name,
+        sup_rule_name: built_in_rules.sup_rule_name,
+        differential_rule_name,
+    };
+    let nothing_name = built_in_rules.nothing_name;
+    let sup_parser = Rc::new(built_in_rules.make_container_parser(InputNodeVariant::Sup));
+    MakeParserFn(move |parser| {
+        let sup_parser = sup_parser.clone();
+        select_ref! {
+          InputNode::Container(InputNodeVariant::Fraction, grid) if leibniz_order(grid).is_some() && is_leibniz_operator(grid) == is_operator => grid,
+        }
+        .map(move |grid| {
+            let order = leibniz_order(grid).flatten();
+            let new_grid = GridVec::from_one_dimensional(
+                grid.values()
+                    .map(|row| {
+                        let p = make_differential_row_parser(parser.clone(), names, &*sup_parser);
+                        let (output, errors) = p.parse(&row.values).into_output_errors();
+                        // leibniz_order only checks the start of the row, the rest is user input.
+                        // A broken row should not take down the whole editor.
+                        if !errors.is_empty() {
+                            log::warn!("Errors while parsing a differential: {:?}", errors);
+                        }
+                        output.unwrap_or_else(|| {
+                            BuiltInRules::nothing_node_with_name(nothing_name, 0)
+                        })
+                    })
+                    .collect(),
+                grid.width(),
+            );
+            SyntaxNodeBuilder::new(SyntaxNodeChildren::NewRows(new_grid))
+                .set_value(order.map(|v| vec![v]).unwrap_or_default())
+        })
+        .boxed()
+    })
+}
+
+/// A limit that is the base of an under container. The base becomes the operator,
+/// and the annotation is parsed like the subscript of a limit.
+fn make_under_limit_parser(built_in_rules: &BuiltInRules, name: &'static str) -> impl MakeParser {
+    let operator_rule_name = built_in_rules.operator_rule_name;
+    let under_parser = built_in_rules.make_container_parser(InputNodeVariant::Under);
+    let symbols: Vec<String> = name.chars().map(String::from).collect();
+    MakeParserFn(move |parser| {
+        let base = InputRow::new(InputNode::symbols(symbols.clone()));
+        let under_limit = select_ref! {
+          InputNode::Container(InputNodeVariant::Under, grid) if grid.values().next() == Some(&base) => (),
+        }
+        .rewind();
+
+        let symbols = symbols.clone();
+        under_limit
+            .ignore_then(under_parser.build(parser))
+            .map(move |mut builder| {
+                if let SyntaxNodeChildren::NewRows(grid) = &mut builder.children {
+                    let operator =
+                        SyntaxNodeBuilder::new_leaf_node(symbols.clone(), LeafNodeType::Operator)
+                            .build(operator_rule_name, 0..symbols.len());
+                    grid.set(Index2D::new(0, 0, grid.width()), operator);
+                }
+                builder
+            })
+            .boxed()
+    })
+}
+
+fn make_primes_parser() -> impl MakeParser {
+    MakeParserFn(|_| {
+        let prime = select! {
+          InputNode::Symbol(a) if a == "'" || a == "′" => a,
+        };
+        prime
+            .repeated()
+            .at_least(1)
+            .collect::<Vec<_>>()
+            .map(|v| {
+                let order = v.len().min(u8::MAX as usize) as u8;
+                SyntaxNodeBuilder::new_leaf_node(vec![v.concat()], LeafNodeType::Operator)
+                    .set_value(vec![order])
+            })
+            .boxed()
+    })
+}
+
+/// Combining characters for ẋ, ẍ and x⃛
+fn newton_order(dots: &str) -> Option<u8> {
+    match dots {
+        "\u{0307}" => Some(1),
+        "\u{0308}" => Some(2),
+        "\u{20DB}" => Some(3),
+        _ => None,
+    }
+}
+
+fn make_newton_parser() -> impl MakeParser {
+    MakeParserFn(|_| {
+        select! {
+          InputNode::Symbol(a) if a.chars().next().map(unicode_ident::is_xid_start).unwrap_or(false)
+            && newton_order(&a[a.chars().next().unwrap().len_utf8()..]).is_some() => a,
+        }
+        .map(|v| {
+            let first_char_length = v.chars().next().unwrap().len_utf8();
+            let order = newton_order(&v[first_char_length..]).unwrap();
+            SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Symbol).set_value(vec![order])
+        })
+        .boxed()
+    })
+}
+
+/// Integral signs, including the multiple and the contour integrals.
+/// Paired with how many differentials they can bind.
+const INTEGRAL_SYMBOLS: [(&str, usize); 6] =
+    [("∫", 1), ("∬", 2), ("∭", 3), ("∮", 1), ("∯", 2), ("∰", 3)];
+
+/// A d, or an upright ⅆ, followed by a variable. Like the dx in an integral.
+fn differential_parser<'a>(
+    operator_rule_name: SyntaxNodeNameId,
+    differential_rule_name: SyntaxNodeNameId,
+    variable_rule_name: SyntaxNodeNameId,
+) -> impl Parser<'a, ParserInput<'a>, SyntaxNode, BasicParserExtra> + Clone {
+    select! {
+      InputNode::Symbol(d) if d == "d" || d == "ⅆ" => d,
+    }
+    .map_with_span(move |d, span: SimpleSpan| {
+        SyntaxNodeBuilder::new_leaf_node(vec![d], LeafNodeType::Operator)
+            .build(operator_rule_name, span.into_range())
+    })
+    .then(
+        select! {
+          InputNode::Symbol(v) if unicode_ident::is_xid_start(v.chars().next().unwrap_or(' ')) => v,
+        }
+        .map_with_span(move |v, span: SimpleSpan| {
+            SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Symbol)
+                .build(variable_rule_name, span.into_range())
+        }),
+    )
+    .map_with_span(move |(d, variable), span: SimpleSpan| {
+        SyntaxNode::new(
+            differential_rule_name,
+            span.into_range(),
+            SyntaxNodeChildren::Children(vec![d, variable]),
+        )
+    })
+}
+
+/// An integral sign with optional bounds, followed by the integrand and the differentials.
+/// The children are the sign, the lower bound, the upper bound, the integrand and the differentials.
+fn make_integral_parser(
+    built_in_rules: &BuiltInRules,
+    differential_rule_name: SyntaxNodeNameId,
+    variable_rule_name: SyntaxNodeNameId,
+) -> impl MakeParser {
+    let names = BoundsNames::new(built_in_rules);
+    let operator_rule_name = names.operator_rule_name;
+    let glued_names = GluedDifferentialNames {
+        operator_rule_name,
+        variable_rule_name,
+        differential_rule_name,
+    };
+    let sub_parser = Rc::new(built_in_rules.make_container_parser(InputNodeVariant::Sub));
+    let sup_parser = Rc::new(built_in_rules.make_container_parser(InputNodeVariant::Sup));
+    MakeParserFn(move |parser| {
+        let differential = differential_parser(
+            operator_rule_name,
+            differential_rule_name,
+            variable_rule_name,
+        );
+        let bounds = make_bounds_parser(
+            sub_parser.build(parser.clone()),
+            sup_parser.build(parser.clone()),
+            names,
+        );
+
+        // Without a differential, the integrand ends wherever the surrounding expression ends, like in (∫ f)
+        let integrand = call_pratt_parser_with_outer_ending(
+            parser.clone(),
+            (0, Strength::Weak),
+            differential.clone().map(|_| ()).boxed(),
+            closing_brackets_parser(),
+        );
+
+        // A double integral binds up to two differentials, but it's also fine to write a single dA
+        let integral_parsers = (1..=3)
+            .map(|differentials_count| {
+                select! {
+                  InputNode::Symbol(a) if INTEGRAL_SYMBOLS.contains(&(a.as_str(), differentials_count)) => a,
+                }
+                .map_with_span(move |v, span: SimpleSpan| {
+                    SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Operator)
+                        .build(operator_rule_name, span.into_range())
+                })
+                .then(bounds.clone())
+                .then(integrand.clone())
+                .then(
+                    differential
+                        .clone()
+                        .repeated()
+                        .at_most(differentials_count)
+                        .collect::<Vec<_>>(),
+                )
+                .map(move |(((sign, bounds), integrand), differentials)| {
+                    let (integrand, differentials) = if differentials.is_empty() {
+                        split_glued_differentials(integrand, differentials_count, glued_names)
+                    } else {
+                        (integrand, differentials)
+                    };
+                    let mut children = vec![sign];
+                    children.extend(names.ordered_bounds(&children[0], bounds));
+                    children.push(integrand);
+                    children.extend(differentials);
+                    SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(children))
+                })
+                .boxed()
+            })
+            .collect::<Vec<_>>();
+
+        choice(integral_parsers).boxed()
+    })
+}
+
+#[derive(Clone, Copy)]
+struct BoundsNames {
+    operator_rule_name: SyntaxNodeNameId,
+    nothing_rule_name: SyntaxNodeNameId,
+}
+
+impl BoundsNames {
+    fn new(built_in_rules: &BuiltInRules) -> Self {
+        Self {
+            operator_rule_name: built_in_rules.operator_rule_name,
+            nothing_rule_name: built_in_rules.nothing_name,
+        }
+    }
+
+    /// The lower and the upper bound, in that order. A missing bound becomes an empty node.
+    fn ordered_bounds(
+        &self,
+        sign: &SyntaxNode,
+        (lower, upper): (Option<SyntaxNode>, Option<SyntaxNode>),
+    ) -> [SyntaxNode; 2] {
+        let nothing = |position: usize| {
+            SyntaxNode::new(
+                self.nothing_rule_name,
+                position..position,
+                SyntaxNodeChildren::Children(vec![]),
+            )
+        };
+        let lower = lower.unwrap_or_else(|| nothing(sign.range().end));
+        let upper = upper.unwrap_or_else(|| nothing(lower.range().end));
+        [lower, upper]
+    }
+}
+
+/// A lower bound in a sub, followed by an upper bound in a sup. Both are optional.
+fn make_bounds_parser<'a>(
+    sub_parser: BoxedTokenParser<'a, 'a>,
+    sup_parser: BoxedTokenParser<'a, 'a>,
+    names: BoundsNames,
+) -> chumsky::Boxed<
+    'a,
+    'a,
+    ParserInput<'a>,
+    (Option<SyntaxNode>, Option<SyntaxNode>),
+    BasicParserExtra,
+> {
+    let operator_rule_name = names.operator_rule_name;
+    let bound = move |container_parser: BoxedTokenParser<'a, 'a>| {
+        container_parser
+            .map_with_span(move |v, span: SimpleSpan| {
+                v.build(operator_rule_name, span.into_range())
+            })
+            .or_not()
+    };
+    bound(sub_parser).then(bound(sup_parser)).boxed()
+}
+
+/// Splits the differentials off of a variable like the xdx in ∫xdx, where the differential got glued to the integrand.
+/// Only happens when the integral has no separate differential.
+fn split_glued_differentials(
+    integrand: SyntaxNode,
+    differentials_count: usize,
+    names: GluedDifferentialNames,
+) -> (SyntaxNode, Vec<SyntaxNode>) {
+    let mut integrand = integrand;
+    let mut differentials = vec![];
+    while differentials.len() < differentials_count {
+        match split_glued_differential(integrand, names) {
+            (node, Some(differential)) => {
+                integrand = node;
+                differentials.push(differential);
+            }
+            (node, None) => {
+                integrand = node;
+                break;
+            }
+        }
+    }
+    differentials.reverse();
+    (integrand, differentials)
+}
+
+#[derive(Clone, Copy)]
+struct GluedDifferentialNames {
+    operator_rule_name: SyntaxNodeNameId,
+    variable_rule_name: SyntaxNodeNameId,
+    differential_rule_name: SyntaxNodeNameId,
+}
+
+/// Looks at the last variable of a node, and splits a trailing differential off of it.
+fn split_glued_differential(
+    node: SyntaxNode,
+    names: GluedDifferentialNames,
+) -> (SyntaxNode, Option<SyntaxNode>) {
+    let GluedDifferentialNames {
+        operator_rule_name,
+        variable_rule_name,
+        differential_rule_name,
+    } = names;
+    let range = node.range();
+    let value = node.value;
+    match node.children {
+        SyntaxNodeChildren::Leaf(mut leaf) if node.name == variable_rule_name => {
+            let length = leaf.symbols.len();
+            let is_glued = length >= 3
+                && matches!(leaf.symbols[length - 2].as_str(), "d" | "ⅆ")
+                && unicode_ident::is_xid_start(
+                    leaf.symbols[length - 1].chars().next().unwrap_or(' '),
+                );
+            if !is_glued {
+                let mut node = SyntaxNode::new(node.name, range, SyntaxNodeChildren::Leaf(leaf));
+                node.value = value;
+                return (node, None);
+            }
+            let variable = leaf.symbols.split_off(length - 1);
+            let d = leaf.symbols.split_off(length - 2);
+            let split = range.end - 2;
+            let differential = SyntaxNode::new(
+                differential_rule_name,
+                split..range.end,
+                SyntaxNodeChildren::Children(vec![
+                    SyntaxNodeBuilder::new_leaf_node(d, LeafNodeType::Operator)
+                        .build(operator_rule_name, split..(split + 1)),
+                    SyntaxNodeBuilder::new_leaf_node(variable, LeafNodeType::Symbol)
+                        .build(variable_rule_name, (split + 1)..range.end),
+                ]),
+            );
+            let node = SyntaxNode::new(
+                variable_rule_name,
+                range.start..split,
+                SyntaxNodeChildren::Leaf(leaf),
+            );
+            (node, Some(differential))
+        }
+        SyntaxNodeChildren::Children(mut children) => {
+            let differential = match children.pop() {
+                Some(last) => {
+                    let (last, differential) = split_glued_differential(last, names);
+                    children.push(last);
+                    differential
+                }
+                None => None,
+            };
+            let end = children.last().map(|v| v.range().end).unwrap_or(range.end);
+            let end = if differential.is_some() {
+                end
+            } else {
+                range.end
+            };
+            let mut node = SyntaxNode::new(
+                node.name,
+                range.start..end,
+                SyntaxNodeChildren::Children(children),
+            );
+            node.value = value;
+            (node, differential)
+        }
+        children => {
+            let mut node = SyntaxNode::new(node.name, range, children);
+            node.value = value;
+            (node, None)
+        }
+    }
+}
+
+/// Big operators, which are written with an index variable in the subscript
+const BIG_OPERATORS: [(&str, &str); 5] = [
+    ("∑", "Sum"),
+    ("∏", "Product"),
+    ("∐", "Coproduct"),
+    ("⋃", "BigUnion"),
+    ("⋂", "BigIntersection"),
+];
+
+/// Parses the subscript of a big operator. Recognizes `n=0` as a bound index,
+/// and falls back to parsing any other expression, like in `∑_{i ∈ S}`.
+fn make_index_container_parser(
+    bound_index_rule_name: SyntaxNodeNameId,
+    operator_rule_name: SyntaxNodeNameId,
+    variable_rule_name: SyntaxNodeNameId,
+    nothing_name: SyntaxNodeNameId,
+) -> impl MakeParser {
+    MakeParserFn(move |parser| {
+        select_ref! {
+          InputNode::Container(InputNodeVariant::Sub, grid) => grid,
+        }
+        .map(move |grid| {
+            let new_grid = GridVec::from_one_dimensional(
+                grid.values()
+                    .map(|row| {
+                        let index_variable =
+                            identifier_parser().map_with_span(move |symbols, span: SimpleSpan| {
+                                SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol)
+                                    .build(variable_rule_name, span.into_range())
+                            });
+                        let equals = just_symbol("=").map_with_span(move |v, span: SimpleSpan| {
+                            SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Operator)
+                                .build(operator_rule_name, span.into_range())
+                        });
+                        let bound_index = index_variable
+                            .then(equals)
+                            .then(call_pratt_parser(
+                                parser.clone(),
+                                (0, Strength::Weak),
+                                end().boxed(),
+                            ))
+                            .map_with_span(move |((variable, equals), start), span: SimpleSpan| {
+                                SyntaxNode::new(
+                                    bound_index_rule_name,
+                                    span.into_range(),
+                                    SyntaxNodeChildren::Children(vec![variable, equals, start]),
+                                )
+                            });
+                        let p: BoxedNodeParser = choice((
+                            bound_index,
+                            call_pratt_parser(parser.clone(), (0, Strength::Weak), end().boxed()),
+                        ))
+                        .boxed();
+                        let (output, errors) = p.parse(&row.values).into_output_errors();
+                        // The bounds are user input. A broken row should not take down the whole editor.
+                        if !errors.is_empty() {
+                            log::warn!("Errors while parsing a bound: {:?}", errors);
+                        }
+                        output.unwrap_or_else(|| {
+                            BuiltInRules::nothing_node_with_name(nothing_name, 0)
+                        })
+                    })
+                    .collect(),
+                grid.width(),
+            );
+            SyntaxNodeBuilder::new(SyntaxNodeChildren::NewRows(new_grid))
+        })
+        .boxed()
+    })
+}
+
+/// A big operator with its bounds, followed by the operand.
+/// The children are the sign, the lower bound, the upper bound and the operand.
+/// The operand extends as far as the right side of a prefix operator with a binding power of 100 would.
+/// So `∑ i + 1` is `(∑ i) + 1`, while `∑ 2 i` keeps the multiplication.
+fn make_big_operator_parser(
+    symbol: &str,
+    built_in_rules: &BuiltInRules,
+    bound_index_rule_name: SyntaxNodeNameId,
+    variable_rule_name: SyntaxNodeNameId,
+) -> impl MakeParser {
+    let symbol = symbol.to_string();
+    let names = BoundsNames::new(built_in_rules);
+    let operator_rule_name = names.operator_rule_name;
+    let sub_parser = Rc::new(make_index_container_parser(
+        bound_index_rule_name,
+        operator_rule_name,
+        variable_rule_name,
+        built_in_rules.nothing_name,
+    ));
+    let sup_parser = Rc::new(built_in_rules.make_container_parser(InputNodeVariant::Sup));
+    MakeParserFn(move |parser| {
+        let bounds = make_bounds_parser(
+            sub_parser.build(parser.clone()),
+            sup_parser.build(parser.clone()),
+            names,
+        );
+        let operand = call_pratt_parser_with_outer_ending(
+            parser.clone(),
+            (100, Strength::Strong),
+            end().boxed(),
+            closing_brackets_parser(),
+        );
+
+        just_symbol(symbol.clone())
+            .map_with_span(move |v, span: SimpleSpan| {
+                SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Operator)
+                    .build(operator_rule_name, span.into_range())
+            })
+            .then(bounds)
+            .then(operand)
+            .map(move |((sign, bounds), operand)| {
+                let mut children = vec![sign];
+                children.extend(names.ordered_bounds(&children[0], bounds));
+                children.push(operand);
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(children))
+            })
+            .boxed()
+    })
 }
@@ -1,25 +1,35 @@
-use crate::make_parser::just_operator_parser;
+use crate::locale::Locale;
+use crate::make_parser::{just_operator_parser, MakeParser, MakeParserFn};
 
 use crate::parse_module::*;
 use crate::parse_modules::ParseModules;
+use crate::parser::pratt_parser::{call_pratt_parser, Strength};
+use crate::parser_extensions::just_symbol;
+use crate::syntax_tree::{SyntaxNodeBuilder, SyntaxNodeChildren};
+use crate::type_checker::{Type, TypeSignature};
 use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
+use chumsky::{prelude::*, Parser};
+use input_tree::node::InputNode;
 
-use input_tree::input_nodes;
+use super::logic_rules::{bound_variable_parser, operator_node, BoundVariableNames};
 
 pub struct CollectionsRules {
     module_name: String,
     rules: Vec<ParseRule>,
     autocomplete_rules: Vec<AutocompleteRule>,
+    type_signatures: Vec<TypeSignature>,
 }
 
 impl CollectionsRules {
-    pub fn new(modules: &mut ParseModules) -> Self {
-        let rules = Self::get_rules(modules);
+    pub fn new(modules: &mut ParseModules, locale: &Locale) -> Self {
+        let rules = Self::get_rules(modules, locale);
         let autocomplete_rules = Self::get_autocomplete_rules();
+        let type_signatures = Self::get_type_signatures(modules);
         Self {
             module_name: "Collections".into(),
             rules,
             autocomplete_rules,
+            type_signatures,
         }
     }
     fn rule_name(name: &str) -> PathIdentifier {
@@ -39,20 +49,85 @@ impl ParseModule for CollectionsRules {
     fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
         &self.autocomplete_rules
     }
+
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &self.type_signatures
+    }
 }
 impl CollectionsRules {
-    fn get_rules(modules: &mut ParseModules) -> Vec<ParseRule> {
-        vec![left_infix_rule(
-            modules.with_rule_name(Self::rule_name("Tuple")),
-            50,
-            just_operator_parser(','),
-        )]
+    fn get_rules(modules: &mut ParseModules, locale: &Locale) -> Vec<ParseRule> {
+        let names = BoundVariableNames {
+            operator_rule_name: modules.with_rule_name(PathIdentifier::new(vec![
+                "BuiltIn".into(),
+                "Operator".into(),
+            ])),
+            variable_rule_name: modules
+                .with_rule_name(PathIdentifier::new(vec!["Core".into(), "Variable".into()])),
+            bound_variable_rule_name: modules.with_rule_name(Self::rule_name("BoundVariable")),
+        };
+        vec![
+            left_infix_rule(
+                modules.with_rule_name(Self::rule_name("Tuple")),
+                50,
+                just_operator_parser(locale.tuple_separator()),
+            ),
+            name_only_rule(names.bound_variable_rule_name),
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("SetBuilder")),
+                make_set_builder_parser(names),
+            ),
+        ]
     }
 
     fn get_autocomplete_rules() -> Vec<AutocompleteRule> {
-        vec![
-            AutocompleteRule::new("vector", input_nodes! {(table 1 x 1 (row))}),
-            AutocompleteRule::new("matrix", input_nodes! {(table 1 x 1 (row))}),
-        ]
+        vec![]
     }
+
+    fn get_type_signatures(modules: &mut ParseModules) -> Vec<TypeSignature> {
+        vec![TypeSignature::variadic(
+            modules.with_rule_name(Self::rule_name("SetBuilder")),
+            vec![Type::Unknown],
+            Type::Set,
+        )]
+    }
+}
+
+/// Separates the bound variable from the condition, like in {x | P} or {x : P}
+const SET_BUILDER_SEPARATORS: [&str; 2] = ["|", ":"];
+
+/// Set-builder notation with a bound variable, an optional domain and a condition.
+/// For example {x ∈ S | P}.
+fn make_set_builder_parser(names: BoundVariableNames) -> impl MakeParser {
+    MakeParserFn(move |parser| {
+        let operator_rule_name = names.operator_rule_name;
+        let separator = select! {
+          InputNode::Symbol(a) if SET_BUILDER_SEPARATORS.contains(&a.as_str()) => a,
+        };
+        let bound_variable =
+            bound_variable_parser(parser.clone(), names, separator.map(|_| ()).boxed());
+
+        let condition = call_pratt_parser(
+            parser.clone(),
+            (0, Strength::Weak),
+            just_symbol("}").map(|_| ()).boxed(),
+        );
+
+        operator_node(just_symbol("{"), operator_rule_name)
+            .then(bound_variable)
+            .then(operator_node(separator, operator_rule_name))
+            .then(condition)
+            .then(operator_node(just_symbol("}"), operator_rule_name))
+            .map(
+                |((((open, bound_variable), separator), condition), close)| {
+                    SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(vec![
+                        open,
+                        bound_variable,
+                        separator,
+                        condition,
+                        close,
+                    ]))
+                },
+            )
+            .boxed()
+    })
 }
@@ -1,27 +1,43 @@
-use crate::make_parser::just_operator_parser;
+use crate::diagnostic::Diagnostic;
+use crate::make_parser::{closing_brackets_parser, just_operator_parser, MakeParser, MakeParserFn};
 
 use crate::parse_module::*;
 use crate::parse_modules::ParseModules;
+use crate::parser::pratt_parser::{call_pratt_parser_with_outer_ending, InfixBuilder, Strength};
+use crate::syntax_tree::{
+    LeafNodeType, SyntaxNode, SyntaxNodeBuilder, SyntaxNodeChildren, SyntaxNodeNameId,
+};
+use crate::type_checker::{Type, TypeSignature};
 use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
 
+use chumsky::{prelude::*, span::SimpleSpan, Parser};
+use input_tree::focus::MinimalInputRowRange;
 use input_tree::input_nodes;
+use input_tree::node::InputNode;
+use input_tree::row::{Offset, RowIndex, RowIndices};
 
 /// Rules for basic comparisons.
-/// Chains of < <= can be treated as "domain restrictions".
+/// Chains of < <= = are a single node, so that 0 < x ≤ 1 can be treated as a "domain restriction".
 pub struct ComparisonRules {
     module_name: String,
     rules: Vec<ParseRule>,
     autocomplete_rules: Vec<AutocompleteRule>,
+    type_signatures: Vec<TypeSignature>,
+    chain_names: ChainNames,
 }
 
 impl ComparisonRules {
     pub fn new(modules: &mut ParseModules) -> Self {
-        let rules = Self::get_rules(modules);
+        let chain_names = ChainNames::new(modules);
+        let rules = Self::get_rules(modules, &chain_names);
         let autocomplete_rules = Self::get_autocomplete_rules();
+        let type_signatures = Self::get_type_signatures(modules);
         Self {
             module_name: "Comparison".into(),
             rules,
             autocomplete_rules,
+            type_signatures,
+            chain_names,
         }
     }
     fn rule_name(name: &str) -> PathIdentifier {
@@ -41,9 +57,23 @@ impl ParseModule for ComparisonRules {
     fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
         &self.autocomplete_rules
     }
+
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &self.type_signatures
+    }
+
+    fn get_diagnostics(&self, node: &SyntaxNode) -> Vec<Diagnostic> {
+        self.check_chains(node)
+            .into_iter()
+            .map(|chain| Diagnostic {
+                range: chain.range,
+                message: "Mixes ascending and descending comparisons".into(),
+            })
+            .collect()
+    }
 }
 impl ComparisonRules {
-    fn get_rules(modules: &mut ParseModules) -> Vec<ParseRule> {
+    fn get_rules(modules: &mut ParseModules, chain_names: &ChainNames) -> Vec<ParseRule> {
         vec![
             left_infix_rule(
                 modules.with_rule_name(Self::rule_name("Equals")),
@@ -70,13 +100,327 @@ impl ComparisonRules {
                 50,
                 just_operator_parser('≤'),
             ),
+            // Takes priority over the single comparisons, but needs at least two operators
+            left_infix_rule_with_builder(
+                chain_names.chain,
+                50,
+                make_chain_parser(chain_names.operator),
+                ChainBuilder {
+                    names: *chain_names,
+                },
+            ),
         ]
     }
 
     fn get_autocomplete_rules() -> Vec<AutocompleteRule> {
         vec![
-            AutocompleteRule::new(">=", input_nodes! {"≥"}),
-            AutocompleteRule::new("<=", input_nodes! {"≤"}),
+            AutocompleteRule::new(">=", input_nodes! {"≥"})
+                .with_label(">= ≥")
+                .with_description("Greater than or equal"),
+            AutocompleteRule::new("<=", input_nodes! {"≤"})
+                .with_label("<= ≤")
+                .with_description("Less than or equal"),
         ]
     }
+
+    fn get_type_signatures(modules: &mut ParseModules) -> Vec<TypeSignature> {
+        let equals = modules.with_rule_name(Self::rule_name("Equals"));
+        let mut signatures: Vec<_> = Type::KNOWN
+            .into_iter()
+            .map(|v| TypeSignature::new(equals, vec![v, v], Type::Boolean))
+            .collect();
+        for name in [
+            "GreaterThan",
+            "LessThan",
+            "GreaterThanOrEquals",
+            "LessThanOrEquals",
+        ] {
+            signatures.push(TypeSignature::new(
+                modules.with_rule_name(Self::rule_name(name)),
+                vec![Type::Number, Type::Number],
+                Type::Boolean,
+            ));
+        }
+        signatures.push(TypeSignature::variadic(
+            modules.with_rule_name(Self::rule_name("Chain")),
+            vec![Type::Number],
+            Type::Boolean,
+        ));
+        signatures
+    }
+}
+
+/// The comparisons that can be chained, together with their direction.
+const ORDERINGS: [(&str, Direction); 4] = [
+    ("<", Direction::Ascending),
+    ("≤", Direction::Ascending),
+    (">", Direction::Descending),
+    ("≥", Direction::Descending),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+fn ordering_direction(symbol: &str) -> Option<Direction> {
+    ORDERINGS
+        .iter()
+        .find(|(v, _)| *v == symbol)
+        .map(|(_, direction)| *direction)
+}
+
+/// An equals sign can also be part of a chain, like in a < b = c. It does not have a direction.
+fn is_chainable(symbol: &str) -> bool {
+    symbol == "=" || ordering_direction(symbol).is_some()
+}
+
+/// Parses the "< b ≤" in a < b ≤ c. The first and the last operand are parsed by the pratt parser.
+/// The [`ChainBuilder`] then splices it into the chain node.
+fn make_chain_parser(operator_rule_name: SyntaxNodeNameId) -> impl MakeParser {
+    MakeParserFn(move |parser| {
+        let ordering = select! {
+          InputNode::Symbol(a) if is_chainable(&a) => a,
+        }
+        .map_with_span(move |v, span: SimpleSpan| {
+            SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Operator)
+                .build(operator_rule_name, span.into_range())
+        });
+        // Stops at the next comparison, which is stronger than an equals sign
+        let operand = call_pratt_parser_with_outer_ending(
+            parser,
+            (50, Strength::Strong),
+            end().boxed(),
+            closing_brackets_parser(),
+        );
+
+        ordering
+            .clone()
+            .then(
+                operand
+                    .then(ordering)
+                    .repeated()
+                    .at_least(1)
+                    .collect::<Vec<_>>(),
+            )
+            .map(|(first, rest)| {
+                let mut children = vec![first];
+                for (operand, ordering) in rest {
+                    children.push(operand);
+                    children.push(ordering);
+                }
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(children))
+            })
+            .boxed()
+    })
+}
+
+/// Builds a flat chain node, so that a < b ≤ c has five children.
+struct ChainBuilder {
+    names: ChainNames,
+}
+
+impl InfixBuilder<SyntaxNode, SyntaxNode> for ChainBuilder {
+    fn build(&self, op: SyntaxNode, children: (SyntaxNode, SyntaxNode)) -> SyntaxNode {
+        let (left, right) = children;
+        let mut children = vec![left];
+        children.extend(self.splice_operator(op));
+        children.push(right);
+        self.node(self.names.chain, children)
+    }
+}
+
+impl ChainBuilder {
+    /// Takes the operands out of the "< b ≤" operator.
+    fn splice_operator(&self, op: SyntaxNode) -> Vec<SyntaxNode> {
+        let is_operator = op.name == self.names.operator;
+        let is_spaced = op.name == self.names.whitespaces;
+        match op.children {
+            SyntaxNodeChildren::Children(children) if is_operator => children,
+            SyntaxNodeChildren::Children(children) if is_spaced => {
+                self.splice_spaced_operator(children)
+            }
+            _ => vec![op],
+        }
+    }
+
+    /// Keeps the spaces attached to the first and the last operator
+    fn splice_spaced_operator(&self, children: Vec<SyntaxNode>) -> Vec<SyntaxNode> {
+        let mut spaces_before = vec![];
+        let mut operator = None;
+        let mut spaces_after = vec![];
+        for child in children {
+            if operator.is_some() {
+                spaces_after.push(child);
+            } else if child.name == self.names.operator {
+                operator = Some(child);
+            } else {
+                spaces_before.push(child);
+            }
+        }
+        let Some(operator) = operator else {
+            return vec![self.node(self.names.whitespaces, spaces_before)];
+        };
+
+        let mut spliced = self.splice_operator(operator).into_iter();
+        let (first, last) = match (spliced.next(), spliced.next_back()) {
+            (Some(first), Some(last)) => (first, last),
+            (only, _) => {
+                spaces_before.extend(only);
+                spaces_before.extend(spaces_after);
+                return vec![self.node(self.names.whitespaces, spaces_before)];
+            }
+        };
+        let mut result = vec![self.with_spaces(spaces_before, first, vec![])];
+        result.extend(spliced);
+        result.push(self.with_spaces(vec![], last, spaces_after));
+        result
+    }
+
+    fn with_spaces(
+        &self,
+        mut spaces_before: Vec<SyntaxNode>,
+        node: SyntaxNode,
+        spaces_after: Vec<SyntaxNode>,
+    ) -> SyntaxNode {
+        if spaces_before.is_empty() && spaces_after.is_empty() {
+            return node;
+        }
+        spaces_before.push(node);
+        spaces_before.extend(spaces_after);
+        self.node(self.names.whitespaces, spaces_before)
+    }
+
+    fn node(&self, name: SyntaxNodeNameId, children: Vec<SyntaxNode>) -> SyntaxNode {
+        let start = children.iter().map(|v| v.range().start).min().unwrap_or(0);
+        let end = children
+            .iter()
+            .map(|v| v.range().end)
+            .max()
+            .unwrap_or(start);
+        SyntaxNode::new(name, start..end, SyntaxNodeChildren::Children(children))
+    }
+}
+
+/// A chain like 0 < x ≤ 1, with one more operand than operators.
+#[derive(Debug, Clone)]
+pub struct ComparisonChain<'a> {
+    pub operands: Vec<&'a SyntaxNode>,
+    pub operators: Vec<String>,
+}
+
+/// A chain like a < b > c, which does not say how a and c are related.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MixedChain {
+    pub range: MinimalInputRowRange,
+}
+
+#[derive(Clone, Copy)]
+struct ChainNames {
+    chain: SyntaxNodeNameId,
+    operator: SyntaxNodeNameId,
+    whitespaces: SyntaxNodeNameId,
+    whitespace: SyntaxNodeNameId,
+}
+
+impl ChainNames {
+    fn new(modules: &mut ParseModules) -> Self {
+        let mut rule_name = |module: &str, name: &str| {
+            modules.with_rule_name(PathIdentifier::new(vec![module.into(), name.into()]))
+        };
+        Self {
+            chain: rule_name("Comparison", "Chain"),
+            operator: rule_name("BuiltIn", "Operator"),
+            whitespaces: rule_name("BuiltIn", "Whitespaces"),
+            whitespace: rule_name("BuiltIn", "Whitespace"),
+        }
+    }
+}
+
+impl ComparisonRules {
+    /// Splits a chain node into its operands and operators, in reading order.
+    pub fn chain<'a>(&self, node: &'a SyntaxNode) -> Option<ComparisonChain<'a>> {
+        if node.name != self.chain_names.chain {
+            return None;
+        }
+        let SyntaxNodeChildren::Children(children) = &node.children else {
+            return None;
+        };
+        let mut chain = ComparisonChain {
+            operands: vec![],
+            operators: vec![],
+        };
+        for child in children {
+            self.collect_chain(child, &mut chain);
+        }
+        Some(chain)
+    }
+
+    fn collect_chain<'a>(&self, node: &'a SyntaxNode, chain: &mut ComparisonChain<'a>) {
+        let names = &self.chain_names;
+        let node = self.skip_whitespaces(node);
+        match &node.children {
+            SyntaxNodeChildren::Leaf(leaf) if node.name == names.operator => {
+                chain.operators.push(leaf.symbols.join(""));
+            }
+            _ => chain.operands.push(node),
+        }
+    }
+
+    /// Finds the chains that mix ascending and descending comparisons, including nested ones.
+    pub fn check_chains(&self, node: &SyntaxNode) -> Vec<MixedChain> {
+        let mut mixed_chains = vec![];
+        self.collect_mixed_chains(node, &mut RowIndices::default(), &mut mixed_chains);
+        mixed_chains
+    }
+
+    fn collect_mixed_chains(
+        &self,
+        node: &SyntaxNode,
+        row_indices: &mut RowIndices,
+        mixed_chains: &mut Vec<MixedChain>,
+    ) {
+        if let Some(chain) = self.chain(node) {
+            let mut directions = chain.operators.iter().filter_map(|v| ordering_direction(v));
+            let first = directions.next();
+            if directions.any(|v| Some(v) != first) {
+                let range = node.range();
+                mixed_chains.push(MixedChain {
+                    range: MinimalInputRowRange {
+                        row_indices: row_indices.clone(),
+                        start: Offset(range.start),
+                        end: Offset(range.end),
+                    },
+                });
+            }
+        }
+        match &node.children {
+            SyntaxNodeChildren::Children(children) => {
+                for child in children {
+                    self.collect_mixed_chains(child, row_indices, mixed_chains);
+                }
+            }
+            SyntaxNodeChildren::NewRows(rows) => {
+                for (index, row) in rows.values().enumerate() {
+                    row_indices.push(RowIndex(node.range().start, index));
+                    self.collect_mixed_chains(row, row_indices, mixed_chains);
+                    row_indices.pop();
+                }
+            }
+            SyntaxNodeChildren::Leaf(_) => {}
+        }
+    }
+
+    fn skip_whitespaces<'a>(&self, node: &'a SyntaxNode) -> &'a SyntaxNode {
+        match &node.children {
+            SyntaxNodeChildren::Children(children) if node.name == self.chain_names.whitespaces => {
+                children
+                    .iter()
+                    .find(|v| v.name != self.chain_names.whitespace)
+                    .unwrap_or(node)
+            }
+            _ => node,
+        }
+    }
 }
@@ -0,0 +1,367 @@
+use crate::locale::Locale;
+use crate::make_parser::{just_operator_parser, make_brackets_parser, MakeParser, MakeParserFn};
+use crate::parse_module::*;
+use crate::parse_modules::ParseModules;
+use crate::parser_extensions::just_symbol;
+use crate::rule_collection::{BasicParserExtra, ParserInput};
+use crate::syntax_tree::{
+    LeafNodeType, SyntaxNode, SyntaxNodeBuilder, SyntaxNodeChildren, SyntaxNodeNameId,
+};
+use crate::type_checker::{Type, TypeSignature};
+use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
+use chumsky::{prelude::*, span::SimpleSpan, Parser};
+use input_tree::grid::Grid;
+use input_tree::input_nodes;
+use input_tree::node::{Fence, InputNode, InputNodeVariant};
+
+use super::arithmetic_rules::{number_node, number_parser};
+use super::built_in_rules::BuiltInRules;
+use super::core_rules::identifier_parser;
+
+/// Rules for complex numbers. Optional, since it turns i into the imaginary unit.
+pub struct ComplexRules {
+    module_name: String,
+    rules: Vec<ParseRule>,
+    autocomplete_rules: Vec<AutocompleteRule>,
+    type_signatures: Vec<TypeSignature>,
+    literal_names: LiteralNames,
+}
+
+/// Which letter is the imaginary unit. The double-struck ⅈ is always accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImaginaryUnit {
+    #[default]
+    I,
+    /// Electrical engineers use i for the current.
+    J,
+}
+
+impl ImaginaryUnit {
+    fn symbol(&self) -> &'static str {
+        match self {
+            ImaginaryUnit::I => "i",
+            ImaginaryUnit::J => "j",
+        }
+    }
+}
+
+impl ComplexRules {
+    pub fn new(
+        modules: &mut ParseModules,
+        built_in_rules: &BuiltInRules,
+        locale: &Locale,
+        imaginary_unit: ImaginaryUnit,
+    ) -> Self {
+        let literal_names = LiteralNames::new(modules);
+        let rules = Self::get_rules(
+            modules,
+            built_in_rules,
+            locale,
+            imaginary_unit,
+            &literal_names,
+        );
+        let autocomplete_rules = Self::get_autocomplete_rules();
+        let type_signatures = Self::get_type_signatures(modules);
+        Self {
+            module_name: "Complex".into(),
+            rules,
+            autocomplete_rules,
+            type_signatures,
+            literal_names,
+        }
+    }
+    fn rule_name(name: &str) -> PathIdentifier {
+        PathIdentifier::new(vec!["Complex".into(), name.into()])
+    }
+}
+
+impl ParseModule for ComplexRules {
+    fn get_module_name(&self) -> &str {
+        &self.module_name
+    }
+
+    fn get_rules(&self) -> &[ParseRule] {
+        &self.rules
+    }
+
+    fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
+        &self.autocomplete_rules
+    }
+
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &self.type_signatures
+    }
+}
+
+impl ComplexRules {
+    fn get_rules(
+        modules: &mut ParseModules,
+        built_in_rules: &BuiltInRules,
+        locale: &Locale,
+        imaginary_unit: ImaginaryUnit,
+        literal_names: &LiteralNames,
+    ) -> Vec<ParseRule> {
+        let unit_symbol = imaginary_unit.symbol();
+        vec![
+            atom_rule(
+                literal_names.imaginary_unit,
+                MakeParserFn(move |_| {
+                    imaginary_unit_parser(unit_symbol)
+                        .map(|symbols| {
+                            SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol)
+                        })
+                        .boxed()
+                }),
+            ),
+            // Takes priority over a number followed by the imaginary unit
+            atom_rule(
+                literal_names.imaginary_number,
+                make_imaginary_number_parser(literal_names, locale, unit_symbol),
+            ),
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("Conjugate")),
+                make_conjugate_parser(),
+            ),
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("Modulus")),
+                make_modulus_parser(built_in_rules),
+            ),
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("Modulus")),
+                built_in_rules.make_fenced_parser(Fence::Vertical, Fence::Vertical),
+            ),
+            prefix_rule(
+                modules.with_rule_name(Self::rule_name("RealPart")),
+                100,
+                just_operator_parser(vec!['R', 'e']),
+            ),
+            prefix_rule(
+                modules.with_rule_name(Self::rule_name("ImaginaryPart")),
+                100,
+                just_operator_parser(vec!['I', 'm']),
+            ),
+            prefix_rule(
+                modules.with_rule_name(Self::rule_name("Argument")),
+                100,
+                just_operator_parser(vec!['a', 'r', 'g']),
+            ),
+        ]
+    }
+
+    fn get_autocomplete_rules() -> Vec<AutocompleteRule> {
+        vec![
+            AutocompleteRule::new("Re", input_nodes! {"R", "e"}).with_description("Real part"),
+            AutocompleteRule::new("Im", input_nodes! {"I", "m"}).with_description("Imaginary part"),
+            AutocompleteRule::new("arg", input_nodes! {"a", "r", "g"})
+                .with_description("Argument of a complex number"),
+        ]
+    }
+
+    fn get_type_signatures(modules: &mut ParseModules) -> Vec<TypeSignature> {
+        use Type::*;
+        let mut rule_name = |name: &str| modules.with_rule_name(Self::rule_name(name));
+        let modulus = rule_name("Modulus");
+        vec![
+            TypeSignature::new(rule_name("ImaginaryUnit"), vec![], Number),
+            TypeSignature::variadic(rule_name("ImaginaryNumber"), vec![Number], Number),
+            TypeSignature::variadic(rule_name("Conjugate"), vec![Number], Number),
+            TypeSignature::new(modulus, vec![Number], Number),
+            TypeSignature::new(modulus, vec![Vector], Number),
+            TypeSignature::new(modulus, vec![Matrix], Number),
+            TypeSignature::new(rule_name("RealPart"), vec![Number], Number),
+            TypeSignature::new(rule_name("ImaginaryPart"), vec![Number], Number),
+            TypeSignature::new(rule_name("Argument"), vec![Number], Number),
+        ]
+    }
+}
+
+/// |z|, except for a square table between bars, which is always a determinant.
+/// That way the result does not depend on the order of the complex and the linear algebra module.
+fn make_modulus_parser(built_in_rules: &BuiltInRules) -> impl MakeParser {
+    let brackets_parser = make_brackets_parser(built_in_rules.operator_rule_name, "|", "|");
+    MakeParserFn(move |parser| {
+        let determinant = just_symbol("|")
+            .then(select_ref! {
+              InputNode::Container(InputNodeVariant::Table, grid) if grid.width() == grid.height() => (),
+            })
+            .then(just_symbol("|"));
+        determinant
+            .not()
+            .rewind()
+            .ignore_then(brackets_parser.build(parser))
+            .boxed()
+    })
+}
+
+/// The imaginary unit, but only when it is not part of a longer name like "in".
+fn imaginary_unit_parser<'a>(
+    unit_symbol: &'static str,
+) -> impl Parser<'a, ParserInput<'a>, Vec<String>, BasicParserExtra> {
+    identifier_parser().filter(move |symbols: &Vec<String>| {
+        matches!(&symbols[..], [symbol] if symbol == unit_symbol || symbol == "ⅈ")
+    })
+}
+
+/// A number directly followed by the imaginary unit, like 3i.
+/// The value is the normalized imaginary part.
+fn make_imaginary_number_parser(
+    literal_names: &LiteralNames,
+    locale: &Locale,
+    unit_symbol: &'static str,
+) -> impl MakeParser {
+    let number_rule_name = literal_names.number;
+    let imaginary_unit_rule_name = literal_names.imaginary_unit;
+    let decimal_separator = locale.decimal_separator();
+    let e_notation = locale.e_notation;
+    MakeParserFn(move |_| {
+        number_parser(decimal_separator, e_notation)
+            .map_with_span(move |(symbols, value), span: SimpleSpan| {
+                (
+                    number_node(symbols, value.clone()).build(number_rule_name, span.into_range()),
+                    value,
+                )
+            })
+            .then(imaginary_unit_parser(unit_symbol).map_with_span(
+                move |symbols, span: SimpleSpan| {
+                    SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol)
+                        .build(imaginary_unit_rule_name, span.into_range())
+                },
+            ))
+            .map(|((number, value), unit)| {
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(vec![number, unit]))
+                    .set_value(value.into_bytes())
+            })
+            .boxed()
+    })
+}
+
+/// Combining characters for the bar in z̄
+const CONJUGATE_BARS: [char; 2] = ['\u{0304}', '\u{0305}'];
+
+/// A variable with a bar above it
+fn make_conjugate_parser() -> impl MakeParser {
+    MakeParserFn(|_| {
+        select! {
+          InputNode::Symbol(a) if {
+            let mut chars = a.chars();
+            chars.next().map(unicode_ident::is_xid_start).unwrap_or(false)
+              && chars.next().map(|v| CONJUGATE_BARS.contains(&v)).unwrap_or(false)
+              && chars.next().is_none()
+          } => a,
+        }
+        .map(|v| SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Symbol))
+        .boxed()
+    })
+}
+
+/// A complex number with a constant real and imaginary part, as normalized decimal strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComplexLiteral {
+    pub real: String,
+    pub imaginary: String,
+}
+
+/// The nodes that can be lowered to a complex literal.
+struct LiteralNames {
+    number: SyntaxNodeNameId,
+    imaginary_unit: SyntaxNodeNameId,
+    imaginary_number: SyntaxNodeNameId,
+    add: SyntaxNodeNameId,
+    subtract: SyntaxNodeNameId,
+    whitespaces: SyntaxNodeNameId,
+    whitespace: SyntaxNodeNameId,
+}
+
+impl LiteralNames {
+    fn new(modules: &mut ParseModules) -> Self {
+        let mut rule_name = |module: &str, name: &str| {
+            modules.with_rule_name(PathIdentifier::new(vec![module.into(), name.into()]))
+        };
+        Self {
+            number: rule_name("Arithmetic", "Number"),
+            imaginary_unit: rule_name("Complex", "ImaginaryUnit"),
+            imaginary_number: rule_name("Complex", "ImaginaryNumber"),
+            add: rule_name("Arithmetic", "Add"),
+            subtract: rule_name("Arithmetic", "Subtract"),
+            whitespaces: rule_name("BuiltIn", "Whitespaces"),
+            whitespace: rule_name("BuiltIn", "Whitespace"),
+        }
+    }
+}
+
+impl ComplexRules {
+    /// Lowers a + bi, a - bi, bi and a to a complex literal.
+    /// Returns nothing for anything that is not made of constants.
+    pub fn complex_literal(&self, node: &SyntaxNode) -> Option<ComplexLiteral> {
+        let names = &self.literal_names;
+        let leaf_value = |node: &SyntaxNode| String::from_utf8(node.value.clone()).ok();
+        let children = match &node.children {
+            SyntaxNodeChildren::Children(children) => &children[..],
+            _ => &[],
+        };
+
+        if node.name == names.number {
+            Some(ComplexLiteral {
+                real: leaf_value(node)?,
+                imaginary: "0".into(),
+            })
+        } else if node.name == names.imaginary_number {
+            Some(ComplexLiteral {
+                real: "0".into(),
+                imaginary: leaf_value(node)?,
+            })
+        } else if node.name == names.imaginary_unit {
+            Some(ComplexLiteral {
+                real: "0".into(),
+                imaginary: "1".into(),
+            })
+        } else if node.name == names.whitespaces {
+            let child = children.iter().find(|v| v.name != names.whitespace)?;
+            self.complex_literal(child)
+        } else if node.name == names.add || node.name == names.subtract {
+            let is_subtract = node.name == names.subtract;
+            match children {
+                [_, value] if is_subtract => self.complex_literal(value).map(|v| v.negate()),
+                [_, value] => self.complex_literal(value),
+                // Only a real part and an imaginary part can be joined, anything else needs an evaluator
+                [left, _, right] => {
+                    let left = self.complex_literal(left)?;
+                    let right = self.complex_literal(right)?;
+                    let right = if is_subtract { right.negate() } else { right };
+                    match (left.is_real(), right.is_imaginary()) {
+                        (true, true) => Some(ComplexLiteral {
+                            real: left.real,
+                            imaginary: right.imaginary,
+                        }),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl ComplexLiteral {
+    fn is_real(&self) -> bool {
+        self.imaginary == "0"
+    }
+
+    fn is_imaginary(&self) -> bool {
+        self.real == "0"
+    }
+
+    fn negate(self) -> Self {
+        let negate = |v: String| match v.strip_prefix('-') {
+            Some(v) => v.to_string(),
+            None if v == "0" => v,
+            None => format!("-{}", v),
+        };
+        Self {
+            real: negate(self.real),
+            imaginary: negate(self.imaginary),
+        }
+    }
+}
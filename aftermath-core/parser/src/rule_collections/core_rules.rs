@@ -1,12 +1,19 @@
-use crate::make_parser::{make_brackets_parser, make_empty_brackets_parser};
+use crate::make_parser::{make_brackets_parser, make_empty_brackets_parser, MakeParser};
 use crate::parse_module::*;
 use crate::parse_modules::ParseModules;
+use crate::type_checker::{Type, TypeSignature};
 
-use crate::syntax_tree::{LeafNodeType, SyntaxNodeBuilder};
+use crate::parser_extensions::just_symbol;
+use crate::rule_collection::{BasicParserExtra, ParserInput};
+use crate::syntax_tree::{
+    LeafNodeType, SyntaxNode, SyntaxNodeBuilder, SyntaxNodeChildren, SyntaxNodeNameId,
+};
 use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
 use chumsky::{prelude::*, Parser};
 
-use input_tree::node::InputNode;
+use input_tree::grid::{Grid, GridVec};
+use input_tree::input_nodes;
+use input_tree::node::{Fence, InputNode, InputNodeVariant};
 use unicode_ident::{is_xid_continue, is_xid_start};
 
 use super::built_in_rules::BuiltInRules;
@@ -16,16 +23,19 @@ pub struct CoreRules {
     module_name: String,
     rules: Vec<ParseRule>,
     autocomplete_rules: Vec<AutocompleteRule>,
+    type_signatures: Vec<TypeSignature>,
 }
 
 impl CoreRules {
     pub fn new(modules: &mut ParseModules, built_in_rules: &BuiltInRules) -> Self {
         let rules = Self::get_rules(modules, built_in_rules);
         let autocomplete_rules = Self::get_autocomplete_rules();
+        let type_signatures = Self::get_type_signatures(modules);
         Self {
             module_name: "Core".into(),
             rules,
             autocomplete_rules,
+            type_signatures,
         }
     }
     fn rule_name(name: &str) -> PathIdentifier {
@@ -44,6 +54,10 @@ impl ParseModule for CoreRules {
     fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
         &self.autocomplete_rules
     }
+
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &self.type_signatures
+    }
 }
 
 fn is_identifier_start(value: &str) -> bool {
@@ -56,28 +70,38 @@ fn is_identifier_continue(value: &str) -> bool {
     value.chars().all(is_xid_continue)
 }
 
+/// The symbols of a variable name, like "x" or "x1".
+/// Other modules can use this for bound variables, like the index of a sum.
+pub(crate) fn identifier_parser<'a>(
+) -> impl Parser<'a, ParserInput<'a>, Vec<String>, BasicParserExtra> + Clone {
+    select! {
+      InputNode::Symbol(a) if is_identifier_start(&a) => a,
+    }
+    .then(
+        select! {
+          InputNode::Symbol(a) if is_identifier_continue(&a) => a,
+        }
+        .repeated()
+        .collect::<Vec<_>>(),
+    )
+    .map(|v| {
+        let mut symbols = vec![v.0];
+        symbols.extend(v.1);
+        symbols
+    })
+}
+
 impl CoreRules {
     fn get_rules(modules: &mut ParseModules, built_in_rules: &BuiltInRules) -> Vec<ParseRule> {
         vec![
             atom_rule(
                 modules.with_rule_name(Self::rule_name("Variable")),
                 crate::make_parser::MakeParserFn(|_| {
-                    select! {
-                      InputNode::Symbol(a) if is_identifier_start(&a) => a,
-                    }
-                    .then(
-                        select! {
-                          InputNode::Symbol(a) if is_identifier_continue(&a) => a,
-                        }
-                        .repeated()
-                        .collect::<Vec<_>>(),
-                    )
-                    .map(|v| {
-                        let mut symbols = vec![v.0];
-                        symbols.extend(v.1);
-                        SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol)
-                    })
-                    .boxed()
+                    identifier_parser()
+                        .map(|symbols| {
+                            SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol)
+                        })
+                        .boxed()
                 }),
             ),
             // Amusingly, if someone defines the closing bracket as a postfix operator, it'll break the brackets
@@ -90,10 +114,103 @@ impl CoreRules {
                 modules.with_rule_name(Self::rule_name("RoundBrackets")),
                 make_empty_brackets_parser(built_in_rules.operator_rule_name, "(", ")"),
             ),
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("RoundBrackets")),
+                built_in_rules.make_fenced_parser(Fence::Round, Fence::Round),
+            ),
+            // Piecewise functions, a curly bracket followed by a table with (value, condition) rows
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("Piecewise")),
+                make_piecewise_parser(built_in_rules, CaseNames::new(modules)),
+            ),
+            name_only_rule(modules.with_rule_name(Self::rule_name("Cases"))),
+            name_only_rule(modules.with_rule_name(Self::rule_name("CaseValue"))),
+            name_only_rule(modules.with_rule_name(Self::rule_name("CaseCondition"))),
         ]
     }
 
     fn get_autocomplete_rules() -> Vec<AutocompleteRule> {
-        vec![]
+        vec![AutocompleteRule::new(
+            "cases",
+            input_nodes! {"{", (table 2 x 2 (row), (row), (row), (row))},
+        )]
+    }
+
+    fn get_type_signatures(modules: &mut ParseModules) -> Vec<TypeSignature> {
+        let round_brackets = modules.with_rule_name(Self::rule_name("RoundBrackets"));
+        Type::KNOWN
+            .into_iter()
+            .map(|v| TypeSignature::new(round_brackets, vec![v], v))
+            .collect()
     }
 }
+
+#[derive(Clone, Copy)]
+struct CaseNames {
+    cases: SyntaxNodeNameId,
+    value: SyntaxNodeNameId,
+    condition: SyntaxNodeNameId,
+}
+
+impl CaseNames {
+    fn new(modules: &mut ParseModules) -> Self {
+        Self {
+            cases: modules.with_rule_name(CoreRules::rule_name("Cases")),
+            value: modules.with_rule_name(CoreRules::rule_name("CaseValue")),
+            condition: modules.with_rule_name(CoreRules::rule_name("CaseCondition")),
+        }
+    }
+}
+
+/// Every row of the table is one case. The cells stay in the grid, so that they line up with the input,
+/// and get wrapped in a value or a condition node.
+fn make_piecewise_parser(built_in_rules: &BuiltInRules, names: CaseNames) -> impl MakeParser {
+    let operator_rule_name: SyntaxNodeNameId = built_in_rules.operator_rule_name;
+    let table_parser = built_in_rules.make_container_parser(InputNodeVariant::Table);
+    crate::make_parser::MakeParserFn(move |parser| {
+        // Only tables with exactly two columns are cases, the rest stay ordinary tables
+        let two_columns_table = select_ref! {
+          InputNode::Container(InputNodeVariant::Table, grid) if grid.width() == 2 => (),
+        }
+        .rewind();
+
+        just_symbol("{")
+            .map_with_span(|v, span| (v, span.into_range()))
+            .then(
+                two_columns_table
+                    .ignore_then(table_parser.build(parser))
+                    .map_with_span(|v, span| (v, span.into_range())),
+            )
+            .map(move |((bracket, bracket_span), (table, table_span))| {
+                let children = vec![
+                    SyntaxNodeBuilder::new_leaf_node(vec![bracket], LeafNodeType::Operator)
+                        .build(operator_rule_name, bracket_span),
+                    wrap_cases(table, names).build(names.cases, table_span),
+                ];
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(children))
+            })
+            .boxed()
+    })
+}
+
+fn wrap_cases(table: SyntaxNodeBuilder, names: CaseNames) -> SyntaxNodeBuilder {
+    let SyntaxNodeChildren::NewRows(grid) = table.children else {
+        unreachable!("A table always has new rows");
+    };
+    let width = grid.width();
+    let cells = grid
+        .into_iter()
+        .enumerate()
+        .map(|(index, cell)| {
+            let name = if index % width == 0 {
+                names.value
+            } else {
+                names.condition
+            };
+            SyntaxNode::new(name, cell.range(), SyntaxNodeChildren::Children(vec![cell]))
+        })
+        .collect();
+    SyntaxNodeBuilder::new(SyntaxNodeChildren::NewRows(GridVec::from_one_dimensional(
+        cells, width,
+    )))
+}
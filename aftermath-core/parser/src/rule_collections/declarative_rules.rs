@@ -0,0 +1,174 @@
+use std::fmt;
+
+use crate::make_parser::{just_operator_parser, just_symbol_parser};
+use crate::parse_module::*;
+use crate::parse_modules::ParseModules;
+use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
+use input_tree::node::InputNode;
+use serde::{Deserialize, Serialize};
+
+/// Rules that are described by data instead of code, so that new notation can be added at runtime.
+/// Only supports rules that match a fixed sequence of symbols, like ° or ⟶.
+pub struct DeclarativeRules {
+    module_name: String,
+    rules: Vec<ParseRule>,
+    autocomplete_rules: Vec<AutocompleteRule>,
+}
+
+/// The description of a module, usually loaded from a JSON file.
+/// ```json
+/// {
+///   "module_name": "Chemistry",
+///   "rules": [{ "name": "ReactsTo", "kind": "LeftInfix", "binding_power": 30, "symbols": ["⟶"] }],
+///   "autocomplete": [{ "trigger": "->", "result": ["⟶"] }]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleDescription {
+    pub module_name: String,
+    pub rules: Vec<RuleDescription>,
+    #[serde(default)]
+    pub autocomplete: Vec<AutocompleteDescription>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleDescription {
+    /// The rule name, the module name is added in front of it.
+    pub name: String,
+    pub kind: RuleKind,
+    /// Ignored for atoms. For comparison, + has 100 and * has 200.
+    #[serde(default)]
+    pub binding_power: u16,
+    /// The symbols that have to be typed, like ["l", "o", "g"]
+    pub symbols: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleKind {
+    Atom,
+    Prefix,
+    LeftInfix,
+    RightInfix,
+    Postfix,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutocompleteDescription {
+    pub trigger: String,
+    pub result: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum DeclarativeRulesError {
+    Json(serde_json::Error),
+    /// A rule without symbols would match an empty input.
+    MissingSymbols {
+        rule_name: String,
+    },
+}
+
+impl fmt::Display for DeclarativeRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeclarativeRulesError::Json(error) => {
+                write!(f, "Invalid module description: {}", error)
+            }
+            DeclarativeRulesError::MissingSymbols { rule_name } => {
+                write!(f, "The rule {} has no symbols", rule_name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DeclarativeRulesError {}
+
+impl From<serde_json::Error> for DeclarativeRulesError {
+    fn from(error: serde_json::Error) -> Self {
+        DeclarativeRulesError::Json(error)
+    }
+}
+
+impl DeclarativeRules {
+    pub fn new(
+        modules: &mut ParseModules,
+        description: ModuleDescription,
+    ) -> Result<Self, DeclarativeRulesError> {
+        let rules = Self::get_rules(modules, &description)?;
+        let autocomplete_rules = Self::get_autocomplete_rules(&description);
+        Ok(Self {
+            module_name: description.module_name,
+            rules,
+            autocomplete_rules,
+        })
+    }
+
+    pub fn from_json(
+        modules: &mut ParseModules,
+        json: &str,
+    ) -> Result<Self, DeclarativeRulesError> {
+        let description: ModuleDescription = serde_json::from_str(json)?;
+        Self::new(modules, description)
+    }
+}
+
+impl ParseModule for DeclarativeRules {
+    fn get_module_name(&self) -> &str {
+        &self.module_name
+    }
+
+    fn get_rules(&self) -> &[ParseRule] {
+        &self.rules
+    }
+
+    fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
+        &self.autocomplete_rules
+    }
+}
+
+impl DeclarativeRules {
+    fn get_rules(
+        modules: &mut ParseModules,
+        description: &ModuleDescription,
+    ) -> Result<Vec<ParseRule>, DeclarativeRulesError> {
+        description
+            .rules
+            .iter()
+            .map(|rule| {
+                if rule.symbols.iter().all(|v| v.is_empty()) {
+                    return Err(DeclarativeRulesError::MissingSymbols {
+                        rule_name: rule.name.clone(),
+                    });
+                }
+                let name = modules.with_rule_name(PathIdentifier::new(vec![
+                    description.module_name.clone(),
+                    rule.name.clone(),
+                ]));
+                let symbols = rule.symbols.clone();
+                let binding_power = rule.binding_power;
+                Ok(match rule.kind {
+                    RuleKind::Atom => atom_rule(name, just_symbol_parser(symbols)),
+                    RuleKind::Prefix => {
+                        prefix_rule(name, binding_power, just_operator_parser(symbols))
+                    }
+                    RuleKind::LeftInfix => {
+                        left_infix_rule(name, binding_power, just_operator_parser(symbols))
+                    }
+                    RuleKind::RightInfix => {
+                        right_infix_rule(name, binding_power, just_operator_parser(symbols))
+                    }
+                    RuleKind::Postfix => {
+                        postfix_rule(name, binding_power, just_operator_parser(symbols))
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn get_autocomplete_rules(description: &ModuleDescription) -> Vec<AutocompleteRule> {
+        description
+            .autocomplete
+            .iter()
+            .map(|v| AutocompleteRule::new(v.trigger.clone(), InputNode::symbols(v.result.clone())))
+            .collect()
+    }
+}
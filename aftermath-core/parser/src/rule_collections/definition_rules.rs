@@ -0,0 +1,340 @@
+use std::ops::Range;
+
+use crate::make_parser::{
+    closing_brackets_parser, just_operator_parser, make_brackets_parser,
+    make_empty_brackets_parser, MakeParser, MakeParserFn,
+};
+use crate::parse_module::*;
+use crate::parse_modules::ParseModules;
+use crate::parser::pratt_parser::{call_pratt_parser_with_outer_ending, Strength};
+use crate::parser_extensions::just_symbol;
+use crate::syntax_tree::{
+    LeafNodeType, SyntaxNode, SyntaxNodeBuilder, SyntaxNodeChildren, SyntaxNodeNameId,
+};
+use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
+use chumsky::{prelude::*, span::SimpleSpan, Parser};
+use input_tree::focus::MinimalInputRowRange;
+use input_tree::input_nodes;
+use input_tree::node::Fence;
+use input_tree::row::{Offset, RowIndex, RowIndices};
+
+use super::built_in_rules::BuiltInRules;
+use super::core_rules::identifier_parser;
+
+/// Rules for definitions, like x := 3 or f(x) = x^2.
+/// A definition is always a whole statement, and never nested in another expression.
+pub struct DefinitionRules {
+    module_name: String,
+    rules: Vec<ParseRule>,
+    autocomplete_rules: Vec<AutocompleteRule>,
+    names: DefinitionNames,
+}
+
+impl DefinitionRules {
+    pub fn new(modules: &mut ParseModules, built_in_rules: &BuiltInRules) -> Self {
+        let names = DefinitionNames::new(modules);
+        let rules = Self::get_rules(built_in_rules, &names);
+        let autocomplete_rules = Self::get_autocomplete_rules();
+        Self {
+            module_name: "Definition".into(),
+            rules,
+            autocomplete_rules,
+            names,
+        }
+    }
+}
+
+impl ParseModule for DefinitionRules {
+    fn get_module_name(&self) -> &str {
+        &self.module_name
+    }
+
+    fn get_rules(&self) -> &[ParseRule] {
+        &self.rules
+    }
+
+    fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
+        &self.autocomplete_rules
+    }
+}
+
+impl DefinitionRules {
+    fn get_rules(built_in_rules: &BuiltInRules, names: &DefinitionNames) -> Vec<ParseRule> {
+        // Weaker than everything else, so that P := a ∧ b ⟹ c defines P
+        vec![
+            left_infix_rule(names.define, 5, just_operator_parser(vec![':', '='])),
+            left_infix_rule(names.define, 5, just_operator_parser('≔')),
+            // Takes priority over an equation
+            atom_rule(
+                names.define,
+                make_function_definition_parser(built_in_rules, *names),
+            ),
+        ]
+    }
+
+    fn get_autocomplete_rules() -> Vec<AutocompleteRule> {
+        vec![AutocompleteRule::new(":=", input_nodes! {"≔"})
+            .with_label(":= ≔")
+            .with_description("Definition")]
+    }
+}
+
+/// A name that is defined by a statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub name: String,
+    /// The parameters of a function definition, or nothing when a constant is defined.
+    pub parameters: Option<Vec<String>>,
+    /// Where the defined name is written, for "go to definition".
+    pub name_range: MinimalInputRowRange,
+}
+
+/// A variable that is used somewhere, and should be defined somewhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub name: String,
+    pub range: MinimalInputRowRange,
+}
+
+/// The nodes that are needed to recognize a definition.
+#[derive(Clone, Copy)]
+struct DefinitionNames {
+    define: SyntaxNodeNameId,
+    function_application: SyntaxNodeNameId,
+    variable: SyntaxNodeNameId,
+    tuple: SyntaxNodeNameId,
+    operator: SyntaxNodeNameId,
+    whitespaces: SyntaxNodeNameId,
+    whitespace: SyntaxNodeNameId,
+}
+
+impl DefinitionNames {
+    fn new(modules: &mut ParseModules) -> Self {
+        let mut rule_name = |module: &str, name: &str| {
+            modules.with_rule_name(PathIdentifier::new(vec![module.into(), name.into()]))
+        };
+        Self {
+            define: rule_name("Definition", "Define"),
+            function_application: rule_name("Function", "FunctionApplication"),
+            variable: rule_name("Core", "Variable"),
+            tuple: rule_name("Collections", "Tuple"),
+            operator: rule_name("BuiltIn", "Operator"),
+            whitespaces: rule_name("BuiltIn", "Whitespaces"),
+            whitespace: rule_name("BuiltIn", "Whitespace"),
+        }
+    }
+
+    /// f(x, y) with only variables as arguments
+    fn function_head(&self, node: &SyntaxNode) -> Option<(String, Range<usize>, Vec<String>)> {
+        let names = self;
+        if node.name != names.function_application {
+            return None;
+        }
+        let [function, brackets] = children(node) else {
+            return None;
+        };
+        let function = self.skip_whitespaces(function);
+        let name = self.variable_name(function)?;
+
+        let brackets = self.skip_whitespaces(brackets);
+        let parameters = match (&brackets.children, children(brackets)) {
+            // A fenced group has the arguments in its row
+            (SyntaxNodeChildren::NewRows(rows), _) => self.parameters(rows.values().next()?)?,
+            (_, [_, _]) => vec![],
+            (_, [_, arguments, _]) => self.parameters(arguments)?,
+            _ => return None,
+        };
+        Some((name, function.range(), parameters))
+    }
+
+    fn parameters(&self, arguments: &SyntaxNode) -> Option<Vec<String>> {
+        let names = self;
+        let arguments = self.skip_whitespaces(arguments);
+        if arguments.name == names.tuple {
+            children(arguments)
+                .iter()
+                .map(|v| self.skip_whitespaces(v))
+                .filter(|v| v.name != names.operator)
+                .map(|v| self.variable_name(v))
+                .collect()
+        } else {
+            Some(vec![self.variable_name(arguments)?])
+        }
+    }
+
+    fn variable_name(&self, node: &SyntaxNode) -> Option<String> {
+        match &node.children {
+            SyntaxNodeChildren::Leaf(leaf) if node.name == self.variable => {
+                Some(leaf.symbols.join(""))
+            }
+            _ => None,
+        }
+    }
+
+    fn skip_whitespaces<'a>(&self, node: &'a SyntaxNode) -> &'a SyntaxNode {
+        if node.name != self.whitespaces {
+            return node;
+        }
+        children(node)
+            .iter()
+            .find(|v| v.name != self.whitespace)
+            .unwrap_or(node)
+    }
+}
+
+impl DefinitionRules {
+    /// Recognizes x := 3, f(x) := x and f(x) = x^2 as definitions.
+    /// Takes the root of a syntax tree, and returns nothing for equations like x = 3 or f(2) = 4.
+    pub fn definition(&self, node: &SyntaxNode) -> Option<Definition> {
+        let names = &self.names;
+        let node = names.skip_whitespaces(node);
+        let [left, _, _] = children(node) else {
+            return None;
+        };
+        if node.name != names.define {
+            return None;
+        }
+        let left = names.skip_whitespaces(left);
+
+        let (name, name_range, parameters) = match names.variable_name(left) {
+            Some(name) => (name, left.range(), None),
+            None => {
+                let (name, name_range, parameters) = names.function_head(left)?;
+                (name, name_range, Some(parameters))
+            }
+        };
+
+        Some(Definition {
+            name,
+            parameters,
+            name_range: MinimalInputRowRange {
+                row_indices: RowIndices::default(),
+                start: Offset(name_range.start),
+                end: Offset(name_range.end),
+            },
+        })
+    }
+
+    /// Every variable in the syntax tree, including the ones in nested rows.
+    pub fn references(&self, node: &SyntaxNode) -> Vec<Reference> {
+        let mut references = vec![];
+        self.collect_references(node, &mut RowIndices::default(), &mut references);
+        references
+    }
+
+    fn collect_references(
+        &self,
+        node: &SyntaxNode,
+        row_indices: &mut RowIndices,
+        references: &mut Vec<Reference>,
+    ) {
+        if let Some(name) = self.names.variable_name(node) {
+            let range = node.range();
+            references.push(Reference {
+                name,
+                range: MinimalInputRowRange {
+                    row_indices: row_indices.clone(),
+                    start: Offset(range.start),
+                    end: Offset(range.end),
+                },
+            });
+            return;
+        }
+        match &node.children {
+            SyntaxNodeChildren::Children(children) => {
+                for child in children {
+                    self.collect_references(child, row_indices, references);
+                }
+            }
+            SyntaxNodeChildren::NewRows(rows) => {
+                for (index, row) in rows.values().enumerate() {
+                    row_indices.push(RowIndex(node.range().start, index));
+                    self.collect_references(row, row_indices, references);
+                    row_indices.pop();
+                }
+            }
+            SyntaxNodeChildren::Leaf(_) => {}
+        }
+    }
+}
+
+fn children(node: &SyntaxNode) -> &[SyntaxNode] {
+    match &node.children {
+        SyntaxNodeChildren::Children(children) => children,
+        _ => &[],
+    }
+}
+
+/// f(x) = x^2 at the start of a row. The function head only has variables as parameters,
+/// so that f(2) = 4 stays an equation.
+fn make_function_definition_parser(
+    built_in_rules: &BuiltInRules,
+    names: DefinitionNames,
+) -> impl MakeParser {
+    let brackets_parser = make_brackets_parser(names.operator, "(", ")");
+    let empty_brackets_parser = make_empty_brackets_parser(names.operator, "(", ")");
+    let fenced_parser = built_in_rules.make_fenced_parser(Fence::Round, Fence::Round);
+    MakeParserFn(move |parser| {
+        let at_start = empty()
+            .map_with_span(|_, span: SimpleSpan| span.start)
+            .filter(|start| *start == 0);
+        let function = identifier_parser().map_with_span(move |symbols, span: SimpleSpan| {
+            SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol)
+                .build(names.variable, span.into_range())
+        });
+        let brackets = brackets_parser
+            .build(parser.clone())
+            .or(empty_brackets_parser.build(parser.clone()))
+            .or(fenced_parser.build(parser.clone()))
+            .map_with_span(move |v, span: SimpleSpan| v.build(names.operator, span.into_range()));
+        let head = function
+            .then(brackets)
+            .map_with_span(move |(function, brackets), span: SimpleSpan| {
+                SyntaxNode::new(
+                    names.function_application,
+                    span.into_range(),
+                    SyntaxNodeChildren::Children(vec![function, brackets]),
+                )
+            })
+            .filter(move |head| names.function_head(head).is_some());
+        let spaces = just_symbol(" ")
+            .repeated()
+            .at_least(1)
+            .collect::<Vec<_>>()
+            .map_with_span(move |symbols, span: SimpleSpan| {
+                SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Operator)
+                    .build(names.whitespace, span.into_range())
+            });
+        let equals =
+            spaces
+                .or_not()
+                .then(just_symbol("=").map_with_span(move |v, span: SimpleSpan| {
+                    SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Operator)
+                        .build(names.operator, span.into_range())
+                }));
+        let equals = equals.map_with_span(move |(spaces, equals), span: SimpleSpan| match spaces {
+            Some(spaces) => SyntaxNode::new(
+                names.whitespaces,
+                span.into_range(),
+                SyntaxNodeChildren::Children(vec![spaces, equals]),
+            ),
+            None => equals,
+        });
+        // As weak as a definition with :=
+        let body = call_pratt_parser_with_outer_ending(
+            parser,
+            (5, Strength::Strong),
+            end().boxed(),
+            closing_brackets_parser(),
+        );
+
+        at_start
+            .ignore_then(head)
+            .then(equals)
+            .then(body)
+            .map(|((head, equals), body)| {
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(vec![head, equals, body]))
+            })
+            .boxed()
+    })
+}
@@ -1,28 +1,44 @@
-use crate::make_parser::{make_brackets_parser, make_empty_brackets_parser};
+use crate::make_parser::{
+    closing_brackets_parser, just_operator_parser, make_brackets_parser,
+    make_empty_brackets_parser, MakeParser, MakeParserFn,
+};
 use crate::parse_module::*;
 use crate::parse_modules::ParseModules;
+use crate::parser::pratt_parser::{call_pratt_parser_with_outer_ending, Strength};
+use crate::parser_extensions::just_symbol;
+use crate::syntax_tree::{
+    LeafNodeType, SyntaxNode, SyntaxNodeBuilder, SyntaxNodeChildren, SyntaxNodeNameId,
+};
+use crate::type_checker::{Type, TypeSignature};
 
 use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
+use chumsky::{prelude::*, span::SimpleSpan, Parser};
+use input_tree::input_nodes;
+use input_tree::node::Fence;
 
 use super::built_in_rules::BuiltInRules;
+use super::core_rules::identifier_parser;
 
 pub struct FunctionRules {
     module_name: String,
     rules: Vec<ParseRule>,
     autocomplete_rules: Vec<AutocompleteRule>,
+    type_signatures: Vec<TypeSignature>,
 }
 
 impl FunctionRules {
     pub fn new(modules: &mut ParseModules, built_in_rules: &BuiltInRules) -> Self {
         let rules = Self::get_rules(modules, built_in_rules);
         let autocomplete_rules = Self::get_autocomplete_rules();
+        let type_signatures = Self::get_type_signatures(modules);
         Self {
             module_name: "Function".into(),
             rules,
             autocomplete_rules,
+            type_signatures,
         }
     }
-    fn rule_name(name: &str) -> PathIdentifier {
+    pub(crate) fn rule_name(name: &str) -> PathIdentifier {
         PathIdentifier::new(vec!["Function".into(), name.into()])
     }
 }
@@ -39,6 +55,10 @@ impl ParseModule for FunctionRules {
     fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
         &self.autocomplete_rules
     }
+
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &self.type_signatures
+    }
 }
 impl FunctionRules {
     fn get_rules(modules: &mut ParseModules, built_in_rules: &BuiltInRules) -> Vec<ParseRule> {
@@ -53,10 +73,136 @@ impl FunctionRules {
                 800,
                 make_empty_brackets_parser(built_in_rules.operator_rule_name, "(", ")"),
             ),
+            postfix_rule(
+                modules.with_rule_name(Self::rule_name("FunctionApplication")),
+                800,
+                built_in_rules.make_fenced_parser(Fence::Round, Fence::Round),
+            ),
+            // f ∘ g ∘ h is f ∘ (g ∘ h), and f ∘ g(x) is f ∘ (g(x))
+            right_infix_rule(
+                modules.with_rule_name(Self::rule_name("Compose")),
+                700,
+                just_operator_parser('∘'),
+            ),
+            name_only_rule(modules.with_rule_name(Self::rule_name("FunctionType"))),
+            // f: ℝ → ℝ. The arrow is only a function type after a colon, elsewhere it is the x → 0 of a limit.
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("TypeAnnotation")),
+                make_type_annotation_parser(TypeAnnotationNames {
+                    variable: built_in_rules.identifier_rule_name,
+                    operator: built_in_rules.operator_rule_name,
+                    function_type: modules.with_rule_name(Self::rule_name("FunctionType")),
+                }),
+            ),
+            // Weaker than a tuple, so that x, y ↦ x + y has two parameters.
+            // Stronger than an equals sign, so that f = x ↦ x² defines f.
+            right_infix_rule(
+                modules.with_rule_name(Self::rule_name("Lambda")),
+                45,
+                just_operator_parser('↦'),
+            ),
         ]
     }
 
     fn get_autocomplete_rules() -> Vec<AutocompleteRule> {
-        vec![]
+        vec![
+            AutocompleteRule::new("|->", input_nodes! {"↦"})
+                .with_label("|-> ↦")
+                .with_description("Maps to"),
+            AutocompleteRule::new("mapsto", input_nodes! {"↦"})
+                .with_label("mapsto ↦")
+                .with_description("Maps to"),
+            AutocompleteRule::new("circ", input_nodes! {"∘"})
+                .with_label("circ ∘")
+                .with_description("Function composition"),
+        ]
     }
+
+    /// A function can return anything. Multiplications like 2(x+1) are handled by the type checker.
+    fn get_type_signatures(modules: &mut ParseModules) -> Vec<TypeSignature> {
+        let function_application = modules.with_rule_name(Self::rule_name("FunctionApplication"));
+        vec![
+            TypeSignature::new(
+                modules.with_rule_name(Self::rule_name("Lambda")),
+                vec![Type::Unknown, Type::Unknown],
+                Type::Function,
+            ),
+            TypeSignature::new(
+                modules.with_rule_name(Self::rule_name("Compose")),
+                vec![Type::Function, Type::Function],
+                Type::Function,
+            ),
+            TypeSignature::new(function_application, vec![Type::Function], Type::Unknown),
+            TypeSignature::new(
+                function_application,
+                vec![Type::Function, Type::Unknown],
+                Type::Unknown,
+            ),
+        ]
+    }
+}
+
+#[derive(Clone, Copy)]
+struct TypeAnnotationNames {
+    variable: SyntaxNodeNameId,
+    operator: SyntaxNodeNameId,
+    function_type: SyntaxNodeNameId,
+}
+
+/// Parses a statement like f: ℝ → ℝ → ℝ, where the arrows are right-associative.
+/// Like a definition, it has to be at the start of a row, so that the colon of ∀x: P is not a type annotation.
+fn make_type_annotation_parser(names: TypeAnnotationNames) -> impl MakeParser {
+    MakeParserFn(move |parser| {
+        let at_start = empty()
+            .map_with_span(|_, span: SimpleSpan| span.start)
+            .filter(|start| *start == 0);
+        let variable = identifier_parser().map_with_span(move |symbols, span: SimpleSpan| {
+            SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol)
+                .build(names.variable, span.into_range())
+        });
+        let operator = |symbol: &'static str| {
+            just_symbol(symbol).map_with_span(move |v, span: SimpleSpan| {
+                SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Operator)
+                    .build(names.operator, span.into_range())
+            })
+        };
+        // := is a definition
+        let colon = operator(":").then_ignore(just_symbol("=").not().rewind());
+        // Stops at the next arrow
+        let operand = call_pratt_parser_with_outer_ending(
+            parser,
+            (50, Strength::Strong),
+            end().boxed(),
+            closing_brackets_parser(),
+        );
+
+        at_start
+            .ignore_then(variable)
+            .then(colon)
+            .then(operand.clone())
+            .then(operator("→").then(operand).repeated().collect::<Vec<_>>())
+            .map(move |(((variable, colon), first), rest)| {
+                let mut operands = vec![first];
+                let mut arrows = vec![];
+                for (arrow, operand) in rest {
+                    arrows.push(arrow);
+                    operands.push(operand);
+                }
+                let mut function_type = operands.pop().unwrap();
+                while let Some(arrow) = arrows.pop() {
+                    let left = operands.pop().unwrap();
+                    function_type = SyntaxNode::new(
+                        names.function_type,
+                        left.range().start..function_type.range().end,
+                        SyntaxNodeChildren::Children(vec![left, arrow, function_type]),
+                    );
+                }
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(vec![
+                    variable,
+                    colon,
+                    function_type,
+                ]))
+            })
+            .boxed()
+    })
 }
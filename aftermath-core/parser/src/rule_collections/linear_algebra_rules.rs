@@ -0,0 +1,373 @@
+use std::rc::Rc;
+
+use crate::make_parser::{just_operator_parser, MakeParser, MakeParserFn};
+use crate::parse_module::*;
+use crate::parse_modules::ParseModules;
+use crate::parser_extensions::just_symbol;
+use crate::syntax_tree::{LeafNodeType, SyntaxNodeBuilder, SyntaxNodeChildren, SyntaxNodeNameId};
+use crate::type_checker::{Type, TypeSignature};
+use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
+use chumsky::{prelude::*, span::SimpleSpan, Parser};
+use input_tree::grid::{Grid, GridVec};
+use input_tree::input_nodes;
+use input_tree::node::{InputNode, InputNodeVariant};
+use input_tree::row::InputRow;
+
+use super::built_in_rules::BuiltInRules;
+use super::core_rules::identifier_parser;
+
+/// Rules for vectors and matrices.
+pub struct LinearAlgebraRules {
+    module_name: String,
+    rules: Vec<ParseRule>,
+    autocomplete_rules: Vec<AutocompleteRule>,
+    type_signatures: Vec<TypeSignature>,
+}
+
+impl LinearAlgebraRules {
+    pub fn new(modules: &mut ParseModules, built_in_rules: &BuiltInRules) -> Self {
+        let rules = Self::get_rules(modules, built_in_rules);
+        let autocomplete_rules = Self::get_autocomplete_rules();
+        let type_signatures = Self::get_type_signatures(modules);
+        Self {
+            module_name: "LinearAlgebra".into(),
+            rules,
+            autocomplete_rules,
+            type_signatures,
+        }
+    }
+    fn rule_name(name: &str) -> PathIdentifier {
+        PathIdentifier::new(vec!["LinearAlgebra".into(), name.into()])
+    }
+}
+
+impl ParseModule for LinearAlgebraRules {
+    fn get_module_name(&self) -> &str {
+        &self.module_name
+    }
+
+    fn get_rules(&self) -> &[ParseRule] {
+        &self.rules
+    }
+
+    fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
+        &self.autocomplete_rules
+    }
+
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &self.type_signatures
+    }
+}
+
+/// The autocomplete can create matrices up to this size, like matrix3x3 or vector3.
+const MAX_AUTOCOMPLETE_SIZE: usize = 4;
+
+impl LinearAlgebraRules {
+    fn get_rules(modules: &mut ParseModules, built_in_rules: &BuiltInRules) -> Vec<ParseRule> {
+        let matrix_rule_name = modules.with_rule_name(Self::rule_name("Matrix"));
+        let column_vector_rule_name = modules.with_rule_name(Self::rule_name("ColumnVector"));
+        let determinant_rule_name = modules.with_rule_name(Self::rule_name("Determinant"));
+        let transpose_rule_name = modules.with_rule_name(Self::rule_name("Transpose"));
+        let inverse_rule_name = modules.with_rule_name(Self::rule_name("Inverse"));
+        let variable_rule_name =
+            modules.with_rule_name(PathIdentifier::new(vec!["Core".into(), "Variable".into()]));
+        vec![
+            // A table with one column is a vector, every other table is a matrix
+            atom_rule(
+                column_vector_rule_name,
+                make_table_parser(built_in_rules, |grid| grid.width() == 1),
+            ),
+            atom_rule(
+                matrix_rule_name,
+                make_table_parser(built_in_rules, |grid| grid.width() != 1),
+            ),
+            atom_rule(
+                determinant_rule_name,
+                make_determinant_parser(built_in_rules, matrix_rule_name),
+            ),
+            prefix_rule(
+                determinant_rule_name,
+                100,
+                just_operator_parser(vec!['d', 'e', 't']),
+            ),
+            // Binds as tightly as a superscript, which it replaces
+            postfix_rule(transpose_rule_name, 1000, just_operator_parser("ᵀ")),
+            // Takes priority over a variable, which would also take the ᵀ
+            atom_rule(
+                transpose_rule_name,
+                make_transposed_variable_parser(built_in_rules, variable_rule_name),
+            ),
+            postfix_rule(
+                transpose_rule_name,
+                1000,
+                make_sup_parser(built_in_rules, |symbols| matches!(symbols, ["⊤"])),
+            ),
+            // Takes priority over a power
+            atom_rule(
+                transpose_rule_name,
+                make_transpose_parser(
+                    built_in_rules,
+                    TransposeNames {
+                        variable_rule_name,
+                        matrix_rule_name,
+                        column_vector_rule_name,
+                    },
+                ),
+            ),
+            postfix_rule(
+                inverse_rule_name,
+                1000,
+                just_operator_parser(vec!["⁻", "¹"]),
+            ),
+            postfix_rule(
+                inverse_rule_name,
+                1000,
+                make_sup_parser(built_in_rules, |symbols| {
+                    matches!(symbols, ["-", "1"] | ["−", "1"])
+                }),
+            ),
+            // The middle dot · is a multiplication, see the arithmetic rules
+            left_infix_rule(
+                modules.with_rule_name(Self::rule_name("DotProduct")),
+                200,
+                just_operator_parser("⋅"),
+            ),
+            left_infix_rule(
+                modules.with_rule_name(Self::rule_name("CrossProduct")),
+                200,
+                just_operator_parser("×"),
+            ),
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("Vector")),
+                make_arrow_vector_parser(),
+            ),
+        ]
+    }
+
+    fn get_autocomplete_rules() -> Vec<AutocompleteRule> {
+        let empty_table = |rows: usize, columns: usize| {
+            vec![InputNode::table(
+                vec![InputRow::new(vec![]); rows * columns],
+                columns,
+            )]
+        };
+        let mut rules = vec![
+            AutocompleteRule::new("vector", input_nodes! {(table 1 x 1 (row))})
+                .with_description("Column vector"),
+            AutocompleteRule::new("matrix", input_nodes! {(table 1 x 1 (row))})
+                .with_description("Matrix"),
+            AutocompleteRule::new("det", input_nodes! {"d", "e", "t"})
+                .with_description("Determinant"),
+            AutocompleteRule::new("dot", input_nodes! {"⋅"})
+                .with_label("dot ⋅")
+                .with_description("Dot product"),
+            AutocompleteRule::new("cross", input_nodes! {"×"})
+                .with_label("cross ×")
+                .with_description("Cross product"),
+        ];
+        for rows in 1..=MAX_AUTOCOMPLETE_SIZE {
+            rules.push(
+                AutocompleteRule::new(format!("vector{}", rows), empty_table(rows, 1))
+                    .with_description(format!("{}×1 column vector", rows)),
+            );
+            for columns in 1..=MAX_AUTOCOMPLETE_SIZE {
+                rules.push(
+                    AutocompleteRule::new(
+                        format!("matrix{}x{}", rows, columns),
+                        empty_table(rows, columns),
+                    )
+                    .with_description(format!("{}×{} matrix", rows, columns)),
+                );
+            }
+        }
+        rules
+    }
+
+    fn get_type_signatures(modules: &mut ParseModules) -> Vec<TypeSignature> {
+        use Type::*;
+        let mut rule_name = |name: &str| modules.with_rule_name(Self::rule_name(name));
+        let transpose = rule_name("Transpose");
+        vec![
+            TypeSignature::variadic(rule_name("ColumnVector"), vec![Number], Vector),
+            TypeSignature::variadic(rule_name("Matrix"), vec![Number], Matrix),
+            TypeSignature::new(rule_name("Vector"), vec![], Vector),
+            TypeSignature::variadic(rule_name("Determinant"), vec![Matrix], Number),
+            TypeSignature::variadic(transpose, vec![Matrix, Unknown], Matrix),
+            TypeSignature::variadic(transpose, vec![Vector, Unknown], Matrix),
+            TypeSignature::variadic(rule_name("Inverse"), vec![Matrix, Unknown], Matrix),
+            TypeSignature::new(rule_name("DotProduct"), vec![Vector, Vector], Number),
+            TypeSignature::new(rule_name("CrossProduct"), vec![Vector, Vector], Vector),
+        ]
+    }
+}
+
+/// A table with a given shape.
+fn make_table_parser(
+    built_in_rules: &BuiltInRules,
+    is_shape: fn(&GridVec<InputRow>) -> bool,
+) -> impl MakeParser {
+    let table_parser = Rc::new(built_in_rules.make_container_parser(InputNodeVariant::Table));
+    MakeParserFn(move |parser| {
+        select_ref! {
+          InputNode::Container(InputNodeVariant::Table, grid) if is_shape(grid) => (),
+        }
+        .rewind()
+        .ignore_then(table_parser.build(parser))
+        .boxed()
+    })
+}
+
+/// |A| with a square table inside
+fn make_determinant_parser(
+    built_in_rules: &BuiltInRules,
+    matrix_rule_name: SyntaxNodeNameId,
+) -> impl MakeParser {
+    let operator_rule_name = built_in_rules.operator_rule_name;
+    let table_parser = Rc::new(make_table_parser(built_in_rules, |grid| {
+        grid.width() == grid.height()
+    }));
+    MakeParserFn(move |parser| {
+        let bar = || {
+            just_symbol("|").map_with_span(move |v, span: SimpleSpan| {
+                SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Operator)
+                    .build(operator_rule_name, span.into_range())
+            })
+        };
+        bar()
+            .then(
+                table_parser
+                    .build(parser)
+                    .map_with_span(move |v, span: SimpleSpan| {
+                        v.build(matrix_rule_name, span.into_range())
+                    }),
+            )
+            .then(bar())
+            .map(|((left, matrix), right)| {
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(vec![left, matrix, right]))
+            })
+            .boxed()
+    })
+}
+
+struct TransposeNames {
+    variable_rule_name: SyntaxNodeNameId,
+    matrix_rule_name: SyntaxNodeNameId,
+    column_vector_rule_name: SyntaxNodeNameId,
+}
+
+/// A^T, where A is a capital letter or a table. Anything else with a T superscript,
+/// like x^T, stays a power, since T could be a variable.
+fn make_transpose_parser(built_in_rules: &BuiltInRules, names: TransposeNames) -> impl MakeParser {
+    let operator_rule_name = built_in_rules.operator_rule_name;
+    let table_parser = Rc::new(make_table_parser(built_in_rules, |_| true));
+    let sup_parser = Rc::new(make_sup_parser(built_in_rules, |symbols| {
+        matches!(symbols, ["T"])
+    }));
+    MakeParserFn(move |parser| {
+        let capital_letter = identifier_parser()
+            .filter(|symbols: &Vec<String>| {
+                matches!(&symbols[..], [symbol] if symbol.chars().all(char::is_uppercase))
+            })
+            .map_with_span(move |symbols, span: SimpleSpan| {
+                SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol)
+                    .build(names.variable_rule_name, span.into_range())
+            });
+        let table = select_ref! {
+          InputNode::Container(InputNodeVariant::Table, grid) => grid.width(),
+        }
+        .rewind()
+        .then(table_parser.build(parser.clone()))
+        .map_with_span(move |(width, table), span: SimpleSpan| {
+            let rule_name = if width == 1 {
+                names.column_vector_rule_name
+            } else {
+                names.matrix_rule_name
+            };
+            table.build(rule_name, span.into_range())
+        });
+        capital_letter
+            .or(table)
+            .then(
+                sup_parser
+                    .build(parser)
+                    .map_with_span(move |v, span: SimpleSpan| {
+                        v.build(operator_rule_name, span.into_range())
+                    }),
+            )
+            .map(|(operand, sup)| {
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(vec![operand, sup]))
+            })
+            .boxed()
+    })
+}
+
+/// Aᵀ, where the ᵀ is a modifier letter and would otherwise be a part of the variable name.
+fn make_transposed_variable_parser(
+    built_in_rules: &BuiltInRules,
+    variable_rule_name: SyntaxNodeNameId,
+) -> impl MakeParser {
+    let operator_rule_name = built_in_rules.operator_rule_name;
+    MakeParserFn(move |_| {
+        identifier_parser()
+            .filter(|symbols: &Vec<String>| matches!(&symbols[..], [_, .., last] if last == "ᵀ"))
+            .map_with_span(move |mut symbols, span: SimpleSpan| {
+                let operator = symbols.split_off(symbols.len() - 1);
+                let operator_start = span.end - 1;
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(vec![
+                    SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol)
+                        .build(variable_rule_name, span.start..operator_start),
+                    SyntaxNodeBuilder::new_leaf_node(operator, LeafNodeType::Operator)
+                        .build(operator_rule_name, operator_start..span.end),
+                ]))
+            })
+            .boxed()
+    })
+}
+
+/// A superscript with specific contents, like the ⊤ in A^⊤
+fn make_sup_parser(
+    built_in_rules: &BuiltInRules,
+    is_match: fn(&[&str]) -> bool,
+) -> impl MakeParser {
+    let sup_parser = Rc::new(built_in_rules.make_container_parser(InputNodeVariant::Sup));
+    MakeParserFn(move |parser| {
+        select_ref! {
+          InputNode::Container(InputNodeVariant::Sup, grid) if sup_matches(grid, is_match) => (),
+        }
+        .rewind()
+        .ignore_then(sup_parser.build(parser))
+        .boxed()
+    })
+}
+
+fn sup_matches(grid: &GridVec<InputRow>, is_match: fn(&[&str]) -> bool) -> bool {
+    let mut rows = grid.values();
+    let (Some(row), None) = (rows.next(), rows.next()) else {
+        return false;
+    };
+    let symbols = row
+        .values
+        .iter()
+        .map(|v| match v {
+            InputNode::Symbol(a) => Some(a.as_str()),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+    symbols.map(|v| is_match(&v)).unwrap_or(false)
+}
+
+/// A variable with an arrow above it, like v⃗
+fn make_arrow_vector_parser() -> impl MakeParser {
+    MakeParserFn(|_| {
+        select! {
+          InputNode::Symbol(a) if {
+            let mut chars = a.chars();
+            chars.next().map(unicode_ident::is_xid_start).unwrap_or(false)
+              && chars.next() == Some('\u{20D7}')
+              && chars.next().is_none()
+          } => a,
+        }
+        .map(|v| SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Symbol))
+        .boxed()
+    })
+}
@@ -1,24 +1,44 @@
-use crate::make_parser::{just_operator_parser, just_symbol_parser};
+use crate::make_parser::{
+    closing_brackets_parser, just_operator_parser, just_symbol_parser, MakeParser, MakeParserFn,
+};
 
 use crate::parse_module::*;
 use crate::parse_modules::ParseModules;
+use crate::parser::pratt_parser::{call_pratt_parser_with_outer_ending, Strength};
+use crate::parser_extensions::{just_symbol, just_symbols};
+use crate::rule_collection::{
+    BasicParserExtra, BoxedNothingParser, ParserInput, RcPrattParserType,
+};
+use crate::syntax_tree::{
+    LeafNodeType, SyntaxNode, SyntaxNodeBuilder, SyntaxNodeChildren, SyntaxNodeNameId,
+};
+use crate::type_checker::{Type, TypeSignature};
 use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
 
-/// Rules for basic arithmetic.
+use chumsky::{prelude::*, span::SimpleSpan, Parser};
+use input_tree::input_nodes;
+
+use super::built_in_rules::BuiltInRules;
+use super::core_rules::identifier_parser;
+
+/// Rules for propositional and first-order logic.
 pub struct LogicRules {
     module_name: String,
     rules: Vec<ParseRule>,
     autocomplete_rules: Vec<AutocompleteRule>,
+    type_signatures: Vec<TypeSignature>,
 }
 
 impl LogicRules {
-    pub fn new(modules: &mut ParseModules) -> Self {
-        let rules = Self::get_rules(modules);
+    pub fn new(modules: &mut ParseModules, built_in_rules: &BuiltInRules) -> Self {
+        let rules = Self::get_rules(modules, built_in_rules);
         let autocomplete_rules = Self::get_autocomplete_rules();
+        let type_signatures = Self::get_type_signatures(modules);
         Self {
             module_name: "Logic".into(),
             rules,
             autocomplete_rules,
+            type_signatures,
         }
     }
     fn rule_name(name: &str) -> PathIdentifier {
@@ -37,9 +57,19 @@ impl ParseModule for LogicRules {
     fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
         &self.autocomplete_rules
     }
+
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &self.type_signatures
+    }
 }
 impl LogicRules {
-    fn get_rules(modules: &mut ParseModules) -> Vec<ParseRule> {
+    fn get_rules(modules: &mut ParseModules, built_in_rules: &BuiltInRules) -> Vec<ParseRule> {
+        let names = BoundVariableNames {
+            operator_rule_name: built_in_rules.operator_rule_name,
+            variable_rule_name: modules
+                .with_rule_name(PathIdentifier::new(vec!["Core".into(), "Variable".into()])),
+            bound_variable_rule_name: modules.with_rule_name(Self::rule_name("BoundVariable")),
+        };
         vec![
             atom_rule(
                 modules.with_rule_name(Self::rule_name("True")),
@@ -49,35 +79,194 @@ impl LogicRules {
                 modules.with_rule_name(Self::rule_name("False")),
                 just_symbol_parser('⊥'),
             ),
+            // Weaker than the comparisons, so that x > 0 ∧ y > 0 works as expected.
             left_infix_rule(
                 modules.with_rule_name(Self::rule_name("And")),
-                100,
+                25,
                 just_operator_parser('∧'),
             ),
             left_infix_rule(
                 modules.with_rule_name(Self::rule_name("Or")),
-                100,
+                20,
                 just_operator_parser('∨'),
             ),
-            left_infix_rule(
+            prefix_rule(
                 modules.with_rule_name(Self::rule_name("Not")),
-                100,
+                30,
                 just_operator_parser('¬'),
             ),
             left_infix_rule(
                 modules.with_rule_name(Self::rule_name("Equivalent")),
-                100,
+                10,
                 just_operator_parser('⇔'),
             ),
-            left_infix_rule(
+            // a ⟹ b ⟹ c is a ⟹ (b ⟹ c)
+            right_infix_rule(
                 modules.with_rule_name(Self::rule_name("Implies")),
-                100,
+                15,
                 just_operator_parser('⟹'),
             ),
+            name_only_rule(names.bound_variable_rule_name),
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("ForAll")),
+                make_quantifier_parser(vec!["∀"], names),
+            ),
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("Exists")),
+                make_quantifier_parser(vec!["∃"], names),
+            ),
+            // Takes priority over the ∃ rule
+            atom_rule(
+                modules.with_rule_name(Self::rule_name("ExistsUnique")),
+                make_quantifier_parser(vec!["∃", "!"], names),
+            ),
         ]
     }
 
     fn get_autocomplete_rules() -> Vec<AutocompleteRule> {
-        vec![]
+        vec![
+            AutocompleteRule::new("forall", input_nodes! {"∀"})
+                .with_label("forall ∀")
+                .with_description("For all"),
+            AutocompleteRule::new("exists", input_nodes! {"∃"})
+                .with_label("exists ∃")
+                .with_description("There exists"),
+            AutocompleteRule::new("and", input_nodes! {"∧"})
+                .with_label("and ∧")
+                .with_description("Logical and"),
+            AutocompleteRule::new("or", input_nodes! {"∨"})
+                .with_label("or ∨")
+                .with_description("Logical or"),
+            AutocompleteRule::new("not", input_nodes! {"¬"})
+                .with_label("not ¬")
+                .with_description("Logical not"),
+            AutocompleteRule::new("implies", input_nodes! {"⟹"})
+                .with_label("implies ⟹")
+                .with_description("Implies"),
+            AutocompleteRule::new("iff", input_nodes! {"⇔"})
+                .with_label("iff ⇔")
+                .with_description("If and only if"),
+        ]
     }
+
+    fn get_type_signatures(modules: &mut ParseModules) -> Vec<TypeSignature> {
+        use Type::*;
+        let mut rule_name = |name: &str| modules.with_rule_name(Self::rule_name(name));
+        let mut signatures = vec![
+            TypeSignature::new(rule_name("True"), vec![], Boolean),
+            TypeSignature::new(rule_name("False"), vec![], Boolean),
+            TypeSignature::new(rule_name("Not"), vec![Boolean], Boolean),
+        ];
+        for name in ["And", "Or", "Equivalent", "Implies"] {
+            signatures.push(TypeSignature::new(
+                rule_name(name),
+                vec![Boolean, Boolean],
+                Boolean,
+            ));
+        }
+        // The bound variable and the domain can be anything
+        for name in ["ForAll", "Exists", "ExistsUnique"] {
+            signatures.push(TypeSignature::variadic(
+                rule_name(name),
+                vec![Unknown],
+                Boolean,
+            ));
+        }
+        signatures
+    }
+}
+
+/// The nodes of a bound variable. Shared with the set-builder notation.
+#[derive(Clone, Copy)]
+pub(crate) struct BoundVariableNames {
+    pub operator_rule_name: SyntaxNodeNameId,
+    pub variable_rule_name: SyntaxNodeNameId,
+    pub bound_variable_rule_name: SyntaxNodeNameId,
+}
+
+pub(crate) fn operator_node<'a>(
+    parser: impl Parser<'a, ParserInput<'a>, String, BasicParserExtra>,
+    operator_rule_name: SyntaxNodeNameId,
+) -> impl Parser<'a, ParserInput<'a>, SyntaxNode, BasicParserExtra> {
+    parser.map_with_span(move |v, span: SimpleSpan| {
+        SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Operator)
+            .build(operator_rule_name, span.into_range())
+    })
+}
+
+/// Separates the bound variable from the body, like in ∀x: P or ∃x. P
+/// A comma would clash with the tuples, like in ∀x ∈ A, B: P
+const QUANTIFIER_SEPARATORS: [&str; 2] = [":", "."];
+
+/// A quantifier with a bound variable, an optional domain and a body.
+/// For example ∀x ∈ S: P. The body extends as far to the right as possible.
+fn make_quantifier_parser(quantifier: Vec<&str>, names: BoundVariableNames) -> impl MakeParser {
+    let quantifier = quantifier
+        .into_iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>();
+    MakeParserFn(move |parser| {
+        let operator_rule_name = names.operator_rule_name;
+        let separator = select! {
+          input_tree::node::InputNode::Symbol(a) if QUANTIFIER_SEPARATORS.contains(&a.as_str()) => a,
+        };
+        let bound_variable =
+            bound_variable_parser(parser.clone(), names, separator.map(|_| ()).boxed());
+
+        let body = call_pratt_parser_with_outer_ending(
+            parser.clone(),
+            (0, Strength::Weak),
+            end().boxed(),
+            closing_brackets_parser(),
+        );
+
+        operator_node(just_symbols(&quantifier), operator_rule_name)
+            .then(bound_variable)
+            .then(operator_node(separator, operator_rule_name))
+            .then(body)
+            .map(|(((quantifier, bound_variable), separator), body)| {
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(vec![
+                    quantifier,
+                    bound_variable,
+                    separator,
+                    body,
+                ]))
+            })
+            .boxed()
+    })
+}
+
+/// A variable with an optional domain, like the x ∈ S in ∀x ∈ S: P.
+/// The domain goes up to the separator, or up to where the enclosing expression ends.
+pub(crate) fn bound_variable_parser<'a>(
+    parser: RcPrattParserType<'a, 'a>,
+    names: BoundVariableNames,
+    separator: BoxedNothingParser<'a, 'a>,
+) -> impl Parser<'a, ParserInput<'a>, SyntaxNode, BasicParserExtra> {
+    let variable = identifier_parser().map_with_span(move |symbols, span: SimpleSpan| {
+        SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol)
+            .build(names.variable_rule_name, span.into_range())
+    });
+    let domain = operator_node(just_symbol("∈"), names.operator_rule_name).then(
+        call_pratt_parser_with_outer_ending(
+            parser,
+            (0, Strength::Weak),
+            separator,
+            closing_brackets_parser(),
+        ),
+    );
+    variable
+        .then(domain.or_not())
+        .map_with_span(move |(variable, domain), span: SimpleSpan| {
+            let mut children = vec![variable];
+            if let Some((element_of, domain)) = domain {
+                children.push(element_of);
+                children.push(domain);
+            }
+            SyntaxNode::new(
+                names.bound_variable_rule_name,
+                span.into_range(),
+                SyntaxNodeChildren::Children(children),
+            )
+        })
 }
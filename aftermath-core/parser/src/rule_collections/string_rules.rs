@@ -1,6 +1,7 @@
 use crate::parse_module::*;
 use crate::parse_modules::ParseModules;
 use crate::parser_extensions::just_symbol;
+use crate::type_checker::{Type, TypeSignature};
 
 use crate::syntax_tree::{LeafNodeType, SyntaxNodeBuilder};
 use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
@@ -12,16 +13,19 @@ pub struct StringRules {
     module_name: String,
     rules: Vec<ParseRule>,
     autocomplete_rules: Vec<AutocompleteRule>,
+    type_signatures: Vec<TypeSignature>,
 }
 
 impl StringRules {
     pub fn new(modules: &mut ParseModules) -> Self {
         let rules = Self::get_rules(modules);
         let autocomplete_rules = Self::get_autocomplete_rules();
+        let type_signatures = Self::get_type_signatures(modules);
         Self {
             module_name: "String".into(),
             rules,
             autocomplete_rules,
+            type_signatures,
         }
     }
     fn rule_name(name: &str) -> PathIdentifier {
@@ -40,6 +44,10 @@ impl ParseModule for StringRules {
     fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
         &self.autocomplete_rules
     }
+
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &self.type_signatures
+    }
 }
 impl StringRules {
     fn get_rules(modules: &mut ParseModules) -> Vec<ParseRule> {
@@ -80,4 +88,12 @@ impl StringRules {
     fn get_autocomplete_rules() -> Vec<AutocompleteRule> {
         vec![]
     }
+
+    fn get_type_signatures(modules: &mut ParseModules) -> Vec<TypeSignature> {
+        vec![TypeSignature::new(
+            modules.with_rule_name(Self::rule_name("String")),
+            vec![],
+            Type::String,
+        )]
+    }
 }
@@ -0,0 +1,527 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::diagnostic::Diagnostic;
+use crate::locale::Locale;
+use crate::make_parser::{MakeParser, MakeParserFn};
+use crate::parse_module::*;
+use crate::parse_modules::ParseModules;
+use crate::rule_collection::{BasicParserExtra, ParserInput, RcPrattParserType};
+use crate::scope_analysis::location;
+use crate::syntax_tree::{
+    LeafNodeType, SyntaxNode, SyntaxNodeBuilder, SyntaxNodeChildren, SyntaxNodeNameId,
+};
+use crate::type_checker::{Type, TypeSignature};
+use crate::{autocomplete::AutocompleteRule, syntax_tree::PathIdentifier};
+use chumsky::{prelude::*, span::SimpleSpan, Parser};
+use input_tree::focus::MinimalInputRowRange;
+use input_tree::grid::GridVec;
+use input_tree::input_nodes;
+use input_tree::node::{InputNode, InputNodeVariant};
+use input_tree::row::{InputRow, RowIndex, RowIndices};
+
+use super::arithmetic_rules::{number_node, number_parser, signed_integer};
+use super::built_in_rules::BuiltInRules;
+
+/// Rules for physical quantities, like 9.81 m/s².
+pub struct UnitRules {
+    module_name: String,
+    rules: Vec<ParseRule>,
+    autocomplete_rules: Vec<AutocompleteRule>,
+    type_signatures: Vec<TypeSignature>,
+    dimension_names: DimensionNames,
+}
+
+impl UnitRules {
+    pub fn new(modules: &mut ParseModules, built_in_rules: &BuiltInRules, locale: &Locale) -> Self {
+        let dimension_names = DimensionNames::new(modules);
+        let rules = Self::get_rules(modules, built_in_rules, locale, &dimension_names);
+        let autocomplete_rules = Self::get_autocomplete_rules();
+        let type_signatures = Self::get_type_signatures(modules);
+        Self {
+            module_name: "Units".into(),
+            rules,
+            autocomplete_rules,
+            type_signatures,
+            dimension_names,
+        }
+    }
+    fn rule_name(name: &str) -> PathIdentifier {
+        PathIdentifier::new(vec!["Units".into(), name.into()])
+    }
+}
+
+impl ParseModule for UnitRules {
+    fn get_module_name(&self) -> &str {
+        &self.module_name
+    }
+
+    fn get_rules(&self) -> &[ParseRule] {
+        &self.rules
+    }
+
+    fn get_autocomplete_rules(&self) -> &[AutocompleteRule] {
+        &self.autocomplete_rules
+    }
+
+    fn get_type_signatures(&self) -> &[TypeSignature] {
+        &self.type_signatures
+    }
+
+    fn get_diagnostics(&self, node: &SyntaxNode) -> Vec<Diagnostic> {
+        self.check_dimensions(node)
+            .into_iter()
+            .map(|mismatch| Diagnostic {
+                message: format!("Cannot combine {} with {}", mismatch.left, mismatch.right),
+                range: mismatch.range,
+            })
+            .collect()
+    }
+}
+
+impl UnitRules {
+    fn get_rules(
+        modules: &mut ParseModules,
+        built_in_rules: &BuiltInRules,
+        locale: &Locale,
+        dimension_names: &DimensionNames,
+    ) -> Vec<ParseRule> {
+        let names = UnitNames {
+            operator_rule_name: built_in_rules.operator_rule_name,
+            sup_rule_name: built_in_rules.sup_rule_name,
+            whitespace_rule_name: dimension_names.whitespace,
+            whitespaces_rule_name: dimension_names.whitespaces,
+            unit_rule_name: modules.with_rule_name(Self::rule_name("Unit")),
+            multiply_rule_name: modules.with_rule_name(Self::rule_name("Multiply")),
+            divide_rule_name: modules.with_rule_name(Self::rule_name("Divide")),
+        };
+        vec![
+            name_only_rule(names.unit_rule_name),
+            name_only_rule(names.multiply_rule_name),
+            name_only_rule(names.divide_rule_name),
+            // Takes priority over a number followed by a variable
+            atom_rule(
+                dimension_names.quantity,
+                make_quantity_parser(built_in_rules, dimension_names.number, names, locale),
+            ),
+        ]
+    }
+
+    fn get_autocomplete_rules() -> Vec<AutocompleteRule> {
+        vec![
+            AutocompleteRule::new("ohm", input_nodes! {"Ω"})
+                .with_label("ohm Ω")
+                .with_description("Ohm"),
+            AutocompleteRule::new("micro", input_nodes! {"µ"})
+                .with_label("micro µ")
+                .with_description("Micro prefix"),
+        ]
+    }
+
+    /// A quantity is a number. Its unit is checked by the dimension analysis.
+    fn get_type_signatures(modules: &mut ParseModules) -> Vec<TypeSignature> {
+        vec![TypeSignature::variadic(
+            modules.with_rule_name(Self::rule_name("Quantity")),
+            vec![Type::Unknown],
+            Type::Number,
+        )]
+    }
+}
+
+/// Exponents of the SI base dimensions, in the order length, mass, time,
+/// electric current, temperature, amount of substance and luminous intensity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Dimension(pub [i8; 7]);
+
+impl Dimension {
+    pub const DIMENSIONLESS: Dimension = Dimension([0; 7]);
+
+    fn multiply(self, other: Dimension) -> Dimension {
+        let mut result = self.0;
+        for (a, b) in result.iter_mut().zip(other.0) {
+            *a = a.saturating_add(b);
+        }
+        Dimension(result)
+    }
+
+    fn divide(self, other: Dimension) -> Dimension {
+        self.multiply(other.pow(-1))
+    }
+
+    fn pow(self, exponent: i8) -> Dimension {
+        Dimension(self.0.map(|v| v.saturating_mul(exponent)))
+    }
+
+    /// Used for the value of units and quantities.
+    pub fn to_bytes(self) -> Vec<u8> {
+        self.0.iter().map(|v| *v as u8).collect()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Dimension> {
+        let bytes: [u8; 7] = bytes.try_into().ok()?;
+        Some(Dimension(bytes.map(|v| v as i8)))
+    }
+}
+
+/// Written with the SI base units, like m·s^-2
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const BASE_UNITS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+        let factors = BASE_UNITS
+            .iter()
+            .zip(self.0)
+            .filter(|(_, exponent)| *exponent != 0)
+            .map(|(unit, exponent)| match exponent {
+                1 => unit.to_string(),
+                _ => format!("{}^{}", unit, exponent),
+            })
+            .collect::<Vec<_>>();
+        if factors.is_empty() {
+            write!(f, "1")
+        } else {
+            write!(f, "{}", factors.join("·"))
+        }
+    }
+}
+
+/// The SI base units, and the derived units with special names.
+/// The kilogram is a gram with a prefix.
+const UNITS: [(&str, [i8; 7]); 26] = [
+    ("m", [1, 0, 0, 0, 0, 0, 0]),
+    ("g", [0, 1, 0, 0, 0, 0, 0]),
+    ("s", [0, 0, 1, 0, 0, 0, 0]),
+    ("A", [0, 0, 0, 1, 0, 0, 0]),
+    ("K", [0, 0, 0, 0, 1, 0, 0]),
+    ("mol", [0, 0, 0, 0, 0, 1, 0]),
+    ("cd", [0, 0, 0, 0, 0, 0, 1]),
+    ("Hz", [0, 0, -1, 0, 0, 0, 0]),
+    ("N", [1, 1, -2, 0, 0, 0, 0]),
+    ("Pa", [-1, 1, -2, 0, 0, 0, 0]),
+    ("J", [2, 1, -2, 0, 0, 0, 0]),
+    ("W", [2, 1, -3, 0, 0, 0, 0]),
+    ("C", [0, 0, 1, 1, 0, 0, 0]),
+    ("V", [2, 1, -3, -1, 0, 0, 0]),
+    ("F", [-2, -1, 4, 2, 0, 0, 0]),
+    ("Ω", [2, 1, -3, -2, 0, 0, 0]),
+    ("S", [-2, -1, 3, 2, 0, 0, 0]),
+    ("Wb", [2, 1, -2, -1, 0, 0, 0]),
+    ("T", [0, 1, -2, -1, 0, 0, 0]),
+    ("H", [2, 1, -2, -2, 0, 0, 0]),
+    ("lm", [0, 0, 0, 0, 0, 0, 1]),
+    ("lx", [-2, 0, 0, 0, 0, 0, 1]),
+    ("Bq", [0, 0, -1, 0, 0, 0, 0]),
+    ("Gy", [2, 0, -2, 0, 0, 0, 0]),
+    ("Sv", [2, 0, -2, 0, 0, 0, 0]),
+    ("kat", [0, 0, -1, 0, 0, 1, 0]),
+];
+
+/// SI prefixes. Micro can be written with the micro sign or with a Greek mu.
+const PREFIXES: [&str; 25] = [
+    "Q", "R", "Y", "Z", "E", "P", "T", "G", "M", "k", "h", "da", "d", "c", "m", "µ", "μ", "n", "p",
+    "f", "a", "z", "y", "r", "q",
+];
+
+/// A unit, optionally with a prefix. An unprefixed unit takes priority, so cd is a candela.
+fn unit_dimension(symbol: &str) -> Option<Dimension> {
+    let find_unit = |symbol: &str| {
+        UNITS
+            .iter()
+            .find(|(unit, _)| *unit == symbol)
+            .map(|(_, dimension)| Dimension(*dimension))
+    };
+    find_unit(symbol).or_else(|| {
+        PREFIXES
+            .iter()
+            .filter_map(|prefix| symbol.strip_prefix(prefix))
+            .find_map(find_unit)
+    })
+}
+
+/// The exponent of m², if the superscript is a plain integer.
+fn unit_exponent(grid: &GridVec<InputRow>) -> Option<i8> {
+    signed_integer(grid)?.parse().ok()
+}
+
+#[derive(Clone, Copy)]
+struct UnitNames {
+    operator_rule_name: SyntaxNodeNameId,
+    sup_rule_name: SyntaxNodeNameId,
+    whitespace_rule_name: SyntaxNodeNameId,
+    whitespaces_rule_name: SyntaxNodeNameId,
+    unit_rule_name: SyntaxNodeNameId,
+    multiply_rule_name: SyntaxNodeNameId,
+    divide_rule_name: SyntaxNodeNameId,
+}
+
+/// A unit with an optional exponent, like kg or s².
+fn unit_factor_parser<'a>(
+    parser: RcPrattParserType<'a, 'a>,
+    sup_parser: &dyn MakeParser,
+    names: UnitNames,
+) -> impl Parser<'a, ParserInput<'a>, (SyntaxNode, Dimension), BasicParserExtra> {
+    let unit = select! {
+      InputNode::Symbol(a) if !a.is_empty() && a.chars().all(char::is_alphabetic) => a,
+    }
+    .repeated()
+    .at_least(1)
+    .collect::<Vec<_>>()
+    .filter(|symbols: &Vec<String>| unit_dimension(&symbols.concat()).is_some())
+    .map_with_span(move |symbols, span: SimpleSpan| {
+        let dimension = unit_dimension(&symbols.concat()).unwrap();
+        (
+            SyntaxNodeBuilder::new_leaf_node(symbols, LeafNodeType::Symbol)
+                .set_value(dimension.to_bytes())
+                .build(names.unit_rule_name, span.into_range()),
+            dimension,
+        )
+    });
+    let exponent = select_ref! {
+      InputNode::Container(InputNodeVariant::Sup, grid) if unit_exponent(grid).is_some() => unit_exponent(grid).unwrap(),
+    }
+    .rewind()
+    .then(
+        sup_parser
+            .build(parser)
+            .map_with_span(move |v, span: SimpleSpan| {
+                v.build(names.operator_rule_name, span.into_range())
+            }),
+    );
+
+    unit.then(exponent.or_not())
+        .map(move |((unit, dimension), exponent)| match exponent {
+            Some((exponent, sup)) => (
+                SyntaxNode::new(
+                    names.sup_rule_name,
+                    unit.range().start..sup.range().end,
+                    SyntaxNodeChildren::Children(vec![unit, sup]),
+                ),
+                dimension.pow(exponent),
+            ),
+            None => (unit, dimension),
+        })
+}
+
+/// Units joined with · and /, like kg·m/s². Left associative, like the arithmetic operators.
+fn unit_expression_parser<'a>(
+    parser: RcPrattParserType<'a, 'a>,
+    sup_parser: &dyn MakeParser,
+    names: UnitNames,
+) -> impl Parser<'a, ParserInput<'a>, (SyntaxNode, Dimension), BasicParserExtra> {
+    let operator = select! {
+      InputNode::Symbol(a) if a == "·" || a == "⋅" || a == "/" => a,
+    }
+    .map_with_span(move |v, span: SimpleSpan| {
+        let is_divide = v == "/";
+        (
+            SyntaxNodeBuilder::new_leaf_node(vec![v], LeafNodeType::Operator)
+                .build(names.operator_rule_name, span.into_range()),
+            is_divide,
+        )
+    });
+
+    unit_factor_parser(parser.clone(), sup_parser, names)
+        .then(
+            operator
+                .then(unit_factor_parser(parser, sup_parser, names))
+                .repeated()
+                .collect::<Vec<_>>(),
+        )
+        .map(move |(first, rest)| {
+            rest.into_iter().fold(
+                first,
+                |(left, left_dimension), ((operator, is_divide), (right, right_dimension))| {
+                    let (rule_name, dimension) = if is_divide {
+                        (
+                            names.divide_rule_name,
+                            left_dimension.divide(right_dimension),
+                        )
+                    } else {
+                        (
+                            names.multiply_rule_name,
+                            left_dimension.multiply(right_dimension),
+                        )
+                    };
+                    (
+                        SyntaxNode::new(
+                            rule_name,
+                            left.range().start..right.range().end,
+                            SyntaxNodeChildren::Children(vec![left, operator, right]),
+                        ),
+                        dimension,
+                    )
+                },
+            )
+        })
+}
+
+/// A number followed by units. The value is the dimension of the quantity.
+fn make_quantity_parser(
+    built_in_rules: &BuiltInRules,
+    number_rule_name: SyntaxNodeNameId,
+    names: UnitNames,
+    locale: &Locale,
+) -> impl MakeParser {
+    let sup_parser = Rc::new(built_in_rules.make_container_parser(InputNodeVariant::Sup));
+    let decimal_separator = locale.decimal_separator();
+    let e_notation = locale.e_notation;
+    MakeParserFn(move |parser| {
+        let number = number_parser(decimal_separator, e_notation).map_with_span(
+            move |(symbols, value), span: SimpleSpan| {
+                number_node(symbols, value).build(number_rule_name, span.into_range())
+            },
+        );
+        let spaces = select! {
+          InputNode::Symbol(a) if a == " " => a,
+        }
+        .repeated()
+        .collect::<Vec<_>>()
+        .map_with_span(|v, span: SimpleSpan| (v, span.into_range()));
+
+        number
+            .then(spaces)
+            .map(move |(number, (spaces, range))| {
+                if spaces.is_empty() {
+                    return number;
+                }
+                let whitespace = SyntaxNodeBuilder::new_leaf_node(spaces, LeafNodeType::Operator)
+                    .build(names.whitespace_rule_name, range.clone());
+                SyntaxNode::new(
+                    names.whitespaces_rule_name,
+                    number.range().start..range.end,
+                    SyntaxNodeChildren::Children(vec![number, whitespace]),
+                )
+            })
+            .then(unit_expression_parser(parser, &*sup_parser, names))
+            .map(|(number, (unit, dimension))| {
+                SyntaxNodeBuilder::new(SyntaxNodeChildren::Children(vec![number, unit]))
+                    .set_value(dimension.to_bytes())
+            })
+            .boxed()
+    })
+}
+
+/// Adding or subtracting quantities with different dimensions, like 3 m + 2 s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DimensionMismatch {
+    pub range: MinimalInputRowRange,
+    pub left: Dimension,
+    pub right: Dimension,
+}
+
+/// The nodes that the dimensional analysis understands.
+struct DimensionNames {
+    quantity: SyntaxNodeNameId,
+    number: SyntaxNodeNameId,
+    add: SyntaxNodeNameId,
+    subtract: SyntaxNodeNameId,
+    multiply: SyntaxNodeNameId,
+    divide: SyntaxNodeNameId,
+    round_brackets: SyntaxNodeNameId,
+    whitespaces: SyntaxNodeNameId,
+    whitespace: SyntaxNodeNameId,
+}
+
+impl DimensionNames {
+    fn new(modules: &mut ParseModules) -> Self {
+        let mut rule_name = |module: &str, name: &str| {
+            modules.with_rule_name(PathIdentifier::new(vec![module.into(), name.into()]))
+        };
+        Self {
+            quantity: rule_name("Units", "Quantity"),
+            number: rule_name("Arithmetic", "Number"),
+            add: rule_name("Arithmetic", "Add"),
+            subtract: rule_name("Arithmetic", "Subtract"),
+            multiply: rule_name("Arithmetic", "Multiply"),
+            divide: rule_name("Arithmetic", "Divide"),
+            round_brackets: rule_name("Core", "RoundBrackets"),
+            whitespaces: rule_name("BuiltIn", "Whitespaces"),
+            whitespace: rule_name("BuiltIn", "Whitespace"),
+        }
+    }
+}
+
+impl UnitRules {
+    /// Dimensional analysis, which checks that only quantities with the same dimension get added or subtracted.
+    /// A plain number is dimensionless. Anything with a variable has an unknown dimension, and is not checked.
+    pub fn check_dimensions(&self, node: &SyntaxNode) -> Vec<DimensionMismatch> {
+        let mut mismatches = vec![];
+        self.dimension_of(node, &mut RowIndices::default(), &mut mismatches);
+        mismatches
+    }
+
+    fn dimension_of(
+        &self,
+        node: &SyntaxNode,
+        row_indices: &mut RowIndices,
+        mismatches: &mut Vec<DimensionMismatch>,
+    ) -> Option<Dimension> {
+        let names = &self.dimension_names;
+        if node.name == names.quantity {
+            return Dimension::from_bytes(&node.value);
+        }
+        if node.name == names.number {
+            return Some(Dimension::DIMENSIONLESS);
+        }
+
+        let children = match &node.children {
+            SyntaxNodeChildren::Children(children) => children,
+            SyntaxNodeChildren::NewRows(rows) => {
+                let dimensions = rows
+                    .values()
+                    .enumerate()
+                    .map(|(index, row)| {
+                        row_indices.push(RowIndex(node.range().start, index));
+                        let dimension = self.dimension_of(row, row_indices, mismatches);
+                        row_indices.pop();
+                        dimension
+                    })
+                    .collect::<Vec<_>>();
+                // A fenced group has the dimension of its contents
+                if node.name == names.round_brackets {
+                    return dimensions.first().copied().flatten();
+                }
+                return None;
+            }
+            SyntaxNodeChildren::Leaf(_) => return None,
+        };
+        let dimensions = children
+            .iter()
+            .map(|child| self.dimension_of(child, row_indices, mismatches))
+            .collect::<Vec<_>>();
+
+        if node.name == names.whitespaces {
+            children
+                .iter()
+                .zip(dimensions)
+                .find(|(child, _)| child.name != names.whitespace)
+                .and_then(|(_, dimension)| dimension)
+        } else if node.name == names.round_brackets {
+            dimensions.get(1).copied().flatten()
+        } else if node.name == names.add || node.name == names.subtract {
+            match dimensions[..] {
+                // Prefix plus or minus
+                [_, dimension] => dimension,
+                [Some(left), _, Some(right)] if left != right => {
+                    mismatches.push(DimensionMismatch {
+                        range: location(node, row_indices),
+                        left,
+                        right,
+                    });
+                    None
+                }
+                [Some(left), _, Some(_)] => Some(left),
+                _ => None,
+            }
+        } else if node.name == names.multiply || node.name == names.divide {
+            match dimensions[..] {
+                [Some(left), _, Some(right)] if node.name == names.multiply => {
+                    Some(left.multiply(right))
+                }
+                [Some(left), _, Some(right)] => Some(left.divide(right)),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use input_tree::focus::MinimalInputRowRange;
+use input_tree::row::{Offset, RowIndex, RowIndices};
+
+use crate::parse_modules::ParseModules;
+use crate::syntax_tree::{PathIdentifier, SyntaxNode, SyntaxNodeChildren, SyntaxNodeNameId};
+
+/// Finds out which variables are bound by sums, integrals, limits, quantifiers, set-builder notation
+/// and lambdas, and which ones are free.
+/// Works with any combination of parse modules, since missing modules simply never produce their nodes.
+pub struct ScopeAnalysis {
+    names: ScopeNames,
+}
+
+/// Where a variable gets its meaning from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    /// The node that binds the variable, like a sum or a quantifier
+    pub binder_name: SyntaxNodeNameId,
+    pub binder_range: MinimalInputRowRange,
+    /// Where the variable is introduced, like the i in i=0
+    pub variable_range: MinimalInputRowRange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableScope {
+    pub name: String,
+    pub range: MinimalInputRowRange,
+    /// Nothing for a free variable
+    pub bound_by: Option<Binding>,
+}
+
+/// A bound variable that hides a variable with the same name from an outer binder,
+/// like the inner i in ∑_{i=0}^{n} ∑_{i=0}^{i} i
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shadowing {
+    pub name: String,
+    pub inner: Binding,
+    pub outer: Binding,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScopeInfo {
+    /// Every variable. The variables that a binder introduces come before the ones in its body,
+    /// so the dx of an integral comes before the integrand.
+    pub variables: Vec<VariableScope>,
+    pub shadowings: Vec<Shadowing>,
+}
+
+impl ScopeInfo {
+    /// The names of the free variables, without duplicates.
+    pub fn free_variables(&self) -> Vec<&str> {
+        let mut free_variables: Vec<&str> = vec![];
+        for variable in self.variables.iter().filter(|v| v.bound_by.is_none()) {
+            if !free_variables.contains(&variable.name.as_str()) {
+                free_variables.push(&variable.name);
+            }
+        }
+        free_variables
+    }
+}
+
+/// Which child of a binder is the scope of its bound variables.
+#[derive(Clone, Copy)]
+enum BodyPosition {
+    Index(usize),
+    Last,
+}
+
+struct ScopeNames {
+    variable: SyntaxNodeNameId,
+    whitespaces: SyntaxNodeNameId,
+    whitespace: SyntaxNodeNameId,
+    binders: Vec<(SyntaxNodeNameId, BodyPosition)>,
+    /// Nodes that introduce a bound variable, together with the index of the variable
+    introducers: Vec<(SyntaxNodeNameId, usize)>,
+    /// Binders where every variable in the head is a parameter, like the x and y in x, y ↦ x + y
+    parameter_binders: Vec<SyntaxNodeNameId>,
+}
+
+impl ScopeNames {
+    fn new(modules: &mut ParseModules) -> Self {
+        let mut rule_name = |module: &str, name: &str| {
+            modules.with_rule_name(PathIdentifier::new(vec![module.into(), name.into()]))
+        };
+        let mut binders = vec![];
+        for name in ["Sum", "Product", "Coproduct", "BigUnion", "BigIntersection"] {
+            binders.push((rule_name("Calculus", name), BodyPosition::Last));
+        }
+        for name in ["Lim", "LimSup", "LimInf"] {
+            binders.push((rule_name("Calculus", name), BodyPosition::Last));
+        }
+        binders.push((rule_name("Calculus", "Integral"), BodyPosition::Index(3)));
+        for name in ["ForAll", "Exists", "ExistsUnique"] {
+            binders.push((rule_name("Logic", name), BodyPosition::Index(3)));
+        }
+        binders.push((
+            rule_name("Collections", "SetBuilder"),
+            BodyPosition::Index(3),
+        ));
+        let lambda = rule_name("Function", "Lambda");
+        binders.push((lambda, BodyPosition::Last));
+
+        let introducers = vec![
+            (rule_name("Calculus", "BoundIndex"), 0),
+            (rule_name("Calculus", "Approaches"), 0),
+            (rule_name("Calculus", "Differential"), 1),
+            (rule_name("Logic", "BoundVariable"), 0),
+            (rule_name("Collections", "BoundVariable"), 0),
+        ];
+        Self {
+            variable: rule_name("Core", "Variable"),
+            whitespaces: rule_name("BuiltIn", "Whitespaces"),
+            whitespace: rule_name("BuiltIn", "Whitespace"),
+            binders,
+            introducers,
+            parameter_binders: vec![lambda],
+        }
+    }
+}
+
+/// A bound variable that is visible in the current node.
+struct Scope {
+    name: String,
+    binding: Binding,
+}
+
+impl ScopeAnalysis {
+    pub fn new(modules: &mut ParseModules) -> Self {
+        Self {
+            names: ScopeNames::new(modules),
+        }
+    }
+
+    pub fn analyze(&self, node: &SyntaxNode) -> ScopeInfo {
+        let mut info = ScopeInfo::default();
+        self.visit(node, &mut RowIndices::default(), &mut vec![], &mut info);
+        info
+    }
+
+    pub fn free_variables(&self, node: &SyntaxNode) -> Vec<String> {
+        self.analyze(node)
+            .free_variables()
+            .into_iter()
+            .map(|v| v.to_string())
+            .collect()
+    }
+
+    fn visit(
+        &self,
+        node: &SyntaxNode,
+        row_indices: &mut RowIndices,
+        scopes: &mut Vec<Scope>,
+        info: &mut ScopeInfo,
+    ) {
+        if let Some(name) = self.variable_name(node) {
+            let bound_by = scopes
+                .iter()
+                .rev()
+                .find(|scope| scope.name == name)
+                .map(|scope| scope.binding.clone());
+            info.variables.push(VariableScope {
+                name,
+                range: location(node, row_indices),
+                bound_by,
+            });
+            return;
+        }
+
+        let Some(body_index) = self.body_index(node) else {
+            for_each_child(node, row_indices, |child, row_indices| {
+                self.visit(child, row_indices, scopes, info)
+            });
+            return;
+        };
+
+        // The bounds and the domain are outside of the scope, only the body is inside
+        let children = match &node.children {
+            SyntaxNodeChildren::Children(children) => children,
+            _ => return,
+        };
+        let binder_range = location(node, row_indices);
+        let mut introduced = vec![];
+        let is_parameter_binder = self.names.parameter_binders.contains(&node.name);
+        for (index, child) in children.iter().enumerate() {
+            if index == body_index {
+                continue;
+            }
+            if is_parameter_binder {
+                self.visit_parameters(child, row_indices, &mut introduced, info);
+            } else {
+                self.visit_head(child, row_indices, scopes, &mut introduced, info);
+            }
+        }
+
+        let scopes_count = scopes.len();
+        for (name, variable_index) in introduced {
+            let binding = Binding {
+                binder_name: node.name,
+                binder_range: binder_range.clone(),
+                variable_range: info.variables[variable_index].range.clone(),
+            };
+            let outer = scopes[..scopes_count]
+                .iter()
+                .rev()
+                .find(|scope| scope.name == name);
+            if let Some(outer) = outer {
+                info.shadowings.push(Shadowing {
+                    name: name.clone(),
+                    inner: binding.clone(),
+                    outer: outer.binding.clone(),
+                });
+            }
+            info.variables[variable_index].bound_by = Some(binding.clone());
+            scopes.push(Scope { name, binding });
+        }
+        self.visit(&children[body_index], row_indices, scopes, info);
+        scopes.truncate(scopes_count);
+    }
+
+    /// Visits the part of a binder that is not the body, and collects the variables that it introduces.
+    /// They are remembered by their index in the variables list, and get their binding once the whole head is visited.
+    fn visit_head(
+        &self,
+        node: &SyntaxNode,
+        row_indices: &mut RowIndices,
+        scopes: &mut Vec<Scope>,
+        introduced: &mut Vec<(String, usize)>,
+        info: &mut ScopeInfo,
+    ) {
+        if self.variable_name(node).is_some() || self.body_index(node).is_some() {
+            self.visit(node, row_indices, scopes, info);
+            return;
+        }
+        let introducer = self
+            .names
+            .introducers
+            .iter()
+            .find(|(name, _)| *name == node.name);
+        let Some((_, variable_index)) = introducer else {
+            for_each_child(node, row_indices, |child, row_indices| {
+                self.visit_head(child, row_indices, scopes, introduced, info)
+            });
+            return;
+        };
+
+        let SyntaxNodeChildren::Children(children) = &node.children else {
+            return;
+        };
+        for (index, child) in children.iter().enumerate() {
+            let variable = self.skip_whitespaces(child);
+            match self.variable_name(variable) {
+                Some(name) if index == *variable_index => {
+                    introduced.push((name.clone(), info.variables.len()));
+                    info.variables.push(VariableScope {
+                        name,
+                        range: location(variable, row_indices),
+                        bound_by: None,
+                    });
+                }
+                _ => self.visit(child, row_indices, scopes, info),
+            }
+        }
+    }
+
+    /// Every variable in the head of a lambda is a parameter, even inside of a tuple or brackets.
+    fn visit_parameters(
+        &self,
+        node: &SyntaxNode,
+        row_indices: &mut RowIndices,
+        introduced: &mut Vec<(String, usize)>,
+        info: &mut ScopeInfo,
+    ) {
+        if let Some(name) = self.variable_name(node) {
+            introduced.push((name.clone(), info.variables.len()));
+            info.variables.push(VariableScope {
+                name,
+                range: location(node, row_indices),
+                bound_by: None,
+            });
+            return;
+        }
+        for_each_child(node, row_indices, |child, row_indices| {
+            self.visit_parameters(child, row_indices, introduced, info)
+        });
+    }
+
+    fn body_index(&self, node: &SyntaxNode) -> Option<usize> {
+        let (_, body_position) = self
+            .names
+            .binders
+            .iter()
+            .find(|(name, _)| *name == node.name)?;
+        let children_count = match &node.children {
+            SyntaxNodeChildren::Children(children) => children.len(),
+            _ => return None,
+        };
+        match *body_position {
+            BodyPosition::Index(index) if index < children_count => Some(index),
+            BodyPosition::Last if children_count > 0 => Some(children_count - 1),
+            _ => None,
+        }
+    }
+
+    fn variable_name(&self, node: &SyntaxNode) -> Option<String> {
+        match &node.children {
+            SyntaxNodeChildren::Leaf(leaf) if node.name == self.names.variable => {
+                Some(leaf.symbols.join(""))
+            }
+            _ => None,
+        }
+    }
+
+    fn skip_whitespaces<'a>(&self, node: &'a SyntaxNode) -> &'a SyntaxNode {
+        match &node.children {
+            SyntaxNodeChildren::Children(children) if node.name == self.names.whitespaces => {
+                children
+                    .iter()
+                    .find(|v| v.name != self.names.whitespace)
+                    .unwrap_or(node)
+            }
+            _ => node,
+        }
+    }
+}
+
+pub(crate) fn location(node: &SyntaxNode, row_indices: &RowIndices) -> MinimalInputRowRange {
+    let range = node.range();
+    MinimalInputRowRange {
+        row_indices: row_indices.clone(),
+        start: Offset(range.start),
+        end: Offset(range.end),
+    }
+}
+
+/// Calls the function for every child, and keeps track of the row indices of nested rows.
+fn for_each_child(
+    node: &SyntaxNode,
+    row_indices: &mut RowIndices,
+    mut f: impl FnMut(&SyntaxNode, &mut RowIndices),
+) {
+    match &node.children {
+        SyntaxNodeChildren::Children(children) => {
+            for child in children {
+                f(child, row_indices);
+            }
+        }
+        SyntaxNodeChildren::NewRows(rows) => {
+            for (index, row) in rows.values().enumerate() {
+                row_indices.push(RowIndex(node.range().start, index));
+                f(row, row_indices);
+                row_indices.pop();
+            }
+        }
+        SyntaxNodeChildren::Leaf(_) => {}
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+
+use input_tree::focus::MinimalInputRowRange;
+use input_tree::row::{RowIndex, RowIndices};
+
+use crate::diagnostic::Diagnostic;
+use crate::parse_modules::{ParseModuleCollection, ParseModules};
+use crate::rule_collections::arithmetic_rules::ArithmeticRules;
+use crate::rule_collections::built_in_rules::BuiltInRules;
+use crate::rule_collections::function_rules::FunctionRules;
+use crate::scope_analysis::location;
+use crate::syntax_tree::{SyntaxNode, SyntaxNodeChildren, SyntaxNodeNameId};
+
+/// The types that the type checker knows about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    Number,
+    Vector,
+    Matrix,
+    Set,
+    Boolean,
+    Function,
+    String,
+    /// Not known, like a free variable. In a signature, it accepts every type.
+    Unknown,
+}
+
+impl Type {
+    /// Every type except for the unknown one
+    pub const KNOWN: [Type; 7] = [
+        Type::Number,
+        Type::Vector,
+        Type::Matrix,
+        Type::Set,
+        Type::Boolean,
+        Type::Function,
+        Type::String,
+    ];
+
+    fn accepts(&self, other: Type) -> bool {
+        *self == Type::Unknown || other == Type::Unknown || *self == other
+    }
+}
+
+/// Says which operand types a rule accepts, and which type it produces.
+/// Operators are not operands, so 1 + 2 has the operands 1 and 2, and |A| has the operand A.
+/// A rule can have multiple signatures, like adding numbers or adding vectors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeSignature {
+    pub name: SyntaxNodeNameId,
+    pub operands: Vec<Type>,
+    /// The last operand can be repeated any number of times, like the entries of a vector.
+    pub variadic: bool,
+    pub result: Type,
+}
+
+impl TypeSignature {
+    pub fn new(name: SyntaxNodeNameId, operands: Vec<Type>, result: Type) -> Self {
+        Self {
+            name,
+            operands,
+            variadic: false,
+            result,
+        }
+    }
+
+    pub fn variadic(name: SyntaxNodeNameId, operands: Vec<Type>, result: Type) -> Self {
+        Self {
+            name,
+            operands,
+            variadic: true,
+            result,
+        }
+    }
+
+    fn accepts(&self, operand_types: &[Type]) -> bool {
+        let arity_matches = if self.variadic {
+            operand_types.len() + 1 >= self.operands.len()
+        } else {
+            operand_types.len() == self.operands.len()
+        };
+        arity_matches
+            && operand_types.iter().enumerate().all(|(index, operand)| {
+                let expected = self
+                    .operands
+                    .get(index)
+                    .or_else(|| self.operands.last().filter(|_| self.variadic));
+                expected
+                    .map(|expected| expected.accepts(*operand))
+                    .unwrap_or(false)
+            })
+    }
+}
+
+/// What an invisible operator between two values means, like in 2x, f(x) or "a""b".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Juxtaposition {
+    Multiply,
+    Apply,
+    Concat,
+}
+
+/// A node with the type that the type checker assigned to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeType {
+    pub name: SyntaxNodeNameId,
+    pub range: MinimalInputRowRange,
+    pub node_type: Type,
+}
+
+/// A rule that got operands which none of its signatures accept, like 1 + {1,2,3}.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeError {
+    pub name: SyntaxNodeNameId,
+    pub range: MinimalInputRowRange,
+    pub operand_types: Vec<Type>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeCheckResult {
+    /// The type of the whole syntax tree
+    pub root_type: Type,
+    /// Every node that is an operand, from the inside out
+    pub node_types: Vec<NodeType>,
+    pub juxtapositions: Vec<(MinimalInputRowRange, Juxtaposition)>,
+    pub errors: Vec<TypeError>,
+}
+
+/// Assigns types to a syntax tree, using the type signatures of the parse modules.
+pub struct TypeChecker {
+    signatures: HashMap<SyntaxNodeNameId, Vec<TypeSignature>>,
+    names: TypeNames,
+}
+
+struct TypeNames {
+    variable: SyntaxNodeNameId,
+    operator: SyntaxNodeNameId,
+    whitespaces: SyntaxNodeNameId,
+    whitespace: SyntaxNodeNameId,
+    missing_operator: SyntaxNodeNameId,
+    missing_token: SyntaxNodeNameId,
+    function_application: SyntaxNodeNameId,
+    multiply: SyntaxNodeNameId,
+}
+
+impl TypeNames {
+    fn new(modules: &mut ParseModules, built_in: &BuiltInRules) -> Self {
+        Self {
+            variable: built_in.identifier_rule_name,
+            operator: built_in.operator_rule_name,
+            whitespaces: built_in.whitespaces_rule_name,
+            whitespace: built_in.whitespace_rule_name,
+            missing_operator: built_in.error_missing_operator_name,
+            missing_token: built_in.error_missing_token_name,
+            function_application: modules
+                .with_rule_name(FunctionRules::rule_name("FunctionApplication")),
+            multiply: modules.with_rule_name(ArithmeticRules::rule_name("Multiply")),
+        }
+    }
+}
+
+impl TypeChecker {
+    pub fn new(modules: &mut ParseModules, parse_modules: &ParseModuleCollection) -> Self {
+        let mut signatures: HashMap<SyntaxNodeNameId, Vec<TypeSignature>> = HashMap::new();
+        for signature in parse_modules
+            .get_modules()
+            .iter()
+            .flat_map(|v| v.get_type_signatures())
+        {
+            signatures
+                .entry(signature.name)
+                .or_default()
+                .push(signature.clone());
+        }
+        Self {
+            signatures,
+            names: TypeNames::new(modules, parse_modules.get_built_in()),
+        }
+    }
+
+    /// Checks a syntax tree. Variables that are missing from the map have an unknown type.
+    pub fn check(&self, node: &SyntaxNode, variables: &HashMap<String, Type>) -> TypeCheckResult {
+        let mut result = TypeCheckResult {
+            root_type: Type::Unknown,
+            node_types: vec![],
+            juxtapositions: vec![],
+            errors: vec![],
+        };
+        result.root_type = self.node_type(node, &RowIndices::default(), variables, &mut result);
+        result
+    }
+
+    /// Type checks a syntax tree, and reports the type errors in a way that an editor can show them.
+    pub fn get_diagnostics(
+        &self,
+        node: &SyntaxNode,
+        variables: &HashMap<String, Type>,
+    ) -> Vec<Diagnostic> {
+        self.check(node, variables)
+            .errors
+            .into_iter()
+            .map(|error| Diagnostic {
+                message: if error.name == self.names.missing_operator {
+                    format!(
+                        "Missing an operator between {}",
+                        type_list(&error.operand_types)
+                    )
+                } else {
+                    format!("Cannot be used with {}", type_list(&error.operand_types))
+                },
+                range: error.range,
+            })
+            .collect()
+    }
+
+    fn node_type(
+        &self,
+        node: &SyntaxNode,
+        row_indices: &RowIndices,
+        variables: &HashMap<String, Type>,
+        result: &mut TypeCheckResult,
+    ) -> Type {
+        let node = self.skip_whitespaces(node);
+        let range = location(node, row_indices);
+        let node_type = match &node.children {
+            SyntaxNodeChildren::Leaf(leaf) if node.name == self.names.variable => variables
+                .get(&leaf.symbols.join(""))
+                .copied()
+                .unwrap_or(Type::Unknown),
+            _ => {
+                let mut operands = vec![];
+                self.collect_operands(node, row_indices, &mut operands);
+                let operand_types = operands
+                    .into_iter()
+                    .map(|(operand, row_indices)| {
+                        self.node_type(operand, &row_indices, variables, result)
+                    })
+                    .collect::<Vec<_>>();
+                self.apply_signatures(node, range.clone(), operand_types, result)
+            }
+        };
+        result.node_types.push(NodeType {
+            name: node.name,
+            range,
+            node_type,
+        });
+        node_type
+    }
+
+    fn apply_signatures(
+        &self,
+        node: &SyntaxNode,
+        range: MinimalInputRowRange,
+        operand_types: Vec<Type>,
+        result: &mut TypeCheckResult,
+    ) -> Type {
+        let juxtaposition = if node.name == self.names.missing_operator {
+            match operand_types[..] {
+                [left, right] => juxtaposition(left, right),
+                _ => None,
+            }
+        } else if node.name == self.names.function_application {
+            match operand_types.first() {
+                Some(Type::Number | Type::Vector | Type::Matrix) => Some(Juxtaposition::Multiply),
+                _ => Some(Juxtaposition::Apply),
+            }
+        } else {
+            None
+        };
+        if let Some(juxtaposition) = juxtaposition {
+            result.juxtapositions.push((range.clone(), juxtaposition));
+        }
+
+        let name = match juxtaposition {
+            Some(Juxtaposition::Multiply) => self.names.multiply,
+            Some(Juxtaposition::Concat) => return Type::String,
+            Some(Juxtaposition::Apply) => node.name,
+            None if node.name == self.names.missing_operator => {
+                result.errors.push(TypeError {
+                    name: node.name,
+                    range,
+                    operand_types,
+                });
+                return Type::Unknown;
+            }
+            None => node.name,
+        };
+        let Some(signatures) = self.signatures.get(&name) else {
+            return Type::Unknown;
+        };
+        let mut matching = signatures
+            .iter()
+            .filter(|signature| signature.accepts(&operand_types));
+        let Some(first) = matching.next() else {
+            result.errors.push(TypeError {
+                name: node.name,
+                range,
+                operand_types,
+            });
+            return Type::Unknown;
+        };
+        // With unknown operands, multiple signatures can match
+        if matching.all(|signature| signature.result == first.result) {
+            first.result
+        } else {
+            Type::Unknown
+        }
+    }
+
+    /// Operators are skipped, and the contents of brackets and nested rows are operands.
+    fn collect_operands<'a>(
+        &self,
+        node: &'a SyntaxNode,
+        row_indices: &RowIndices,
+        operands: &mut Vec<(&'a SyntaxNode, RowIndices)>,
+    ) {
+        match &node.children {
+            SyntaxNodeChildren::Children(children) => {
+                for child in children {
+                    let child = self.skip_whitespaces(child);
+                    if child.name == self.names.operator {
+                        self.collect_operands(child, row_indices, operands);
+                    } else if child.name != self.names.missing_token
+                        && child.name != self.names.whitespace
+                    {
+                        operands.push((child, row_indices.clone()));
+                    }
+                }
+            }
+            SyntaxNodeChildren::NewRows(rows) => {
+                for (index, row) in rows.values().enumerate() {
+                    let mut row_indices = row_indices.clone();
+                    row_indices.push(RowIndex(node.range().start, index));
+                    operands.push((row, row_indices));
+                }
+            }
+            SyntaxNodeChildren::Leaf(_) => {}
+        }
+    }
+
+    fn skip_whitespaces<'a>(&self, node: &'a SyntaxNode) -> &'a SyntaxNode {
+        match &node.children {
+            SyntaxNodeChildren::Children(children) if node.name == self.names.whitespaces => {
+                children
+                    .iter()
+                    .find(|v| v.name != self.names.whitespace)
+                    .unwrap_or(node)
+            }
+            _ => node,
+        }
+    }
+}
+
+/// Picks the meaning of two values that are written next to each other.
+fn juxtaposition(left: Type, right: Type) -> Option<Juxtaposition> {
+    use Type::*;
+    match (left, right) {
+        (Function, _) => Some(Juxtaposition::Apply),
+        (String, String | Unknown) | (Unknown, String) => Some(Juxtaposition::Concat),
+        (Number | Unknown, Number | Vector | Matrix | Unknown)
+        | (Vector | Matrix, Number)
+        | (Matrix, Vector | Matrix) => Some(Juxtaposition::Multiply),
+        _ => None,
+    }
+}
+
+fn type_list(types: &[Type]) -> String {
+    types
+        .iter()
+        .map(|v| format!("{:?}", v))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
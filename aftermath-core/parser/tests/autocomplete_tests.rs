@@ -1,5 +1,7 @@
 use input_tree::input_nodes;
-use parser::autocomplete::{AutocompleteMatcher, AutocompleteRule, AutocompleteRules};
+use parser::autocomplete::{
+    AutocompleteMatcher, AutocompleteRule, AutocompleteRuleMatch, AutocompleteRules,
+};
 
 fn test_rules<'a>() -> AutocompleteRules {
     let autocomplete_rules = vec![
@@ -82,3 +84,61 @@ fn test_autocomplete_match_followed_by_autocomplete_match() {
     assert!(!result.is_empty());
     assert_eq!(result.len(), 3);
 }
+
+#[test]
+fn test_autocomplete_fuzzy_match() {
+    let input = input_nodes! {"i", "n", "t", "g"};
+    let context = test_rules();
+    let mut result = context.matches(&input, input.len(), 0);
+    result.sort();
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].rule.parser, "integral");
+    assert_eq!(result[0].input_match_length, 4);
+    assert_eq!(result[0].skipped_length, 1);
+    assert!(!result[0].is_complete_match());
+}
+
+#[test]
+fn test_autocomplete_fuzzy_match_starts_at_rule_start() {
+    let input = input_nodes! {"n", "t", "g"};
+    let context = test_rules();
+    let result = context.matches(&input, input.len(), 0);
+    assert!(result.is_empty());
+}
+
+#[test]
+fn test_autocomplete_prefix_match_is_better_than_fuzzy() {
+    let input = input_nodes! {"s", "u", "m"};
+    let context = test_rules();
+    let mut result = context.matches(&input, input.len(), 0);
+    result.sort();
+    assert_eq!(result[0].rule.parser, "sum");
+    assert!(!result[0].is_fuzzy());
+}
+
+#[test]
+fn test_autocomplete_ties_are_sorted_by_keyword() {
+    let rules = AutocompleteRules(vec![
+        AutocompleteRule::new("intz", input_nodes! {"∫"}),
+        AutocompleteRule::new("inty", input_nodes! {"∫"}),
+        AutocompleteRule::new("intx", input_nodes! {"∫"}),
+    ]);
+    let input = input_nodes! {"i", "n", "t"};
+    let mut result = rules.matches(&input, input.len(), 0);
+    result.sort();
+    let keywords: Vec<_> = result.iter().map(|v| v.rule.parser.as_str()).collect();
+    assert_eq!(keywords, vec!["intx", "inty", "intz"]);
+}
+
+#[test]
+fn test_autocomplete_priority() {
+    let rules = AutocompleteRules(vec![
+        AutocompleteRule::new("integral", input_nodes! {"∫"}),
+        AutocompleteRule::new("integrate", input_nodes! {"∫"})
+            .with_priority(AutocompleteRuleMatch::SYMBOL_SCORE / 2),
+    ]);
+    let input = input_nodes! {"i", "n", "t"};
+    let mut result = rules.matches(&input, input.len(), 0);
+    result.sort();
+    assert_eq!(result[0].rule.parser, "integrate");
+}
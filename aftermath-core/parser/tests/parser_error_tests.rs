@@ -2,12 +2,15 @@ use std::rc::Rc;
 
 use input_tree::{input_row, row::InputRow};
 use parser::{
+    locale::Locale,
     parse_modules::{ParseModuleCollection, ParseModules},
     rule_collections::{
         arithmetic_rules::ArithmeticRules, built_in_rules::BuiltInRules,
         calculus_rules::CalculusRules, collections_rules::CollectionsRules,
-        comparison_rules::ComparisonRules, core_rules::CoreRules, function_rules::FunctionRules,
-        logic_rules::LogicRules, string_rules::StringRules,
+        comparison_rules::ComparisonRules, core_rules::CoreRules,
+        definition_rules::DefinitionRules, function_rules::FunctionRules,
+        linear_algebra_rules::LinearAlgebraRules, logic_rules::LogicRules,
+        string_rules::StringRules, unit_rules::UnitRules,
     },
 };
 
@@ -15,13 +18,20 @@ fn create_parser() -> parser::parser::MathParser {
     let mut modules = ParseModules::new();
     let built_in = Rc::new(BuiltInRules::new(&mut modules));
     let core = Rc::new(CoreRules::new(&mut modules, &built_in));
-    let arithmetic = Rc::new(ArithmeticRules::new(&mut modules));
-    let calculus = Rc::new(CalculusRules::new(&mut modules));
-    let collections = Rc::new(CollectionsRules::new(&mut modules));
+    let arithmetic = Rc::new(ArithmeticRules::new(
+        &mut modules,
+        &built_in,
+        &Locale::default(),
+    ));
+    let calculus = Rc::new(CalculusRules::new(&mut modules, &built_in));
+    let collections = Rc::new(CollectionsRules::new(&mut modules, &Locale::default()));
     let comparison = Rc::new(ComparisonRules::new(&mut modules));
+    let definition = Rc::new(DefinitionRules::new(&mut modules, &built_in));
     let function = Rc::new(FunctionRules::new(&mut modules, &built_in));
-    let logic = Rc::new(LogicRules::new(&mut modules));
+    let linear_algebra = Rc::new(LinearAlgebraRules::new(&mut modules, &built_in));
+    let logic = Rc::new(LogicRules::new(&mut modules, &built_in));
     let string = Rc::new(StringRules::new(&mut modules));
+    let units = Rc::new(UnitRules::new(&mut modules, &built_in, &Locale::default()));
 
     let module_collection = ParseModuleCollection::new(
         built_in.clone(),
@@ -32,9 +42,12 @@ fn create_parser() -> parser::parser::MathParser {
             calculus,
             collections,
             comparison,
+            definition,
             function,
+            linear_algebra,
             logic,
             string,
+            units,
         ],
     );
     parser::parser::MathParser::new(module_collection)
@@ -1,28 +1,49 @@
+use std::collections::HashMap;
 use std::rc::Rc;
 
-use input_tree::{input_row, node::InputNode, row::InputRow};
+use input_tree::{
+    input_row,
+    node::InputNode,
+    row::{InputRow, Offset, RowIndex, RowIndices},
+};
 use parser::{
+    locale::{DecimalSeparator, Locale},
     parse_modules::{ParseModuleCollection, ParseModules},
     rule_collections::{
-        arithmetic_rules::ArithmeticRules, built_in_rules::BuiltInRules,
-        calculus_rules::CalculusRules, collections_rules::CollectionsRules,
-        comparison_rules::ComparisonRules, core_rules::CoreRules, function_rules::FunctionRules,
-        logic_rules::LogicRules, string_rules::StringRules,
+        arithmetic_rules::ArithmeticRules,
+        built_in_rules::BuiltInRules,
+        calculus_rules::CalculusRules,
+        collections_rules::CollectionsRules,
+        comparison_rules::ComparisonRules,
+        complex_rules::{ComplexLiteral, ComplexRules, ImaginaryUnit},
+        core_rules::CoreRules,
+        declarative_rules::{DeclarativeRules, DeclarativeRulesError},
+        definition_rules::DefinitionRules,
+        function_rules::FunctionRules,
+        linear_algebra_rules::LinearAlgebraRules,
+        logic_rules::LogicRules,
+        string_rules::StringRules,
+        unit_rules::UnitRules,
     },
-    syntax_tree::SyntaxNode,
+    scope_analysis::ScopeAnalysis,
+    syntax_tree::{PathIdentifier, SyntaxNode},
+    type_checker::{Juxtaposition, Type, TypeCheckResult, TypeChecker},
 };
 
-fn create_parser() -> (parser::parser::MathParser, ParseModules) {
+fn create_modules(locale: &Locale) -> (ParseModuleCollection, ParseModules) {
     let mut modules = ParseModules::new();
     let built_in = Rc::new(BuiltInRules::new(&mut modules));
     let core = Rc::new(CoreRules::new(&mut modules, &built_in));
-    let arithmetic = Rc::new(ArithmeticRules::new(&mut modules));
-    let calculus = Rc::new(CalculusRules::new(&mut modules));
-    let collections = Rc::new(CollectionsRules::new(&mut modules));
+    let arithmetic = Rc::new(ArithmeticRules::new(&mut modules, &built_in, locale));
+    let calculus = Rc::new(CalculusRules::new(&mut modules, &built_in));
+    let collections = Rc::new(CollectionsRules::new(&mut modules, locale));
     let comparison = Rc::new(ComparisonRules::new(&mut modules));
+    let definition = Rc::new(DefinitionRules::new(&mut modules, &built_in));
     let function = Rc::new(FunctionRules::new(&mut modules, &built_in));
-    let logic = Rc::new(LogicRules::new(&mut modules));
+    let linear_algebra = Rc::new(LinearAlgebraRules::new(&mut modules, &built_in));
+    let logic = Rc::new(LogicRules::new(&mut modules, &built_in));
     let string = Rc::new(StringRules::new(&mut modules));
+    let units = Rc::new(UnitRules::new(&mut modules, &built_in, locale));
 
     let module_collection = ParseModuleCollection::new(
         built_in.clone(),
@@ -33,16 +54,39 @@ fn create_parser() -> (parser::parser::MathParser, ParseModules) {
             calculus,
             collections,
             comparison,
+            definition,
             function,
+            linear_algebra,
             logic,
             string,
+            units,
         ],
     );
+    (module_collection, modules)
+}
+
+fn create_parser(locale: &Locale) -> (parser::parser::MathParser, ParseModules) {
+    let (module_collection, modules) = create_modules(locale);
     (parser::parser::MathParser::new(module_collection), modules)
 }
 
+fn type_check(row: &InputRow, variables: &[(&str, Type)]) -> (TypeCheckResult, ParseModules) {
+    let (module_collection, mut modules) = create_modules(&Locale::default());
+    let type_checker = TypeChecker::new(&mut modules, &module_collection);
+    let parsed = parser::parser::MathParser::new(module_collection).parse(&row.values);
+    let variables = variables
+        .iter()
+        .map(|(name, v)| (name.to_string(), *v))
+        .collect::<HashMap<_, _>>();
+    (type_checker.check(&parsed, &variables), modules)
+}
+
 fn parse_row(row: &InputRow) -> (SyntaxNode, ParseModules) {
-    let (parser, modules) = create_parser();
+    parse_row_with_locale(row, &Locale::default())
+}
+
+fn parse_row_with_locale(row: &InputRow, locale: &Locale) -> (SyntaxNode, ParseModules) {
+    let (parser, modules) = create_parser(locale);
     let parsed = parser.parse(&row.values);
     (parsed, modules)
 }
@@ -83,7 +127,7 @@ fn test_sub() {
     let (parsed, modules) = parse_row(&layout);
     assert_eq!(
         parsed.with_display(modules.get_rule_name_map()).to_string(),
-        r#"(BuiltIn::Sub (Core::Variable "a") (BuiltIn::Operator 1x1 (Arithmetic::Number "1")))"#
+        r#"(BuiltIn::Sub (Core::Variable "a") (BuiltIn::Operator 1x1 (Arithmetic::Number "1" 31)))"#
     );
 }
 
@@ -101,8 +145,8 @@ fn test_sup_sub() {
         format!(
             "{}{}{}",
             r#"(BuiltIn::Sub "#,
-            r#"(BuiltIn::Sup (Core::Variable "a") (BuiltIn::Operator 1x1 (Arithmetic::Number "1")))"#,
-            r#" (BuiltIn::Operator 1x1 (Arithmetic::Number "2")))"#
+            r#"(BuiltIn::Sup (Core::Variable "a") (BuiltIn::Operator 1x1 (Arithmetic::Number "1" 31)))"#,
+            r#" (BuiltIn::Operator 1x1 (Arithmetic::Number "2" 32)))"#
         )
     );
 }
@@ -271,14 +315,1153 @@ fn test_parser_empty_brackets() {
     );
 }
 
-// TODO: Add tests for tables
-// TODO: Add more default tokens
-// Document that \x basically means "this has a very specific meaning", such as \| always being a | symbol, and \sum always being a sum symbol.
-// Parse || abs || and their escaped \|| variants
-// 4. Parser for whitespace
-// 5. Parser for chains of < <=, which could be treated as a "domain restriction"
+#[test]
+fn test_parser_piecewise() {
+    let layout = input_row! {(row "{", (table 2 x 2 (row "x"), (row "x", ">", "0"), (row "-", "x"), (row "x", "<", "0")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Core::Piecewise (BuiltIn::Operator "{") (Core::Cases 2x2 (Core::CaseValue (Core::Variable "x")) (Core::CaseCondition (Comparison::GreaterThan (Core::Variable "x") (BuiltIn::Operator ">") (Arithmetic::Number "0" 30))) (Core::CaseValue (Arithmetic::Subtract (BuiltIn::Operator "-") (Core::Variable "x"))) (Core::CaseCondition (Comparison::LessThan (Core::Variable "x") (BuiltIn::Operator "<") (Arithmetic::Number "0" 30)))))"#
+    );
+}
 
-// TODO: The dx at the end of an integral might not even be a closing bracket.
-// After all, it can also sometimes appear inside an integral.
+#[test]
+fn test_parser_leibniz_derivative() {
+    let layout = input_row! {(row (frac (row "d"), (row "d", "x")), "e")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Derivative (BuiltIn::Operator 1x2 (Calculus::Differential (BuiltIn::Operator "d")) (Calculus::Differential (BuiltIn::Operator "d") (Core::Variable "x")) 01) (Core::Variable "e"))"#
+    );
+}
+
+#[test]
+fn test_parser_leibniz_second_derivative() {
+    let layout =
+        input_row! {(row (frac (row "d", (sup (row "2")), "y"), (row "d", "x", (sup (row "2")))))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Derivative 1x2 (Calculus::Differential (BuiltIn::Sup (BuiltIn::Operator "d") (BuiltIn::Operator 1x1 (Arithmetic::Number "2" 32))) (Core::Variable "y")) (Calculus::Differential (BuiltIn::Operator "d") (BuiltIn::Sup (Core::Variable "x") (BuiltIn::Operator 1x1 (Arithmetic::Number "2" 32)))) 02)"#
+    );
+}
+
+#[test]
+fn test_parser_partial_derivative() {
+    let layout = input_row! {(row (frac (row "∂", "f"), (row "∂", "x")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Derivative 1x2 (Calculus::Differential (BuiltIn::Operator "∂") (Core::Variable "f")) (Calculus::Differential (BuiltIn::Operator "∂") (Core::Variable "x")) 01)"#
+    );
+}
+
+#[test]
+fn test_parser_malformed_differential() {
+    let layout = input_row! {(row (frac (row "d", ")", "+"), (row "d", "x")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Derivative 1x2 (Calculus::Differential (BuiltIn::Operator "d") (Arithmetic::Add (Error::UnknownToken ")") (BuiltIn::Operator "+") (Error::MissingToken))) (Calculus::Differential (BuiltIn::Operator "d") (Core::Variable "x")) 01)"#
+    );
+}
+
+#[test]
+fn test_parser_not_a_derivative() {
+    let layout = input_row! {(row (frac (row "d"), (row "x")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(BuiltIn::Fraction 1x2 (Core::Variable "d") (Core::Variable "x"))"#
+    );
+}
+
+#[test]
+fn test_parser_lagrange_derivative() {
+    let layout = input_row! {(row "f", "'", "'", "(", "x", ")")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Function::FunctionApplication (Calculus::Derivative (Core::Variable "f") (BuiltIn::Operator "''" 02)) (BuiltIn::Operator (BuiltIn::Operator "(") (Core::Variable "x") (BuiltIn::Operator ")")))"#
+    );
+}
+
+#[test]
+fn test_parser_newton_derivative() {
+    let layout = input_row! {(row "x\u{0308}")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        "(Calculus::Derivative \"x\u{0308}\" 02)"
+    );
+}
+
+#[test]
+fn test_parser_integral_with_bounds() {
+    let layout =
+        input_row! {(row "∫", (sub (row "0")), (sup (row "1")), "x", " ", "d", "x", "+", "1")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Add (Calculus::Integral (BuiltIn::Operator "∫") (BuiltIn::Operator 1x1 (Arithmetic::Number "0" 30)) (BuiltIn::Operator 1x1 (Arithmetic::Number "1" 31)) (BuiltIn::Whitespaces (Core::Variable "x") (BuiltIn::Whitespace " ")) (Calculus::Differential (BuiltIn::Operator "d") (Core::Variable "x"))) (BuiltIn::Operator "+") (Arithmetic::Number "1" 31))"#
+    );
+}
+
+#[test]
+fn test_parser_nested_integrals() {
+    let layout = input_row! {(row "∫", "∫", "f", " ", "d", "x", " ", "ⅆ", "y")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Integral (BuiltIn::Operator "∫") (BuiltIn::Nothing) (BuiltIn::Nothing) (BuiltIn::Whitespaces (Calculus::Integral (BuiltIn::Operator "∫") (BuiltIn::Nothing) (BuiltIn::Nothing) (BuiltIn::Whitespaces (Core::Variable "f") (BuiltIn::Whitespace " ")) (Calculus::Differential (BuiltIn::Operator "d") (Core::Variable "x"))) (BuiltIn::Whitespace " ")) (Calculus::Differential (BuiltIn::Operator "ⅆ") (Core::Variable "y")))"#
+    );
+}
+
+#[test]
+fn test_parser_double_integral() {
+    let layout = input_row! {(row "∬", "f", " ", "d", "x", "d", "y")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Integral (BuiltIn::Operator "∬") (BuiltIn::Nothing) (BuiltIn::Nothing) (BuiltIn::Whitespaces (Core::Variable "f") (BuiltIn::Whitespace " ")) (Calculus::Differential (BuiltIn::Operator "d") (Core::Variable "x")) (Calculus::Differential (BuiltIn::Operator "d") (Core::Variable "y")))"#
+    );
+}
+
+#[test]
+fn test_parser_integral_without_differential() {
+    let layout = input_row! {(row "(", "∫", "x", ")")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Core::RoundBrackets (BuiltIn::Operator "(") (Calculus::Integral (BuiltIn::Operator "∫") (BuiltIn::Nothing) (BuiltIn::Nothing) (Core::Variable "x")) (BuiltIn::Operator ")"))"#
+    );
+}
+
+#[test]
+fn test_parser_integral_with_glued_differential() {
+    let layout = input_row! {(row "∫", "x", "d", "x")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Integral (BuiltIn::Operator "∫") (BuiltIn::Nothing) (BuiltIn::Nothing) (Core::Variable "x") (Calculus::Differential (BuiltIn::Operator "d") (Core::Variable "x")))"#
+    );
+}
+
+#[test]
+fn test_parser_double_integral_with_glued_differentials() {
+    let layout = input_row! {(row "∬", "2", "x", "y", "d", "x", "d", "y")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Integral (BuiltIn::Operator "∬") (BuiltIn::Nothing) (BuiltIn::Nothing) (Error::MissingOperator (Arithmetic::Number "2" 32) (Error::MissingToken) (Core::Variable "xy")) (Calculus::Differential (BuiltIn::Operator "d") (Core::Variable "x")) (Calculus::Differential (BuiltIn::Operator "d") (Core::Variable "y")))"#
+    );
+}
+
+#[test]
+fn test_parser_integral_with_only_upper_bound() {
+    let layout = input_row! {(row "∫", (sup (row "1")), "x", "d", "x")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Integral (BuiltIn::Operator "∫") (BuiltIn::Nothing) (BuiltIn::Operator 1x1 (Arithmetic::Number "1" 31)) (Core::Variable "x") (Calculus::Differential (BuiltIn::Operator "d") (Core::Variable "x")))"#
+    );
+}
+
+/// The ending parser is checked before an atom, since the dx could also be read as a variable.
+#[test]
+fn test_parser_integral_without_integrand() {
+    let layout = input_row! {(row "∫", "d", "x")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Integral (BuiltIn::Operator "∫") (BuiltIn::Nothing) (BuiltIn::Nothing) (Error::MissingToken) (Calculus::Differential (BuiltIn::Operator "d") (Core::Variable "x")))"#
+    );
+}
+
+/// After an atom, the ending parser also comes before a missing operator.
+#[test]
+fn test_parser_integral_differential_after_integrand() {
+    let layout = input_row! {(row "∫", "f", " ", "d", "x", "+", "1")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Add (Calculus::Integral (BuiltIn::Operator "∫") (BuiltIn::Nothing) (BuiltIn::Nothing) (BuiltIn::Whitespaces (Core::Variable "f") (BuiltIn::Whitespace " ")) (Calculus::Differential (BuiltIn::Operator "d") (Core::Variable "x"))) (BuiltIn::Operator "+") (Arithmetic::Number "1" 31))"#
+    );
+}
+
+#[test]
+fn test_parser_sum_with_index() {
+    let layout = input_row! {(row "∑", (sub (row "n", "=", "0")), (sup (row "1", "0")), "n", (sup (row "2")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Sum (BuiltIn::Operator "∑") (BuiltIn::Operator 1x1 (Calculus::BoundIndex (Core::Variable "n") (BuiltIn::Operator "=") (Arithmetic::Number "0" 30))) (BuiltIn::Operator 1x1 (Arithmetic::Number "10" 3130)) (BuiltIn::Sup (Core::Variable "n") (BuiltIn::Operator 1x1 (Arithmetic::Number "2" 32))))"#
+    );
+}
+
+#[test]
+fn test_parser_sum_with_malformed_index() {
+    let layout = input_row! {(row "∑", (sub (row "n", "=", ")")), (sup (row "=")), "n")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Sum (BuiltIn::Operator "∑") (BuiltIn::Operator 1x1 (Calculus::BoundIndex (Core::Variable "n") (BuiltIn::Operator "=") (Error::UnknownToken ")"))) (BuiltIn::Operator 1x1 (Comparison::Equals (Error::MissingToken) (BuiltIn::Operator "=") (Error::MissingToken))) (Core::Variable "n"))"#
+    );
+}
+
+#[test]
+fn test_parser_sum_in_brackets() {
+    let layout = input_row! {(row "(", "∑", (sub (row "i")), "i", ")", "+", "1")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Add (Core::RoundBrackets (BuiltIn::Operator "(") (Calculus::Sum (BuiltIn::Operator "∑") (BuiltIn::Operator 1x1 (Core::Variable "i")) (BuiltIn::Nothing) (Core::Variable "i")) (BuiltIn::Operator ")")) (BuiltIn::Operator "+") (Arithmetic::Number "1" 31))"#
+    );
+}
+
+#[test]
+fn test_parser_unclosed_nested_brackets() {
+    let layout = input_row! {(row "(", "(", "a", ")", "b")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Error::MissingOperator (Function::FunctionApplication (Error::UnknownToken "(") (BuiltIn::Operator (BuiltIn::Operator "(") (Core::Variable "a") (BuiltIn::Operator ")"))) (Error::MissingToken) (Core::Variable "b"))"#
+    );
+}
+
+#[test]
+fn test_parser_stray_closing_bracket() {
+    let layout = input_row! {(row "(", "a", ")", ")", "b")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Error::MissingOperator (Error::MissingOperator (Core::RoundBrackets (BuiltIn::Operator "(") (Core::Variable "a") (BuiltIn::Operator ")")) (Error::MissingToken) (Error::UnknownToken ")")) (Error::MissingToken) (Core::Variable "b"))"#
+    );
+}
+
+#[test]
+fn test_parser_function_call_in_brackets() {
+    let layout = input_row! {(row "(", "f", "(", "a", ")", "+", "b", ")")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Core::RoundBrackets (BuiltIn::Operator "(") (Arithmetic::Add (Function::FunctionApplication (Core::Variable "f") (BuiltIn::Operator (BuiltIn::Operator "(") (Core::Variable "a") (BuiltIn::Operator ")"))) (BuiltIn::Operator "+") (Core::Variable "b")) (BuiltIn::Operator ")"))"#
+    );
+}
+
+#[test]
+fn test_parser_fraction_in_brackets() {
+    let layout = input_row! {(row "(", (frac (row "(", "a", ")"), (row "b")), ")", "c")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Error::MissingOperator (Core::RoundBrackets (BuiltIn::Operator "(") (BuiltIn::Fraction 1x2 (Core::RoundBrackets (BuiltIn::Operator "(") (Core::Variable "a") (BuiltIn::Operator ")")) (Core::Variable "b")) (BuiltIn::Operator ")")) (Error::MissingToken) (Core::Variable "c"))"#
+    );
+}
+
+#[test]
+fn test_parser_sum_operand_extent() {
+    let layout = input_row! {(row "∏", "2", "*", "i", "+", "1")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Add (Calculus::Product (BuiltIn::Operator "∏") (BuiltIn::Nothing) (BuiltIn::Nothing) (Arithmetic::Multiply (Arithmetic::Number "2" 32) (BuiltIn::Operator "*") (Core::Variable "i"))) (BuiltIn::Operator "+") (Arithmetic::Number "1" 31))"#
+    );
+}
+
+#[test]
+fn test_parser_logical_negation() {
+    let layout = input_row! {(row "¬", "a", "∧", "b")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Logic::And (Logic::Not (BuiltIn::Operator "¬") (Core::Variable "a")) (BuiltIn::Operator "∧") (Core::Variable "b"))"#
+    );
+}
+
+#[test]
+fn test_parser_implies_is_right_associative() {
+    let layout = input_row! {(row "a", "⟹", "b", "⟹", "c")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Logic::Implies (Core::Variable "a") (BuiltIn::Operator "⟹") (Logic::Implies (Core::Variable "b") (BuiltIn::Operator "⟹") (Core::Variable "c")))"#
+    );
+}
+
+#[test]
+fn test_parser_quantifier() {
+    let layout = input_row! {(row "∀", "x", "∈", "S", ":", "x", ">", "0")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Logic::ForAll (BuiltIn::Operator "∀") (Logic::BoundVariable (Core::Variable "x") (BuiltIn::Operator "∈") (Core::Variable "S")) (BuiltIn::Operator ":") (Comparison::GreaterThan (Core::Variable "x") (BuiltIn::Operator ">") (Arithmetic::Number "0" 30)))"#
+    );
+}
+
+#[test]
+fn test_parser_quantifier_in_brackets() {
+    let layout = input_row! {(row "(", "∀", "x", ":", "P", ")", "∧", "Q")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Logic::And (Core::RoundBrackets (BuiltIn::Operator "(") (Logic::ForAll (BuiltIn::Operator "∀") (Logic::BoundVariable (Core::Variable "x")) (BuiltIn::Operator ":") (Core::Variable "P")) (BuiltIn::Operator ")")) (BuiltIn::Operator "∧") (Core::Variable "Q"))"#
+    );
+}
+
+#[test]
+fn test_parser_quantifier_domain_tuple() {
+    let layout = input_row! {(row "∀", "x", "∈", "A", ",", "B", ":", "P")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Logic::ForAll (BuiltIn::Operator "∀") (Logic::BoundVariable (Core::Variable "x") (BuiltIn::Operator "∈") (Collections::Tuple (Core::Variable "A") (BuiltIn::Operator ",") (Core::Variable "B"))) (BuiltIn::Operator ":") (Core::Variable "P"))"#
+    );
+}
+
+#[test]
+fn test_parser_exists_unique() {
+    let layout = input_row! {(row "∃", "!", "x", ":", "P", "∧", "Q")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Logic::ExistsUnique (BuiltIn::Operator "∃!") (Logic::BoundVariable (Core::Variable "x")) (BuiltIn::Operator ":") (Logic::And (Core::Variable "P") (BuiltIn::Operator "∧") (Core::Variable "Q")))"#
+    );
+}
+
+#[test]
+fn test_parser_scientific_notation() {
+    let layout = input_row! {(row "1", ".", "5", "e", "-", "3")};
+    let locale = Locale {
+        e_notation: true,
+        ..Default::default()
+    };
+    let (parsed, modules) = parse_row_with_locale(&layout, &locale);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Number "1.5e-3" 312e35652d33)"#
+    );
+}
+
+#[test]
+fn test_parser_e_notation_is_opt_in() {
+    let layout = input_row! {(row "2", "e", "-", "3")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Subtract (Error::MissingOperator (Arithmetic::Number "2" 32) (Error::MissingToken) (Core::Variable "e")) (BuiltIn::Operator "-") (Arithmetic::Number "3" 33))"#
+    );
+}
+
+#[test]
+fn test_parser_scientific_notation_with_sup() {
+    let layout = input_row! {(row "1", ".", "5", "·", "1", "0", (sup (row "-", "3")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::ScientificNumber (Arithmetic::Number "1.5" 312e35) (BuiltIn::Operator "·") (BuiltIn::Sup (Arithmetic::Number "10" 3130) (BuiltIn::Operator 1x1 (Arithmetic::Subtract (BuiltIn::Operator "-") (Arithmetic::Number "3" 33)))) 312e35652d33)"#
+    );
+}
 
-// TODO: Write tests for sum_{n=0}^{10} n^2
+#[test]
+fn test_parser_digit_grouping() {
+    let layout = input_row! {(row "1", "\u{2009}", "0", "0", "0", "_", "0", "0", "0")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        "(Arithmetic::Number \"1\u{2009}000_000\" 31303030303030)"
+    );
+}
+
+#[test]
+fn test_parser_hex_and_binary() {
+    let layout = input_row! {(row "0", "x", "1", "F", "+", "0", "b", "1", "0")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Add (Arithmetic::Number "0x1F" 3331) (BuiltIn::Operator "+") (Arithmetic::Number "0b10" 32))"#
+    );
+}
+
+#[test]
+fn test_parser_decimal_comma() {
+    let layout = input_row! {(row "(", "3", ",", "1", "4", ";", "2", ")")};
+    let locale = Locale {
+        decimal_separator: DecimalSeparator::Comma,
+        ..Default::default()
+    };
+    let (parsed, modules) = parse_row_with_locale(&layout, &locale);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Core::RoundBrackets (BuiltIn::Operator "(") (Collections::Tuple (Arithmetic::Number "3,14" 332e3134) (BuiltIn::Operator ";") (Arithmetic::Number "2" 32)) (BuiltIn::Operator ")"))"#
+    );
+}
+
+#[test]
+fn test_parser_quantity() {
+    let layout = input_row! {(row "9", ".", "8", "1", " ", "m", "/", "s", (sup (row "2")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Units::Quantity (BuiltIn::Whitespaces (Arithmetic::Number "9.81" 392e3831) (BuiltIn::Whitespace " ")) (Units::Divide (Units::Unit "m" 01000000000000) (BuiltIn::Operator "/") (BuiltIn::Sup (Units::Unit "s" 00000100000000) (BuiltIn::Operator 1x1 (Arithmetic::Number "2" 32)))) 0100fe00000000)"#
+    );
+}
+
+#[test]
+fn test_dimension_mismatch() {
+    let mut modules = ParseModules::new();
+    let built_in = Rc::new(BuiltInRules::new(&mut modules));
+    let core = Rc::new(CoreRules::new(&mut modules, &built_in));
+    let locale = Locale::default();
+    let arithmetic = Rc::new(ArithmeticRules::new(&mut modules, &built_in, &locale));
+    let units = Rc::new(UnitRules::new(&mut modules, &built_in, &locale));
+    let parser = parser::parser::MathParser::new(ParseModuleCollection::new(
+        built_in.clone(),
+        vec![built_in, core, arithmetic, units.clone()],
+    ));
+
+    let layout = input_row! {(row "3", " ", "m", "+", "2", " ", "s")};
+    let mismatches = units.check_dimensions(&parser.parse(&layout.values));
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(
+        (mismatches[0].range.start.0, mismatches[0].range.end.0),
+        (0, 7)
+    );
+    assert_eq!(
+        parser.get_diagnostics(&parser.parse(&layout.values))[0].message,
+        "Cannot combine m with s"
+    );
+
+    let layout = input_row! {(row "3", " ", "m", "+", "2", " ", "k", "m")};
+    assert!(units
+        .check_dimensions(&parser.parse(&layout.values))
+        .is_empty());
+}
+
+#[test]
+fn test_parser_determinant_over_modulus() {
+    let mut modules = ParseModules::new();
+    let built_in = Rc::new(BuiltInRules::new(&mut modules));
+    let core = Rc::new(CoreRules::new(&mut modules, &built_in));
+    let locale = Locale::default();
+    let linear_algebra = Rc::new(LinearAlgebraRules::new(&mut modules, &built_in));
+    let complex = Rc::new(ComplexRules::new(
+        &mut modules,
+        &built_in,
+        &locale,
+        ImaginaryUnit::I,
+    ));
+    // The complex module comes last, so its rules are tried first
+    let parser = parser::parser::MathParser::new(ParseModuleCollection::new(
+        built_in.clone(),
+        vec![built_in, core, linear_algebra, complex],
+    ));
+
+    let layout =
+        input_row! {(row "|", (table 2 x 2 (row "a"), (row "b"), (row "c"), (row "d")), "|")};
+    assert_eq!(
+        parser
+            .parse(&layout.values)
+            .with_display(modules.get_rule_name_map())
+            .to_string(),
+        r#"(LinearAlgebra::Determinant (BuiltIn::Operator "|") (LinearAlgebra::Matrix 2x2 (Core::Variable "a") (Core::Variable "b") (Core::Variable "c") (Core::Variable "d")) (BuiltIn::Operator "|"))"#
+    );
+
+    let layout = input_row! {(row "|", (table 1 x 2 (row "x"), (row "y")), "|")};
+    assert_eq!(
+        parser
+            .parse(&layout.values)
+            .with_display(modules.get_rule_name_map())
+            .to_string(),
+        r#"(Complex::Modulus (BuiltIn::Operator "|") (LinearAlgebra::ColumnVector 1x2 (Core::Variable "x") (Core::Variable "y")) (BuiltIn::Operator "|"))"#
+    );
+}
+
+fn create_complex_parser(
+    imaginary_unit: ImaginaryUnit,
+) -> (parser::parser::MathParser, ParseModules, Rc<ComplexRules>) {
+    let mut modules = ParseModules::new();
+    let built_in = Rc::new(BuiltInRules::new(&mut modules));
+    let core = Rc::new(CoreRules::new(&mut modules, &built_in));
+    let locale = Locale::default();
+    let arithmetic = Rc::new(ArithmeticRules::new(&mut modules, &built_in, &locale));
+    let function = Rc::new(FunctionRules::new(&mut modules, &built_in));
+    let complex = Rc::new(ComplexRules::new(
+        &mut modules,
+        &built_in,
+        &locale,
+        imaginary_unit,
+    ));
+    let parser = parser::parser::MathParser::new(ParseModuleCollection::new(
+        built_in.clone(),
+        vec![built_in, core, arithmetic, function, complex.clone()],
+    ));
+    (parser, modules, complex)
+}
+
+#[test]
+fn test_parser_complex_literal() {
+    let (parser, modules, complex) = create_complex_parser(ImaginaryUnit::I);
+    let layout = input_row! {(row "1", "-", "2", "i")};
+    let parsed = parser.parse(&layout.values);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Subtract (Arithmetic::Number "1" 31) (BuiltIn::Operator "-") (Complex::ImaginaryNumber (Arithmetic::Number "2" 32) (Complex::ImaginaryUnit "i") 32))"#
+    );
+    assert_eq!(
+        complex.complex_literal(&parsed),
+        Some(ComplexLiteral {
+            real: "1".into(),
+            imaginary: "-2".into(),
+        })
+    );
+}
+
+#[test]
+fn test_parser_imaginary_unit_engineering() {
+    let (parser, modules, _) = create_complex_parser(ImaginaryUnit::J);
+    let layout = input_row! {(row "R", "e", "(", "i", "+", "j", ")")};
+    let parsed = parser.parse(&layout.values);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Complex::RealPart (BuiltIn::Operator "Re") (Core::RoundBrackets (BuiltIn::Operator "(") (Arithmetic::Add (Core::Variable "i") (BuiltIn::Operator "+") (Complex::ImaginaryUnit "j")) (BuiltIn::Operator ")")))"#
+    );
+}
+
+#[test]
+fn test_parser_modulus_of_conjugate() {
+    let (parser, modules, _) = create_complex_parser(ImaginaryUnit::I);
+    let layout = input_row! {(row "|", "z\u{0304}", "|")};
+    let parsed = parser.parse(&layout.values);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        "(Complex::Modulus (BuiltIn::Operator \"|\") (Complex::Conjugate \"z\u{0304}\") (BuiltIn::Operator \"|\"))"
+    );
+}
+
+#[test]
+fn test_parser_fenced_modulus() {
+    let (parser, modules, _) = create_complex_parser(ImaginaryUnit::I);
+    let layout = input_row! {(row (fenced "|" "|" (row "z")))};
+    let parsed = parser.parse(&layout.values);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Complex::Modulus 1x1 (Core::Variable "z") 7c7c)"#
+    );
+}
+
+#[test]
+fn test_parser_matrix_and_vector() {
+    let layout = input_row! {(row (table 2 x 2 (row "1"), (row "2"), (row "3"), (row "4")), (table 1 x 2 (row "x"), (row "y")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Error::MissingOperator (LinearAlgebra::Matrix 2x2 (Arithmetic::Number "1" 31) (Arithmetic::Number "2" 32) (Arithmetic::Number "3" 33) (Arithmetic::Number "4" 34)) (Error::MissingToken) (LinearAlgebra::ColumnVector 1x2 (Core::Variable "x") (Core::Variable "y")))"#
+    );
+}
+
+#[test]
+fn test_parser_transpose_dot_product() {
+    let layout = input_row! {(row "A", (sup (row "T")), "⋅", "B", "ᵀ")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(LinearAlgebra::DotProduct (LinearAlgebra::Transpose (Core::Variable "A") (BuiltIn::Operator 1x1 (Core::Variable "T"))) (BuiltIn::Operator "⋅") (LinearAlgebra::Transpose (Core::Variable "B") (BuiltIn::Operator "ᵀ")))"#
+    );
+}
+
+#[test]
+fn test_parser_middle_dot_multiplies() {
+    let layout = input_row! {(row "2", "·", "3")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Multiply (Arithmetic::Number "2" 32) (BuiltIn::Operator "·") (Arithmetic::Number "3" 33))"#
+    );
+}
+
+#[test]
+fn test_parser_transpose_only_for_matrices() {
+    let layout = input_row! {(row "x", (sup (row "T")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(BuiltIn::Sup (Core::Variable "x") (BuiltIn::Operator 1x1 (Core::Variable "T")))"#
+    );
+
+    let layout = input_row! {(row (table 1 x 2 (row "x"), (row "y")), (sup (row "T")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(LinearAlgebra::Transpose (LinearAlgebra::ColumnVector 1x2 (Core::Variable "x") (Core::Variable "y")) (BuiltIn::Operator 1x1 (Core::Variable "T")))"#
+    );
+}
+
+#[test]
+fn test_parser_transpose_without_linear_algebra() {
+    let mut modules = ParseModules::new();
+    let built_in = Rc::new(BuiltInRules::new(&mut modules));
+    let core = Rc::new(CoreRules::new(&mut modules, &built_in));
+    let parser = parser::parser::MathParser::new(ParseModuleCollection::new(
+        built_in.clone(),
+        vec![built_in, core],
+    ));
+    // The ᵀ is a modifier letter, so it is a part of the variable name
+    let layout = input_row! {(row "B", "ᵀ")};
+    let parsed = parser.parse(&layout.values);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Core::Variable "Bᵀ")"#
+    );
+}
+
+#[test]
+fn test_parser_determinant() {
+    let layout =
+        input_row! {(row "|", (table 2 x 2 (row "a"), (row "b"), (row "c"), (row "d")), "|")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(LinearAlgebra::Determinant (BuiltIn::Operator "|") (LinearAlgebra::Matrix 2x2 (Core::Variable "a") (Core::Variable "b") (Core::Variable "c") (Core::Variable "d")) (BuiltIn::Operator "|"))"#
+    );
+}
+
+#[test]
+fn test_parser_definition() {
+    let layout = input_row! {(row "x", ":", "=", "3")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Definition::Define (Core::Variable "x") (BuiltIn::Operator ":=") (Arithmetic::Number "3" 33))"#
+    );
+}
+
+#[test]
+fn test_parser_function_definition() {
+    let layout = input_row! {(row "f", "(", "x", ")", " ", "=", "x", (sup (row "2")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Definition::Define (Function::FunctionApplication (Core::Variable "f") (BuiltIn::Operator (BuiltIn::Operator "(") (Core::Variable "x") (BuiltIn::Operator ")"))) (BuiltIn::Whitespaces (BuiltIn::Whitespace " ") (BuiltIn::Operator "=")) (BuiltIn::Sup (Core::Variable "x") (BuiltIn::Operator 1x1 (Arithmetic::Number "2" 32))))"#
+    );
+
+    // Not a definition, since 2 is not a parameter
+    let layout = input_row! {(row "f", "(", "2", ")", "=", "4")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Comparison::Equals (Function::FunctionApplication (Core::Variable "f") (BuiltIn::Operator (BuiltIn::Operator "(") (Arithmetic::Number "2" 32) (BuiltIn::Operator ")"))) (BuiltIn::Operator "=") (Arithmetic::Number "4" 34))"#
+    );
+}
+
+#[test]
+fn test_parser_definition_weaker_than_logic() {
+    let layout = input_row! {(row "P", "≔", "a", "∧", "b", "⟹", "c")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Definition::Define (Core::Variable "P") (BuiltIn::Operator "≔") (Logic::Implies (Logic::And (Core::Variable "a") (BuiltIn::Operator "∧") (Core::Variable "b")) (BuiltIn::Operator "⟹") (Core::Variable "c")))"#
+    );
+}
+
+#[test]
+fn test_parser_set_builder() {
+    let layout = input_row! {(row "{", "x", "∈", "S", "|", "x", ">", "0", "}")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Collections::SetBuilder (BuiltIn::Operator "{") (Collections::BoundVariable (Core::Variable "x") (BuiltIn::Operator "∈") (Core::Variable "S")) (BuiltIn::Operator "|") (Comparison::GreaterThan (Core::Variable "x") (BuiltIn::Operator ">") (Arithmetic::Number "0" 30)) (BuiltIn::Operator "}"))"#
+    );
+}
+
+#[test]
+fn test_parser_limit_approaches() {
+    let layout = input_row! {(row "l", "i", "m", (sub (row "x", "→", "0")), "f", "(", "x", ")")};
+    let (parsed, mut modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Lim (BuiltIn::Sub (BuiltIn::Operator "lim") (BuiltIn::Operator 1x1 (Calculus::Approaches (Core::Variable "x") (BuiltIn::Operator "→") (Arithmetic::Number "0" 30)))) (Function::FunctionApplication (Core::Variable "f") (BuiltIn::Operator (BuiltIn::Operator "(") (Core::Variable "x") (BuiltIn::Operator ")"))))"#
+    );
+    let scopes = ScopeAnalysis::new(&mut modules).free_variables(&parsed);
+    assert_eq!(scopes, vec!["f"]);
+}
+
+#[test]
+fn test_parser_limit_under() {
+    let layout = input_row! {(row (under (row "l", "i", "m"), (row "n", "→", "∞")), "a", "n")};
+    let (parsed, mut modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Calculus::Lim (BuiltIn::Operator 1x2 (BuiltIn::Operator "lim") (Calculus::Approaches (Core::Variable "n") (BuiltIn::Operator "→") (Calculus::Infinity "∞"))) (Core::Variable "an"))"#
+    );
+    let scopes = ScopeAnalysis::new(&mut modules).free_variables(&parsed);
+    assert_eq!(scopes, vec!["an"]);
+}
+
+#[test]
+fn test_parser_multiscripts() {
+    let layout =
+        input_row! {(row (multiscripts (row "C"), (row), (row), (row "6"), (row "1", "4")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(BuiltIn::Multiscripts 1x5 (Core::Variable "C") (Error::MissingToken) (Error::MissingToken) (Arithmetic::Number "6" 36) (Arithmetic::Number "14" 3134))"#
+    );
+}
+
+#[test]
+fn test_parser_text() {
+    let layout = input_row! {(row "x", "=", "0", (text (row " ", "f", "o", "r", " ", "a", "l", "l", " ")), "x")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(BuiltIn::TextBetween (Comparison::Equals (Core::Variable "x") (BuiltIn::Operator "=") (Arithmetic::Number "0" 30)) (BuiltIn::Operator 1x1 (BuiltIn::TextContent " for all ")) (Core::Variable "x"))"#
+    );
+}
+
+#[test]
+fn test_parser_fenced() {
+    let layout = input_row! {(row "f", (fenced "(" ")" (row "x")), "+", (fenced "[" ")" (row "0", ",", "1")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Add (Function::FunctionApplication (Core::Variable "f") (BuiltIn::Operator 1x1 (Core::Variable "x") 2829)) (BuiltIn::Operator "+") (BuiltIn::Fenced 1x1 (Collections::Tuple (Arithmetic::Number "0" 30) (BuiltIn::Operator ",") (Arithmetic::Number "1" 31)) 5b29))"#
+    );
+}
+
+#[test]
+fn test_parser_fenced_round_brackets() {
+    let layout = input_row! {(row (fenced "(" ")" (row "a", "+", "b")), "*", "c")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Multiply (Core::RoundBrackets 1x1 (Arithmetic::Add (Core::Variable "a") (BuiltIn::Operator "+") (Core::Variable "b")) 2829) (BuiltIn::Operator "*") (Core::Variable "c"))"#
+    );
+}
+#[test]
+fn test_parser_placeholder() {
+    let layout = input_row! {(row "⬚", "+", "1")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Add (BuiltIn::Placeholder "⬚") (BuiltIn::Operator "+") (Arithmetic::Number "1" 31))"#
+    );
+}
+
+#[test]
+fn test_parser_over() {
+    let layout = input_row! {(row (over (row "x", "+", "y"), (row "‾")), "+", "1")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Add (BuiltIn::Bar 1x2 (Arithmetic::Add (Core::Variable "x") (BuiltIn::Operator "+") (Core::Variable "y")) (BuiltIn::Operator "‾")) (BuiltIn::Operator "+") (Arithmetic::Number "1" 31))"#
+    );
+}
+
+#[test]
+fn test_parser_accents() {
+    let layout =
+        input_row! {(row (over (row "x"), (row "^")), "+", (under (row "a", "+", "b"), (row "⏟")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Add (BuiltIn::Hat 1x2 (Core::Variable "x") (BuiltIn::Operator "^")) (BuiltIn::Operator "+") (BuiltIn::UnderBrace 1x2 (Arithmetic::Add (Core::Variable "a") (BuiltIn::Operator "+") (Core::Variable "b")) (BuiltIn::Operator "⏟")))"#
+    );
+}
+
+#[test]
+fn test_parser_over_without_accent() {
+    let layout = input_row! {(row (over (row "x"), (row "n")))};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(BuiltIn::Over 1x2 (Core::Variable "x") (Core::Variable "n"))"#
+    );
+}
+
+#[test]
+fn test_scope_quantifier_domain_is_outside() {
+    let layout = input_row! {(row "∀", "x", "∈", "x", ":", "x", ">", "y")};
+    let (parsed, mut modules) = parse_row(&layout);
+    let info = ScopeAnalysis::new(&mut modules).analyze(&parsed);
+    let bound = info
+        .variables
+        .iter()
+        .map(|v| (v.name.as_str(), v.bound_by.is_some()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        bound,
+        vec![("x", true), ("x", false), ("x", true), ("y", false)]
+    );
+    assert_eq!(info.free_variables(), vec!["x", "y"]);
+}
+
+#[test]
+fn test_scope_integral_bounds_are_outside() {
+    let layout = input_row! {(row "∫", (sub (row "0")), (sup (row "t")), "x", " ", "d", "x")};
+    let (parsed, mut modules) = parse_row(&layout);
+    let free_variables = ScopeAnalysis::new(&mut modules).free_variables(&parsed);
+    assert_eq!(free_variables, vec!["t"]);
+}
+
+#[test]
+fn test_scope_shadowing() {
+    let layout = input_row! {(row "∑", (sub (row "i", "=", "0")), (sup (row "n")), "∑", (sub (row "i", "=", "0")), (sup (row "i")), "i")};
+    let (parsed, mut modules) = parse_row(&layout);
+    let info = ScopeAnalysis::new(&mut modules).analyze(&parsed);
+    assert_eq!(info.free_variables(), vec!["n"]);
+    assert_eq!(info.shadowings.len(), 1);
+    let shadowing = &info.shadowings[0];
+    assert_eq!(shadowing.name, "i");
+    assert_eq!(shadowing.outer.binder_range.start, Offset(0));
+    assert_eq!(shadowing.inner.binder_range.start, Offset(3));
+
+    // The upper bound of the inner sum still refers to the outer i
+    let upper_bound = &info.variables[3];
+    assert_eq!(
+        upper_bound.range.row_indices,
+        RowIndices::new(vec![RowIndex(5, 0)])
+    );
+    assert_eq!(upper_bound.bound_by.as_ref(), Some(&shadowing.outer));
+    let body = info.variables.last().unwrap();
+    assert_eq!(body.bound_by.as_ref(), Some(&shadowing.inner));
+}
+
+#[test]
+fn test_type_arithmetic() {
+    let layout = input_row! {(row "1", "+", (frac (row "2"), (row "3")), "*", "4")};
+    let (result, _) = type_check(&layout, &[]);
+    assert_eq!(result.root_type, Type::Number);
+    assert!(result.errors.is_empty());
+}
+
+#[test]
+fn test_type_error_number_plus_set() {
+    let layout = input_row! {(row "1", "+", "{", "x", "|", "x", ">", "0", "}")};
+    let (result, mut modules) = type_check(&layout, &[]);
+    assert_eq!(result.root_type, Type::Unknown);
+    assert_eq!(result.errors.len(), 1);
+    let error = &result.errors[0];
+    assert_eq!(
+        error.name,
+        modules.with_rule_name(PathIdentifier::new(vec!["Arithmetic".into(), "Add".into()]))
+    );
+    assert_eq!(error.operand_types, vec![Type::Number, Type::Set]);
+    assert_eq!((error.range.start, error.range.end), (Offset(0), Offset(9)));
+}
+
+#[test]
+fn test_type_error_diagnostics() {
+    let layout = input_row! {(row "1", "+", "{", "x", "|", "x", ">", "0", "}")};
+    let (module_collection, mut modules) = create_modules(&Locale::default());
+    let type_checker = TypeChecker::new(&mut modules, &module_collection);
+    let parsed = parser::parser::MathParser::new(module_collection).parse(&layout.values);
+    let diagnostics = type_checker.get_diagnostics(&parsed, &HashMap::new());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        (diagnostics[0].range.start, diagnostics[0].range.end),
+        (Offset(0), Offset(9))
+    );
+    assert_eq!(diagnostics[0].message, "Cannot be used with Number, Set");
+}
+
+#[test]
+fn test_type_juxtaposition_multiply() {
+    let layout = input_row! {(row "2", "x")};
+    let (result, _) = type_check(&layout, &[("x", Type::Number)]);
+    assert_eq!(result.root_type, Type::Number);
+    assert_eq!(result.juxtapositions.len(), 1);
+    assert_eq!(result.juxtapositions[0].1, Juxtaposition::Multiply);
+}
+
+#[test]
+fn test_type_juxtaposition_concat() {
+    let layout = input_row! {(row "\"", "a", "\"", "\"", "b", "\"")};
+    let (result, _) = type_check(&layout, &[]);
+    assert_eq!(result.root_type, Type::String);
+    assert_eq!(result.juxtapositions[0].1, Juxtaposition::Concat);
+}
+
+#[test]
+fn test_type_juxtaposition_apply() {
+    let layout = input_row! {(row "f", "(", "x", ")")};
+    let (result, _) = type_check(&layout, &[("f", Type::Function)]);
+    assert_eq!(result.juxtapositions[0].1, Juxtaposition::Apply);
+    assert!(result.errors.is_empty());
+
+    let layout = input_row! {(row "2", "(", "x", "+", "1", ")")};
+    let (result, _) = type_check(&layout, &[]);
+    assert_eq!(result.root_type, Type::Number);
+    assert_eq!(result.juxtapositions[0].1, Juxtaposition::Multiply);
+}
+
+#[test]
+fn test_type_variables() {
+    let layout = input_row! {(row "A", "*", "v", "+", "w")};
+    let (result, _) = type_check(
+        &layout,
+        &[
+            ("A", Type::Matrix),
+            ("v", Type::Vector),
+            ("w", Type::Vector),
+        ],
+    );
+    assert_eq!(result.root_type, Type::Vector);
+    assert!(result.errors.is_empty());
+
+    let layout = input_row! {(row "2", "v")};
+    let (result, _) = type_check(&layout, &[("v", Type::Boolean)]);
+    assert_eq!(result.errors.len(), 1);
+}
+
+#[test]
+fn test_type_comparison_and_logic() {
+    let layout = input_row! {(row "x", ">", "0", "∧", "⊤")};
+    let (result, _) = type_check(&layout, &[]);
+    assert_eq!(result.root_type, Type::Boolean);
+
+    let layout = input_row! {(row "1", "∧", "⊤")};
+    let (result, _) = type_check(&layout, &[]);
+    assert_eq!(result.errors.len(), 1);
+}
+
+#[test]
+fn test_type_nested_rows() {
+    let layout = input_row! {(row (table 1 x 2 (row "1"), (row "x")))};
+    let (result, _) = type_check(&layout, &[]);
+    assert_eq!(result.root_type, Type::Vector);
+
+    let layout = input_row! {(row (frac (row "1"), (row "⊤")))};
+    let (result, _) = type_check(&layout, &[]);
+    let error = &result.errors[0];
+    assert_eq!(error.operand_types, vec![Type::Number, Type::Boolean]);
+}
+
+const CHEMISTRY_MODULE: &str = r#"{
+    "module_name": "Chemistry",
+    "rules": [
+        { "name": "ReactsTo", "kind": "LeftInfix", "binding_power": 30, "symbols": ["⟶"] },
+        { "name": "Degree", "kind": "Postfix", "binding_power": 600, "symbols": ["°"] },
+        { "name": "Avogadro", "kind": "Atom", "symbols": ["N", "A"] }
+    ],
+    "autocomplete": [{ "trigger": "->", "result": ["⟶"] }]
+}"#;
+
+fn create_parser_with_declarative(json: &str) -> (parser::parser::MathParser, ParseModules) {
+    let locale = Locale::default();
+    let mut modules = ParseModules::new();
+    let built_in = Rc::new(BuiltInRules::new(&mut modules));
+    let core = Rc::new(CoreRules::new(&mut modules, &built_in));
+    let arithmetic = Rc::new(ArithmeticRules::new(&mut modules, &built_in, &locale));
+    let declarative = Rc::new(DeclarativeRules::from_json(&mut modules, json).unwrap());
+    let module_collection = ParseModuleCollection::new(
+        built_in.clone(),
+        vec![built_in, core, arithmetic, declarative],
+    );
+    (parser::parser::MathParser::new(module_collection), modules)
+}
+
+#[test]
+fn test_declarative_rules() {
+    let (parser, modules) = create_parser_with_declarative(CHEMISTRY_MODULE);
+    let layout = input_row! {(row "N", "A", "+", "9", "0", "°", "⟶", "x")};
+    let parsed = parser.parse(&layout.values);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Chemistry::ReactsTo (Arithmetic::Add (Chemistry::Avogadro "NA") (BuiltIn::Operator "+") (Chemistry::Degree (Arithmetic::Number "90" 3930) (BuiltIn::Operator "°"))) (BuiltIn::Operator "⟶") (Core::Variable "x"))"#
+    );
+}
+
+#[test]
+fn test_declarative_rules_errors() {
+    let mut modules = ParseModules::new();
+    let result = DeclarativeRules::from_json(&mut modules, r#"{ "module_name": "A" }"#);
+    assert!(matches!(result, Err(DeclarativeRulesError::Json(_))));
+
+    let result = DeclarativeRules::from_json(
+        &mut modules,
+        r#"{ "module_name": "A", "rules": [{ "name": "B", "kind": "Atom", "symbols": [] }] }"#,
+    );
+    assert!(matches!(
+        result,
+        Err(DeclarativeRulesError::MissingSymbols { rule_name }) if rule_name == "B"
+    ));
+}
+
+#[test]
+fn test_parser_type_annotation() {
+    let layout = input_row! {(row "f", ":", " ", "ℝ", " ", "→", " ", "ℝ")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Function::TypeAnnotation (Core::Variable "f") (BuiltIn::Operator ":") (Function::FunctionType (BuiltIn::Whitespaces (BuiltIn::Whitespace " ") (Core::Variable "ℝ") (BuiltIn::Whitespace " ")) (BuiltIn::Operator "→") (BuiltIn::Whitespaces (BuiltIn::Whitespace " ") (Core::Variable "ℝ"))))"#
+    );
+}
+
+#[test]
+fn test_parser_function_type_is_right_associative() {
+    let layout = input_row! {(row "f", ":", "A", "→", "B", "→", "C")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Function::TypeAnnotation (Core::Variable "f") (BuiltIn::Operator ":") (Function::FunctionType (Core::Variable "A") (BuiltIn::Operator "→") (Function::FunctionType (Core::Variable "B") (BuiltIn::Operator "→") (Core::Variable "C"))))"#
+    );
+}
+
+#[test]
+fn test_parser_lambda() {
+    let layout = input_row! {(row "f", "=", "x", "↦", "x", (sup (row "2")), "+", "1")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Comparison::Equals (Core::Variable "f") (BuiltIn::Operator "=") (Function::Lambda (Core::Variable "x") (BuiltIn::Operator "↦") (Arithmetic::Add (BuiltIn::Sup (Core::Variable "x") (BuiltIn::Operator 1x1 (Arithmetic::Number "2" 32))) (BuiltIn::Operator "+") (Arithmetic::Number "1" 31))))"#
+    );
+}
+
+#[test]
+fn test_parser_lambda_is_right_associative() {
+    let layout = input_row! {(row "x", ",", "y", "↦", "z", "↦", "x", "+", "z")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Function::Lambda (Collections::Tuple (Core::Variable "x") (BuiltIn::Operator ",") (Core::Variable "y")) (BuiltIn::Operator "↦") (Function::Lambda (Core::Variable "z") (BuiltIn::Operator "↦") (Arithmetic::Add (Core::Variable "x") (BuiltIn::Operator "+") (Core::Variable "z"))))"#
+    );
+}
+
+#[test]
+fn test_parser_compose() {
+    let layout = input_row! {(row "f", "∘", "g", "∘", "h", "(", "x", ")")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Function::Compose (Core::Variable "f") (BuiltIn::Operator "∘") (Function::Compose (Core::Variable "g") (BuiltIn::Operator "∘") (Function::FunctionApplication (Core::Variable "h") (BuiltIn::Operator (BuiltIn::Operator "(") (Core::Variable "x") (BuiltIn::Operator ")")))))"#
+    );
+}
+
+#[test]
+fn test_parser_right_infix_with_left_infix() {
+    let layout = input_row! {(row "a", "∧", "b", "⟹", "c", "⟹", "d", "∨", "e")};
+    let (parsed, modules) = parse_row(&layout);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Logic::Implies (Logic::And (Core::Variable "a") (BuiltIn::Operator "∧") (Core::Variable "b")) (BuiltIn::Operator "⟹") (Logic::Implies (Core::Variable "c") (BuiltIn::Operator "⟹") (Logic::Or (Core::Variable "d") (BuiltIn::Operator "∨") (Core::Variable "e"))))"#
+    );
+}
+
+#[test]
+fn test_scope_lambda_parameters() {
+    let layout = input_row! {(row "x", ",", "y", "↦", "x", "+", "y", "+", "c")};
+    let (parsed, mut modules) = parse_row(&layout);
+    let free_variables = ScopeAnalysis::new(&mut modules).free_variables(&parsed);
+    assert_eq!(free_variables, vec!["c"]);
+}
+
+fn create_comparison_parser() -> (
+    parser::parser::MathParser,
+    ParseModules,
+    Rc<ComparisonRules>,
+) {
+    let locale = Locale::default();
+    let mut modules = ParseModules::new();
+    let built_in = Rc::new(BuiltInRules::new(&mut modules));
+    let core = Rc::new(CoreRules::new(&mut modules, &built_in));
+    let arithmetic = Rc::new(ArithmeticRules::new(&mut modules, &built_in, &locale));
+    let comparison = Rc::new(ComparisonRules::new(&mut modules));
+    let parser = parser::parser::MathParser::new(ParseModuleCollection::new(
+        built_in.clone(),
+        vec![built_in, core, arithmetic, comparison.clone()],
+    ));
+    (parser, modules, comparison)
+}
+
+#[test]
+fn test_parser_comparison_chain() {
+    let (parser, modules, comparison) = create_comparison_parser();
+    let layout = input_row! {(row "0", "<", "x", "+", "1", "≤", "1")};
+    let parsed = parser.parse(&layout.values);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Comparison::Chain (Arithmetic::Number "0" 30) (BuiltIn::Operator "<") (Arithmetic::Add (Core::Variable "x") (BuiltIn::Operator "+") (Arithmetic::Number "1" 31)) (BuiltIn::Operator "≤") (Arithmetic::Number "1" 31))"#
+    );
+    let chain = comparison.chain(&parsed).unwrap();
+    assert_eq!(chain.operands.len(), 3);
+    assert_eq!(chain.operators, vec!["<", "≤"]);
+    assert!(comparison.check_chains(&parsed).is_empty());
+}
+
+#[test]
+fn test_parser_single_comparison_is_not_a_chain() {
+    let (parser, modules, comparison) = create_comparison_parser();
+    let layout = input_row! {(row "x", ">", "0")};
+    let parsed = parser.parse(&layout.values);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Comparison::GreaterThan (Core::Variable "x") (BuiltIn::Operator ">") (Arithmetic::Number "0" 30))"#
+    );
+    assert!(comparison.chain(&parsed).is_none());
+}
+
+#[test]
+fn test_parser_mixed_comparison_chain() {
+    let (parser, _, comparison) = create_comparison_parser();
+    let layout = input_row! {(row "a", "<", "b", ">", "c", "≥", "d")};
+    let parsed = parser.parse(&layout.values);
+    let chain = comparison.chain(&parsed).unwrap();
+    assert_eq!(chain.operators, vec!["<", ">", "≥"]);
+    let mixed_chains = comparison.check_chains(&parsed);
+    assert_eq!(mixed_chains.len(), 1);
+    assert_eq!(mixed_chains[0].range.start, Offset(0));
+    assert_eq!(mixed_chains[0].range.end, Offset(7));
+    let diagnostics = parser.get_diagnostics(&parsed);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].message,
+        "Mixes ascending and descending comparisons"
+    );
+}
+
+#[test]
+fn test_parser_comparison_chain_with_equals() {
+    let (parser, modules, comparison) = create_comparison_parser();
+    let layout = input_row! {(row "a", "<", "b", "=", "c")};
+    let parsed = parser.parse(&layout.values);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Comparison::Chain (Core::Variable "a") (BuiltIn::Operator "<") (Core::Variable "b") (BuiltIn::Operator "=") (Core::Variable "c"))"#
+    );
+    let chain = comparison.chain(&parsed).unwrap();
+    assert_eq!(chain.operators, vec!["<", "="]);
+    assert!(comparison.check_chains(&parsed).is_empty());
+}
+
+#[test]
+fn test_parser_comparison_chain_with_spaces() {
+    let (parser, modules, _) = create_comparison_parser();
+    let layout = input_row! {(row "0", " ", "<", "x", "<", " ", "1")};
+    let parsed = parser.parse(&layout.values);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Comparison::Chain (BuiltIn::Whitespaces (Arithmetic::Number "0" 30) (BuiltIn::Whitespace " ")) (BuiltIn::Operator "<") (Core::Variable "x") (BuiltIn::Whitespaces (BuiltIn::Operator "<") (BuiltIn::Whitespace " ")) (Arithmetic::Number "1" 31))"#
+    );
+}
+
+#[test]
+fn test_parser_comparison_chain_in_brackets() {
+    let (parser, modules, _) = create_comparison_parser();
+    let layout = input_row! {(row "(", "0", "<", "x", "<", "1", ")", "+", "y")};
+    let parsed = parser.parse(&layout.values);
+    assert_eq!(
+        parsed.with_display(modules.get_rule_name_map()).to_string(),
+        r#"(Arithmetic::Add (Core::RoundBrackets (BuiltIn::Operator "(") (Comparison::Chain (Arithmetic::Number "0" 30) (BuiltIn::Operator "<") (Core::Variable "x") (BuiltIn::Operator "<") (Arithmetic::Number "1" 31)) (BuiltIn::Operator ")")) (BuiltIn::Operator "+") (Core::Variable "y"))"#
+    );
+}
+
+// TODO: Add tests for tables
+// TODO: Add more default tokens
+// Document that \x basically means "this has a very specific meaning", such as \| always being a | symbol, and \sum always being a sum symbol.
+// Parse || abs || and their escaped \|| variants
+// 4. Parser for whitespace
+// 5. Parser for chains of < <=, which could be treated as a "domain restriction"
+
+// TODO: The dx at the end of an integral might not even be a closing bracket.
+// After all, it can also sometimes appear inside an integral.
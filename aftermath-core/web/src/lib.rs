@@ -1,5 +1,7 @@
 pub mod math_editor;
 pub mod math_parser;
+pub mod symbol_table;
+pub mod type_checker;
 mod utils;
 
 use log::Level;
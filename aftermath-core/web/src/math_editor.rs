@@ -20,6 +20,12 @@ pub struct MathEditorBindings {
     serializer: serde_wasm_bindgen::Serializer,
 }
 
+impl MathEditorBindings {
+    pub fn get_editor_mut(&mut self) -> &mut MathEditor {
+        &mut self.editor
+    }
+}
+
 #[wasm_bindgen]
 impl MathEditorBindings {
     #[wasm_bindgen(constructor)]
@@ -64,6 +70,14 @@ impl MathEditorBindings {
         self.editor.select_all();
     }
 
+    pub fn move_to_next_placeholder(&mut self) -> bool {
+        self.editor.move_to_next_placeholder().is_some()
+    }
+
+    pub fn move_to_previous_placeholder(&mut self) -> bool {
+        self.editor.move_to_previous_placeholder().is_some()
+    }
+
     pub fn undo(&mut self) -> bool {
         self.editor.undo().is_some()
     }
@@ -81,6 +95,12 @@ impl MathEditorBindings {
     pub fn finish_selection(&mut self) {
         self.editor.finish_selection();
     }
+    pub fn add_caret(&mut self, position: MinimalInputRowPosition) {
+        self.editor.add_caret(position);
+    }
+    pub fn select_next_occurrence(&mut self) -> bool {
+        self.editor.select_next_occurrence().is_some()
+    }
 
     pub fn copy(&mut self, data_type: SerializedDataType) -> Result<String, String> {
         self.editor.copy(data_type).map_err(|e| e.to_string())
@@ -113,6 +133,9 @@ impl MathEditorBindings {
         let result = autocomplete.serialize(&self.serializer)?;
         Ok(result)
     }
+    pub fn set_autocorrect_enabled(&mut self, keyword: String, enabled: bool) {
+        self.editor.set_autocorrect_enabled(&keyword, enabled);
+    }
 
     pub fn get_caret(&self) -> Result<JsValue, JsValue> {
         let result = self.editor.get_caret().serialize(&self.serializer)?;
@@ -127,6 +150,10 @@ impl MathEditorBindings {
         let result = self.editor.get_syntax_tree().serialize(&self.serializer)?;
         Ok(result)
     }
+    pub fn get_diagnostics(&mut self) -> Result<JsValue, JsValue> {
+        let result = self.editor.get_diagnostics().serialize(&self.serializer)?;
+        Ok(result)
+    }
 
     pub fn splice_at_range(
         &mut self,
@@ -150,6 +177,8 @@ impl<'a> From<AutocompleteResults<'a>> for AutocompleteResultsBindings {
                     rule: rule_match.rule.clone(),
                     rule_match_length: rule_match.rule_match_length,
                     input_match_length: rule_match.input_match_length,
+                    label: rule_match.rule.get_label().to_string(),
+                    is_fuzzy: rule_match.is_fuzzy(),
                 })
                 .collect(),
             caret_position,
@@ -182,4 +211,8 @@ pub struct AutocompleteRuleMatchBindings {
     /// How much of the input was matched, starting from the end where the caret is and going backwards.
     /// Used for underlining the input.
     pub input_match_length: usize,
+    /// What the popup shows. The description and the category are on the rule.
+    pub label: String,
+    /// Fuzzy matches don't get underlined, since the input has gaps.
+    pub is_fuzzy: bool,
 }
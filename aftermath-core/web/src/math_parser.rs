@@ -1,14 +1,25 @@
 use std::rc::Rc;
 
 use parser::{
+    locale::{DecimalSeparator, Locale},
     parse_module::{BoxedParseModule, ParseModule},
     parse_modules::{ParseModuleCollection, ParseModules},
     parser::MathParser,
     rule_collections::{
-        arithmetic_rules::ArithmeticRules, built_in_rules::BuiltInRules,
-        calculus_rules::CalculusRules, collections_rules::CollectionsRules,
-        comparison_rules::ComparisonRules, core_rules::CoreRules, function_rules::FunctionRules,
-        logic_rules::LogicRules, string_rules::StringRules,
+        arithmetic_rules::ArithmeticRules,
+        built_in_rules::BuiltInRules,
+        calculus_rules::CalculusRules,
+        collections_rules::CollectionsRules,
+        comparison_rules::ComparisonRules,
+        complex_rules::{ComplexRules, ImaginaryUnit},
+        core_rules::CoreRules,
+        declarative_rules::DeclarativeRules,
+        definition_rules::DefinitionRules,
+        function_rules::FunctionRules,
+        linear_algebra_rules::LinearAlgebraRules,
+        logic_rules::LogicRules,
+        string_rules::StringRules,
+        unit_rules::UnitRules,
     },
     syntax_tree::SyntaxNodeNameMap,
 };
@@ -57,8 +68,13 @@ impl ParseModuleCollectionBindings {
 }
 impl ParseModuleCollectionBindings {
     fn build(self) -> ParseModuleCollection {
+        self.to_collection()
+    }
+
+    /// For the type checker, which needs the modules without taking them.
+    pub fn to_collection(&self) -> ParseModuleCollection {
         ParseModuleCollection::new(
-            self.built_in,
+            self.built_in.clone(),
             self.modules.iter().map(|v| v.get_module()).collect(),
         )
     }
@@ -69,6 +85,9 @@ impl ParseModuleCollectionBindings {
 pub struct ParseModulesBindings {
     built_in: Rc<BuiltInRules>,
     parse_modules: ParseModules,
+    locale: Locale,
+    /// Kept for the symbol table
+    definition_rules: Option<Rc<DefinitionRules>>,
 }
 
 #[wasm_bindgen]
@@ -79,9 +98,20 @@ impl ParseModulesBindings {
         Self {
             built_in: Rc::new(BuiltInRules::new(&mut parse_modules)),
             parse_modules,
+            locale: Locale::default(),
+            definition_rules: None,
         }
     }
 
+    /// Has to be set before creating the modules, since it changes how numbers and tuples are parsed.
+    pub fn set_decimal_comma(&mut self, decimal_comma: bool) {
+        self.locale.decimal_separator = if decimal_comma {
+            DecimalSeparator::Comma
+        } else {
+            DecimalSeparator::Dot
+        };
+    }
+
     pub fn get_built_in(&self) -> BoxedParseModule {
         BoxedParseModule::new(self.built_in.clone())
     }
@@ -90,6 +120,15 @@ impl ParseModulesBindings {
         self.parse_modules.get_rule_name_map().clone()
     }
 }
+impl ParseModulesBindings {
+    pub fn get_definition_rules(&self) -> Option<Rc<DefinitionRules>> {
+        self.definition_rules.clone()
+    }
+
+    pub fn get_parse_modules_mut(&mut self) -> &mut ParseModules {
+        &mut self.parse_modules
+    }
+}
 
 #[wasm_bindgen]
 pub struct ParseModulesCreator;
@@ -100,24 +139,72 @@ impl ParseModulesCreator {
         CoreRules::new(&mut modules.parse_modules, &modules.built_in).boxed()
     }
     pub fn make_arithmetic(modules: &mut ParseModulesBindings) -> BoxedParseModule {
-        ArithmeticRules::new(&mut modules.parse_modules).boxed()
+        ArithmeticRules::new(
+            &mut modules.parse_modules,
+            &modules.built_in,
+            &modules.locale,
+        )
+        .boxed()
     }
     pub fn make_calculus(modules: &mut ParseModulesBindings) -> BoxedParseModule {
-        CalculusRules::new(&mut modules.parse_modules).boxed()
+        CalculusRules::new(&mut modules.parse_modules, &modules.built_in).boxed()
     }
     pub fn make_collections(modules: &mut ParseModulesBindings) -> BoxedParseModule {
-        CollectionsRules::new(&mut modules.parse_modules).boxed()
+        CollectionsRules::new(&mut modules.parse_modules, &modules.locale).boxed()
     }
     pub fn make_comparison(modules: &mut ParseModulesBindings) -> BoxedParseModule {
         ComparisonRules::new(&mut modules.parse_modules).boxed()
     }
+    /// In engineering mode, j is the imaginary unit.
+    pub fn make_complex(modules: &mut ParseModulesBindings, engineering: bool) -> BoxedParseModule {
+        let imaginary_unit = if engineering {
+            ImaginaryUnit::J
+        } else {
+            ImaginaryUnit::I
+        };
+        ComplexRules::new(
+            &mut modules.parse_modules,
+            &modules.built_in,
+            &modules.locale,
+            imaginary_unit,
+        )
+        .boxed()
+    }
+    /// Builds a module from a JSON description, see `ModuleDescription` for the format.
+    pub fn make_declarative(
+        modules: &mut ParseModulesBindings,
+        json: &str,
+    ) -> Result<BoxedParseModule, String> {
+        DeclarativeRules::from_json(&mut modules.parse_modules, json)
+            .map(|v| v.boxed())
+            .map_err(|error| error.to_string())
+    }
+    pub fn make_definition(modules: &mut ParseModulesBindings) -> BoxedParseModule {
+        let definition_rules = Rc::new(DefinitionRules::new(
+            &mut modules.parse_modules,
+            &modules.built_in,
+        ));
+        modules.definition_rules = Some(definition_rules.clone());
+        BoxedParseModule::new(definition_rules)
+    }
     pub fn make_function(modules: &mut ParseModulesBindings) -> BoxedParseModule {
         FunctionRules::new(&mut modules.parse_modules, &modules.built_in).boxed()
     }
+    pub fn make_linear_algebra(modules: &mut ParseModulesBindings) -> BoxedParseModule {
+        LinearAlgebraRules::new(&mut modules.parse_modules, &modules.built_in).boxed()
+    }
     pub fn make_logic(modules: &mut ParseModulesBindings) -> BoxedParseModule {
-        LogicRules::new(&mut modules.parse_modules).boxed()
+        LogicRules::new(&mut modules.parse_modules, &modules.built_in).boxed()
     }
     pub fn make_string(modules: &mut ParseModulesBindings) -> BoxedParseModule {
         StringRules::new(&mut modules.parse_modules).boxed()
     }
+    pub fn make_units(modules: &mut ParseModulesBindings) -> BoxedParseModule {
+        UnitRules::new(
+            &mut modules.parse_modules,
+            &modules.built_in,
+            &modules.locale,
+        )
+        .boxed()
+    }
 }
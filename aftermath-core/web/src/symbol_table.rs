@@ -0,0 +1,88 @@
+use caret::symbol_table::{EditorId, SymbolTable};
+use input_tree::focus::{MinimalInputRowPosition, MinimalInputRowRange};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{math_editor::MathEditorBindings, math_parser::ParseModulesBindings};
+
+#[wasm_bindgen]
+pub struct SymbolTableBindings {
+    symbol_table: SymbolTable,
+    serializer: serde_wasm_bindgen::Serializer,
+}
+
+#[wasm_bindgen]
+impl SymbolTableBindings {
+    /// Requires the definition module to be created first.
+    #[wasm_bindgen(constructor)]
+    pub fn new(modules: &ParseModulesBindings) -> Result<SymbolTableBindings, String> {
+        let definition_rules = modules
+            .get_definition_rules()
+            .ok_or("The definition module has not been created")?;
+        Ok(Self {
+            symbol_table: SymbolTable::new(definition_rules),
+            serializer: serde_wasm_bindgen::Serializer::new(),
+        })
+    }
+
+    pub fn update(&mut self, editor_id: usize, editor: &mut MathEditorBindings) {
+        self.symbol_table
+            .update(EditorId(editor_id), editor.get_editor_mut());
+    }
+
+    pub fn remove(&mut self, editor_id: usize) {
+        self.symbol_table.remove(EditorId(editor_id));
+    }
+
+    pub fn get_undefined_names(&self, editor_id: usize) -> Result<JsValue, JsValue> {
+        let undefined_names: Vec<ReferenceBindings> = self
+            .symbol_table
+            .undefined_names(EditorId(editor_id))
+            .into_iter()
+            .map(|reference| ReferenceBindings {
+                name: reference.name.clone(),
+                range: reference.range.clone(),
+            })
+            .collect();
+        let result = undefined_names.serialize(&self.serializer)?;
+        Ok(result)
+    }
+
+    pub fn go_to_definition(
+        &self,
+        editor_id: usize,
+        position: MinimalInputRowPosition,
+    ) -> Result<JsValue, JsValue> {
+        let location = self
+            .symbol_table
+            .go_to_definition(EditorId(editor_id), &position)
+            .map(|(editor_id, range)| DefinitionLocationBindings {
+                editor_id: editor_id.0,
+                range,
+            });
+        let result = location.serialize(&self.serializer)?;
+        Ok(result)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "wasm",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
+pub struct ReferenceBindings {
+    pub name: String,
+    pub range: MinimalInputRowRange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "wasm",
+    derive(tsify::Tsify),
+    tsify(into_wasm_abi, from_wasm_abi)
+)]
+pub struct DefinitionLocationBindings {
+    pub editor_id: usize,
+    pub range: MinimalInputRowRange,
+}
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use parser::type_checker::TypeChecker;
+use serde::Serialize;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::{
+    math_editor::MathEditorBindings,
+    math_parser::{ParseModuleCollectionBindings, ParseModulesBindings},
+};
+
+#[wasm_bindgen]
+pub struct TypeCheckerBindings {
+    type_checker: TypeChecker,
+    serializer: serde_wasm_bindgen::Serializer,
+}
+
+#[wasm_bindgen]
+impl TypeCheckerBindings {
+    /// Requires the modules to be added to the collection first.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        modules: &mut ParseModulesBindings,
+        parse_modules: &ParseModuleCollectionBindings,
+    ) -> Self {
+        Self {
+            type_checker: TypeChecker::new(
+                modules.get_parse_modules_mut(),
+                &parse_modules.to_collection(),
+            ),
+            serializer: serde_wasm_bindgen::Serializer::new(),
+        }
+    }
+
+    /// Free variables have an unknown type, so they never cause a type error.
+    pub fn get_diagnostics(&self, editor: &mut MathEditorBindings) -> Result<JsValue, JsValue> {
+        let syntax_tree = editor.get_editor_mut().get_syntax_tree();
+        let result = self
+            .type_checker
+            .get_diagnostics(syntax_tree, &HashMap::new())
+            .serialize(&self.serializer)?;
+        Ok(result)
+    }
+}
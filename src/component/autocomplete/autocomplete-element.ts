@@ -1,6 +1,15 @@
 import type { ViewportCoordinate } from "../../rendering/viewport-coordinate";
 import { createNode } from "../../utils/dom-utils";
 
+/**
+ * One line in the autocomplete popup.
+ */
+export interface AutocompleteItem {
+  label: string;
+  /** A short explanation, like "Integral with bounds" */
+  description?: string | null;
+}
+
 /**
  * There's only one autocomplete element.
  * However, the autocomplete logic is separate from the element. See caret instead.
@@ -50,13 +59,18 @@ export class AutocompleteElement {
     this.#element.style.top = `${position.y - parentPos.top}px`;
   }
 
-  setElements(elements: string[]) {
+  setElements(elements: AutocompleteItem[], selectedIndex: number) {
     this.#listElement.innerHTML = "";
-    for (const element of elements) {
-      const li = document.createElement("li");
-      li.innerText = element;
+    elements.forEach((element, index) => {
+      const li = createNode("li", {
+        classList: index === selectedIndex ? ["autocomplete-selected"] : [],
+      });
+      li.append(createNode("span", { classList: ["autocomplete-label"], innerText: element.label }));
+      if (element.description) {
+        li.append(createNode("span", { classList: ["autocomplete-description"], innerText: element.description }));
+      }
       this.#listElement.append(li);
-    }
+    });
     this.setVisibility(elements.length > 0);
   }
 }
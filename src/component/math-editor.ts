@@ -14,6 +14,7 @@ import {
   MathEditorBindings,
   MathEditorHelper,
   ModulesCreator,
+  DeclarativeModuleNames,
   type MinimalInputRowPosition,
   type SyntaxNode,
 } from "./../core";
@@ -22,7 +23,7 @@ import { AutocompleteElement } from "./autocomplete/autocomplete-element";
 import { CaretDomElement } from "./caret/single-caret-element";
 import { createNode, htmlToElement } from "../utils/dom-utils";
 import { keyIn } from "../utils/pattern-matching-utils";
-import { ViewportMath } from "../rendering/viewport-coordinate";
+import { ViewportMath, type ViewportCoordinate } from "../rendering/viewport-coordinate";
 
 class RenderTaskQueue {
   tasks: (() => void)[] = [];
@@ -124,7 +125,8 @@ export class MathEditor extends HTMLElement {
 
     // Rendering
     this.renderer = new MathMLRenderer(
-      new Map(Array.from(ModulesCreator.get_syntax_node_name_map().values, ([k, v]) => [joinPathIdentifier(k), v]))
+      new Map(Array.from(ModulesCreator.get_syntax_node_name_map().values, ([k, v]) => [joinPathIdentifier(k), v])),
+      DeclarativeModuleNames
     );
 
     this.syntaxTree = MathEditorHelper.getSyntaxTree(this.mathEditor);
@@ -150,10 +152,22 @@ export class MathEditor extends HTMLElement {
         this.mathEditor.move_caret("Left", "Char");
       } else if (ev.key === "ArrowRight") {
         this.mathEditor.move_caret("Right", "Char");
+      } else if (ev.key === "Tab") {
+        // Jump to the next thing that has to be filled in. Without any, the focus leaves the editor as usual.
+        const moved = ev.shiftKey
+          ? this.mathEditor.move_to_previous_placeholder()
+          : this.mathEditor.move_to_next_placeholder();
+        if (moved) {
+          ev.preventDefault();
+        }
       } else if (ev.code === "KeyZ" && ev.ctrlKey) {
         this.mathEditor.undo();
       } else if (ev.code === "KeyY" && ev.ctrlKey) {
         this.mathEditor.redo();
+      } else if (ev.code === "KeyD" && ev.ctrlKey) {
+        // Like in code editors, this adds a caret at the next occurrence of the selection
+        this.mathEditor.select_next_occurrence();
+        ev.preventDefault();
       }
       this.updateInput();
     });
@@ -238,6 +252,11 @@ export class MathEditor extends HTMLElement {
       if (!newPosition) return;
       container.setPointerCapture(e.pointerId);
       focusCallback();
+      if (e.altKey) {
+        this.mathEditor.add_caret(newPosition);
+        this.updateInput();
+        return;
+      }
       isPointerDown = true;
       this.mathEditor.start_selection(newPosition, "Char");
       this.updateInput();
@@ -366,6 +385,7 @@ export class MathEditor extends HTMLElement {
     if (!this.isConnected) return;
     let carets = MathEditorHelper.getCaret(this.mathEditor);
     this.caretsContainer.replaceChildren();
+    let autocompletePosition: ViewportCoordinate | null = null;
     for (const caret of carets) {
       const element = new CaretDomElement();
       this.caretsContainer.append(element.element);
@@ -386,6 +406,10 @@ export class MathEditor extends HTMLElement {
           y: renderedCaret.baseline + caretSize * 0.1,
         });
         element.setHeight(caretSize);
+        autocompletePosition ??= {
+          x: renderedCaret.rect.x + renderedCaret.rect.width,
+          y: renderedCaret.rect.y + renderedCaret.rect.height,
+        };
 
         // Render selection
         element.clearSelections();
@@ -444,14 +468,14 @@ export class MathEditor extends HTMLElement {
     the text is aligned with the caret
     the not yet typed part of the autocomplete is bolded or something
       */
-    /*this.autocomplete.setElements(this.carets.autocompleteResults.flatMap((v) => v.result.potentialRules.map((v) => v.value)));
-    const mainCaretBounds = this.carets.mainCaretBounds;
-    if (mainCaretBounds) {
-      this.autocomplete.setPosition({
-        x: mainCaretBounds.x,
-        y: mainCaretBounds.y + mainCaretBounds.height,
-      });
-    }*/
+    const autocomplete = MathEditorHelper.getAutocomplete(this.mathEditor);
+    this.autocomplete.setElements(
+      (autocomplete?.matches ?? []).map((v) => ({ label: v.label, description: v.rule.description })),
+      autocomplete?.selected_index ?? 0
+    );
+    if (autocompletePosition !== null) {
+      this.autocomplete.setPosition(autocompletePosition);
+    }
 
     /*
 
@@ -10,11 +10,13 @@ import init, {
   type RowIndices,
   type SerializedDataType,
   type AutocompleteResultsBindings,
+  type Diagnostic,
   ParseModulesBindings,
   BoxedParseModule,
   ParseModuleCollectionBindings,
   MathParserBindings,
   ParseModulesCreator,
+  TypeCheckerBindings,
 } from "../../aftermath-core/pkg";
 import { assert } from "../utils/assert";
 
@@ -31,17 +33,31 @@ export const MathModules = {
   Calculus: ParseModulesCreator.make_calculus(ModulesCreator),
   Collections: ParseModulesCreator.make_collections(ModulesCreator),
   Comparison: ParseModulesCreator.make_comparison(ModulesCreator),
+  Definition: ParseModulesCreator.make_definition(ModulesCreator),
   Function: ParseModulesCreator.make_function(ModulesCreator),
+  LinearAlgebra: ParseModulesCreator.make_linear_algebra(ModulesCreator),
   Logic: ParseModulesCreator.make_logic(ModulesCreator),
   String: ParseModulesCreator.make_string(ModulesCreator),
+  // Not part of the default parser, since it reads "2 s" as two seconds
+  Units: ParseModulesCreator.make_units(ModulesCreator),
+  // Not part of the default parser, since it reads "i" as the imaginary unit
+  Complex: ParseModulesCreator.make_complex(ModulesCreator, false),
 };
 
-export function makeMathParserWith(modules: BoxedParseModule[]) {
+function makeModuleCollection(modules: BoxedParseModule[]) {
   const collection = new ParseModuleCollectionBindings(ModulesCreator);
   for (let module of modules) {
     collection.add_module(module);
   }
-  return new MathParserBindings(collection);
+  return collection;
+}
+
+export function makeMathParserWith(modules: BoxedParseModule[]) {
+  return new MathParserBindings(makeModuleCollection(modules));
+}
+
+export function makeTypeCheckerWith(modules: BoxedParseModule[]) {
+  return new TypeCheckerBindings(ModulesCreator, makeModuleCollection(modules));
 }
 
 export const MathEditorHelper = {
@@ -63,23 +79,40 @@ export const MathEditorHelper = {
   getSyntaxTree(mathEditor: MathEditorBindings): SyntaxNode {
     return mathEditor.get_syntax_tree();
   },
+  getDiagnostics(mathEditor: MathEditorBindings, typeChecker: TypeCheckerBindings = DefaultTypeChecker): Diagnostic[] {
+    return [...mathEditor.get_diagnostics(), ...typeChecker.get_diagnostics(mathEditor)];
+  },
   spliceAtRange(mathEditor: MathEditorBindings, range: MinimalInputRowRange, values: InputNode[]) {
     return mathEditor.splice_at_range(range, values);
   },
 };
 
+/** Names of the modules that were created from a JSON description, the renderer gives them a generic look. */
+export const DeclarativeModuleNames: string[] = [];
+
+/** Creates a module from a JSON description, see `ModuleDescription` in the parser crate. */
+export function makeDeclarativeModule(json: string): BoxedParseModule {
+  const module = ParseModulesCreator.make_declarative(ModulesCreator, json);
+  DeclarativeModuleNames.push(JSON.parse(json).module_name);
+  return module;
+}
+
 // TODO: Make this configurable
-export const DefaultParser = makeMathParserWith([
+const DefaultModules = [
   // MathModules.BuiltIn is already included
   MathModules.Core,
   MathModules.Arithmetic,
   MathModules.Calculus,
   MathModules.Collections,
   MathModules.Comparison,
+  MathModules.Definition,
   MathModules.Function,
+  MathModules.LinearAlgebra,
   MathModules.Logic,
   MathModules.String,
-]);
+];
+export const DefaultParser = makeMathParserWith(DefaultModules);
+export const DefaultTypeChecker = makeTypeCheckerWith(DefaultModules);
 
 export function isInputRow(value: InputRow | InputNode | (InputRow | InputNode)[]): value is InputRow {
   if (!Array.isArray(value) && "values" in value) {
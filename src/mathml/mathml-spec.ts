@@ -62,10 +62,10 @@ export const MathMLTagsExpectedChildrenCount: Record<MathMLTags, number | null>
   msubsup: "TODO:" as any as number,
   munder: 2,
   mover: 2,
-  munderover: "TODO:" as any as number,
-  mmultiscripts: "TODO:" as any as number,
+  munderover: 3,
+  mmultiscripts: null,
   none: "TODO:" as any as number,
-  mprescripts: "TODO:" as any as number,
+  mprescripts: 0,
   mtable: null,
   mtr: null,
   mtd: null,
@@ -61,6 +61,61 @@ function sub(value: InputRow): InputNode {
     ],
   };
 }
+function under(values: [InputRow, InputRow]): InputNode {
+  return {
+    Container: [
+      "Under",
+      {
+        values,
+        width: 1,
+      },
+    ],
+  };
+}
+function over(values: [InputRow, InputRow]): InputNode {
+  return {
+    Container: [
+      "Over",
+      {
+        values,
+        width: 1,
+      },
+    ],
+  };
+}
+function underOver(values: [InputRow, InputRow, InputRow]): InputNode {
+  return {
+    Container: [
+      "UnderOver",
+      {
+        values,
+        width: 1,
+      },
+    ],
+  };
+}
+function multiscripts(values: [InputRow, InputRow, InputRow, InputRow, InputRow]): InputNode {
+  return {
+    Container: [
+      "Multiscripts",
+      {
+        values,
+        width: 1,
+      },
+    ],
+  };
+}
+function text(value: InputRow): InputNode {
+  return {
+    Container: [
+      "Text",
+      {
+        values: [value],
+        width: 1,
+      },
+    ],
+  };
+}
 function table(values: InputRow[], width: number): InputNode {
   return {
     Container: [
@@ -83,7 +138,9 @@ function toMathLayout(element: Element, errors: Error[]): (InputRow | InputNode)
     return wrapInRow(children.flatMap((c) => toMathLayout(c, errors)));
   } else if (tagIs(element, "semantics") && children.length > 0) {
     return toMathLayout(children[0], errors);
-  } else if (tagIs(element, "mtext", "ms")) {
+  } else if (tagIs(element, "mtext")) {
+    return text(wrapInRow(unicodeSplit(getText(element)).map((v) => symbol(v))));
+  } else if (tagIs(element, "ms")) {
     return [symbol('"'), ...unicodeSplit(getText(element)).map((v) => symbol(v)), symbol('"')];
   } else if (tagIs(element, "mi", "mn")) {
     return unicodeSplit(getText(element)).map((v) => symbol(v));
@@ -126,28 +183,38 @@ function toMathLayout(element: Element, errors: Error[]): (InputRow | InputNode)
       ]
     );
   } else if (tagIs(element, "munder")) {
-    // It's usually a sub/sup
     return (
-      expectNChildren(element, 2, errors) ?? [
-        ...wrapInRow(toMathLayout(children[0], errors)).values,
-        sub(wrapInRow(toMathLayout(children[1], errors))),
-      ]
+      expectNChildren(element, 2, errors) ??
+      under(children.map((c) => wrapInRow(toMathLayout(c, errors))) as [InputRow, InputRow])
     );
   } else if (tagIs(element, "mover")) {
     return (
-      expectNChildren(element, 2, errors) ?? [
-        ...wrapInRow(toMathLayout(children[0], errors)).values,
-        sup(wrapInRow(toMathLayout(children[1], errors))),
-      ]
+      expectNChildren(element, 2, errors) ??
+      over(children.map((c) => wrapInRow(toMathLayout(c, errors))) as [InputRow, InputRow])
     );
   } else if (tagIs(element, "munderover")) {
     return (
-      expectNChildren(element, 3, errors) ?? [
-        ...wrapInRow(toMathLayout(children[0], errors)).values,
-        sub(wrapInRow(toMathLayout(children[1], errors))),
-        sup(wrapInRow(toMathLayout(children[2], errors))),
-      ]
+      expectNChildren(element, 3, errors) ??
+      underOver(children.map((c) => wrapInRow(toMathLayout(c, errors))) as [InputRow, InputRow, InputRow])
     );
+  } else if (tagIs(element, "mmultiscripts")) {
+    // Only the first pair of scripts on each side is kept, a <none/> is an empty script
+    const prescriptsIndex = children.findIndex((c) => tagIs(c, "mprescripts"));
+    const postscripts = prescriptsIndex === -1 ? children.slice(1) : children.slice(1, prescriptsIndex);
+    const prescripts = prescriptsIndex === -1 ? [] : children.slice(prescriptsIndex + 1);
+    const script = (c: Element | undefined) =>
+      c === undefined || tagIs(c, "none") ? wrapInRow(null) : wrapInRow(toMathLayout(c, errors));
+    if (children.length === 0) {
+      errors.push(new Error("Expected a base in mmultiscripts"));
+      return symbol("Error");
+    }
+    return multiscripts([
+      wrapInRow(toMathLayout(children[0], errors)),
+      script(postscripts[0]),
+      script(postscripts[1]),
+      script(prescripts[0]),
+      script(prescripts[1]),
+    ]);
   } else if (tagIs(element, "mtable")) {
     if (!children.every((c) => tagIs(c, "mtr") && [...c.children].every((cc) => tagIs(cc, "mtd")))) {
       errors.push(new Error("Unexpected children " + element));
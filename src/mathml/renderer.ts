@@ -9,8 +9,11 @@ import type {
 } from "../rendering/render-result";
 import { assert } from "../utils/assert";
 import { MathMLRenderResult } from "./render-result";
+import { BoundsMathMLElement } from "./renderer/rendered-bounds-element";
 import { SimpleContainerMathMLElement } from "./renderer/rendered-container-element";
+import { FencedMathMLElement } from "./renderer/rendered-fenced-element";
 import { MissingMathMLElement } from "./renderer/rendered-missing";
+import { MultiscriptsMathMLElement } from "./renderer/rendered-multiscripts-element";
 import { NothingMathMLElement } from "./renderer/rendered-nothing";
 import { RootMathMLElement } from "./renderer/rendered-root-element";
 import { RowsContainerMathMLElement } from "./renderer/rendered-rows-element";
@@ -37,7 +40,10 @@ export class MathMLRenderer implements Renderer<MathMLElement> {
 
   private readonly nameMap: NameMap;
 
-  constructor(nameMap: NameMap) {
+  /**
+   * @param declarativeModules Modules that were created from a description at runtime. Their rules get a generic renderer.
+   */
+  constructor(nameMap: NameMap, declarativeModules: readonly string[] = []) {
     this.nameMap = nameMap;
     // TODO:
     // If it's a square root, make the 2 a bit lighter?
@@ -109,6 +115,59 @@ export class MathMLRenderer implements Renderer<MathMLElement> {
         assert(hasSyntaxNodeChildren(syntaxTree, "NewRows"));
         return new TableMathMLElement(syntaxTree, rowIndex, this);
       });
+      // The rows are stored in MathML order, with the base first
+      builtIn.add("Under", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "NewRows"));
+        return new RowsContainerMathMLElement(syntaxTree, rowIndex, "munder", this);
+      });
+      builtIn.add("Over", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "NewRows"));
+        return new RowsContainerMathMLElement(syntaxTree, rowIndex, "mover", this);
+      });
+      // Accents and braces, the accent is an operator in the annotation row
+      builtIn.add(["Hat", "Bar", "Vec", "Dot", "DoubleDot", "Tilde", "OverBrace"], (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "NewRows"));
+        return new RowsContainerMathMLElement(syntaxTree, rowIndex, "mover", this);
+      });
+      builtIn.add(["UnderBar", "UnderBrace"], (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "NewRows"));
+        return new RowsContainerMathMLElement(syntaxTree, rowIndex, "munder", this);
+      });
+      builtIn.add("UnderOver", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "NewRows"));
+        return new RowsContainerMathMLElement(syntaxTree, rowIndex, "munderover", this);
+      });
+      builtIn.add("Multiscripts", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "NewRows"));
+        return new MultiscriptsMathMLElement(syntaxTree, rowIndex, this);
+      });
+      builtIn.add("Placeholder", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "Leaf"));
+        return new TextMathMLElement(syntaxTree, rowIndex, "mi");
+      });
+      // Brackets that aren't covered by a more specific rule, like the half-open interval [0, 1)
+      builtIn.add("Fenced", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "NewRows"));
+        return new FencedMathMLElement(syntaxTree, rowIndex, this);
+      });
+      builtIn.add("Text", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "NewRows"));
+        return new RowsContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
+      });
+      builtIn.add("TextContent", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "Leaf"));
+        return new TextMathMLElement(syntaxTree, rowIndex, "mtext");
+      });
+      builtIn.add("TextBetween", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
+        return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this, {
+          // The text is the operator
+          newRowsOperatorOverride: (node) => {
+            assert(hasSyntaxNodeChildren(node, "NewRows"));
+            return new RowsContainerMathMLElement(node, rowIndex, "mrow", this);
+          },
+        });
+      });
     }
     {
       const error = this.rendererCollection("Error");
@@ -133,9 +192,25 @@ export class MathMLRenderer implements Renderer<MathMLElement> {
         return new TextMathMLElement(syntaxTree, rowIndex, "mi");
       });
       core.add("RoundBrackets", (syntaxTree, rowIndex) => {
+        if (hasSyntaxNodeChildren(syntaxTree, "NewRows")) {
+          return new FencedMathMLElement(syntaxTree, rowIndex, this);
+        }
+        assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
+        return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this, { stretchyOperators: true });
+      });
+      core.add("Piecewise", (syntaxTree, rowIndex) => {
+        // A stretchy curly bracket followed by a table, like \begin{cases}
         assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
         return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this, { stretchyOperators: true });
       });
+      core.add("Cases", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "NewRows"));
+        return new TableMathMLElement(syntaxTree, rowIndex, this);
+      });
+      core.add(["CaseValue", "CaseCondition"], (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
+        return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
+      });
     }
     {
       const arithmetic = this.rendererCollection("Arithmetic");
@@ -143,10 +218,13 @@ export class MathMLRenderer implements Renderer<MathMLElement> {
         assert(hasSyntaxNodeChildren(syntaxTree, "Leaf"));
         return new TextMathMLElement(syntaxTree, rowIndex, "mn");
       });
-      arithmetic.add(["Add", "Subtract", "Multiply", "Divide", "Exponent", "Factorial"], (syntaxTree, rowIndex) => {
-        assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
-        return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
-      });
+      arithmetic.add(
+        ["Add", "Subtract", "Multiply", "Divide", "Exponent", "Factorial", "ScientificNumber"],
+        (syntaxTree, rowIndex) => {
+          assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
+          return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
+        }
+      );
     }
     {
       const calculus = this.rendererCollection("Calculus");
@@ -154,11 +232,45 @@ export class MathMLRenderer implements Renderer<MathMLElement> {
         assert(hasSyntaxNodeChildren(syntaxTree, "Leaf"));
         return new TextMathMLElement(syntaxTree, rowIndex, "mi");
       });
-      calculus.add(["Lim", "LimSup", "LimInf"], (syntaxTree, rowIndex) => {
+      calculus.add(["Lim", "LimSup", "LimInf", "Approaches"], (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
+        return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this, {
+          // A limit can be written with the x → 0 under it
+          newRowsOperatorOverride: (node) => {
+            assert(hasSyntaxNodeChildren(node, "NewRows"));
+            return new RowsContainerMathMLElement(node, rowIndex, "munder", this);
+          },
+        });
+      });
+      calculus.add(
+        ["Integral", "Sum", "Product", "Coproduct", "BigUnion", "BigIntersection"],
+        (syntaxTree, rowIndex) => {
+          assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
+          return new BoundsMathMLElement(syntaxTree, rowIndex, this);
+        }
+      );
+      calculus.add("BoundIndex", (syntaxTree, rowIndex) => {
         assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
         return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
       });
-      calculus.add(["Integral", "Sum"], (syntaxTree, rowIndex) => {
+      calculus.add("Derivative", (syntaxTree, rowIndex) => {
+        // Leibniz notation is a fraction, Lagrange notation is a postfix operator, Newton notation is a symbol
+        // A d/dx that is applied to something is a prefix operator that is a fraction
+        if (hasSyntaxNodeChildren(syntaxTree, "NewRows")) {
+          return new RowsContainerMathMLElement(syntaxTree, rowIndex, "mfrac", this);
+        } else if (hasSyntaxNodeChildren(syntaxTree, "Children")) {
+          return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this, {
+            newRowsOperatorOverride: (node) => {
+              assert(hasSyntaxNodeChildren(node, "NewRows"));
+              return new RowsContainerMathMLElement(node, rowIndex, "mfrac", this);
+            },
+          });
+        } else {
+          assert(hasSyntaxNodeChildren(syntaxTree, "Leaf"));
+          return new TextMathMLElement(syntaxTree, rowIndex, "mi");
+        }
+      });
+      calculus.add("Differential", (syntaxTree, rowIndex) => {
         assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
         return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
       });
@@ -166,25 +278,38 @@ export class MathMLRenderer implements Renderer<MathMLElement> {
     {
       const comparison = this.rendererCollection("Comparison");
       comparison.add(
-        ["Equals", "GreaterThan", "LessThan", "GreaterThanOrEquals", "LessThanOrEquals"],
+        ["Equals", "GreaterThan", "LessThan", "GreaterThanOrEquals", "LessThanOrEquals", "Chain"],
         (syntaxTree, rowIndex) => {
           assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
           return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
         }
       );
     }
+    {
+      const definition = this.rendererCollection("Definition");
+      definition.add("Define", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
+        return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
+      });
+    }
     {
       const collection = this.rendererCollection("Collections");
-      collection.add("Tuple", (syntaxTree, rowIndex) => {
+      collection.add(["Tuple", "SetBuilder", "BoundVariable"], (syntaxTree, rowIndex) => {
         assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
         return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
       });
     }
     {
       const functions = this.rendererCollection("Function");
-      functions.add("FunctionApplication", (syntaxTree, rowIndex) => {
+      functions.add(["FunctionApplication", "Compose", "FunctionType", "TypeAnnotation", "Lambda"], (syntaxTree, rowIndex) => {
         assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
-        return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
+        return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this, {
+          // The arguments can be a fenced group
+          newRowsOperatorOverride: (node) => {
+            assert(hasSyntaxNodeChildren(node, "NewRows"));
+            return new FencedMathMLElement(node, rowIndex, this);
+          },
+        });
       });
     }
     {
@@ -200,12 +325,82 @@ export class MathMLRenderer implements Renderer<MathMLElement> {
         assert(hasSyntaxNodeChildren(syntaxTree, "Leaf"));
         return new TextMathMLElement(syntaxTree, rowIndex, "mi");
       });
-      logic.add(["And", "Or", "Not", "Equivalent", "Implies"], (syntaxTree, rowIndex) => {
+      logic.add(
+        ["And", "Or", "Not", "Equivalent", "Implies", "ForAll", "Exists", "ExistsUnique", "BoundVariable"],
+        (syntaxTree, rowIndex) => {
+          assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
+          return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
+        }
+      );
+    }
+
+    {
+      const linearAlgebra = this.rendererCollection("LinearAlgebra");
+      linearAlgebra.add(["Matrix", "ColumnVector"], (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "NewRows"));
+        return new TableMathMLElement(syntaxTree, rowIndex, this);
+      });
+      linearAlgebra.add("Vector", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "Leaf"));
+        return new TextMathMLElement(syntaxTree, rowIndex, "mi");
+      });
+      linearAlgebra.add(["Transpose", "Inverse"], (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
+        // Either A^T with a superscript, or Aᵀ with a modifier letter
+        if (hasSyntaxNodeChildren(syntaxTree.children.Children[1], "NewRows")) {
+          return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "msup", this, {
+            newRowsOperatorOverride: (node) => {
+              assert(hasSyntaxNodeChildren(node, "NewRows"));
+              return new RowsContainerMathMLElement(node, rowIndex, "mrow", this);
+            },
+          });
+        }
+        return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
+      });
+      linearAlgebra.add(["Determinant", "DotProduct", "CrossProduct"], (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
+        return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
+      });
+    }
+    {
+      const complex = this.rendererCollection("Complex");
+      complex.add(["ImaginaryUnit", "Conjugate"], (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "Leaf"));
+        return new TextMathMLElement(syntaxTree, rowIndex, "mi");
+      });
+      complex.add(["ImaginaryNumber", "Modulus", "RealPart", "ImaginaryPart", "Argument"], (syntaxTree, rowIndex) => {
+        if (hasSyntaxNodeChildren(syntaxTree, "NewRows")) {
+          return new FencedMathMLElement(syntaxTree, rowIndex, this);
+        }
+        assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
+        return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
+      });
+    }
+    {
+      const units = this.rendererCollection("Units");
+      units.add("Unit", (syntaxTree, rowIndex) => {
+        assert(hasSyntaxNodeChildren(syntaxTree, "Leaf"));
+        return new TextMathMLElement(syntaxTree, rowIndex, "mi");
+      });
+      units.add(["Quantity", "Multiply", "Divide"], (syntaxTree, rowIndex) => {
         assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
         return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
       });
     }
 
+    this.nameMap.forEach((name, path) => {
+      if (this.renderers.has(name) || !declarativeModules.some((v) => path.startsWith(v + "::"))) {
+        return;
+      }
+      this.renderers.set(name, (syntaxTree, rowIndex) => {
+        if (hasSyntaxNodeChildren(syntaxTree, "Leaf")) {
+          return new TextMathMLElement(syntaxTree, rowIndex, "mi");
+        }
+        assert(hasSyntaxNodeChildren(syntaxTree, "Children"));
+        return new SimpleContainerMathMLElement(syntaxTree, rowIndex, "mrow", this);
+      });
+    });
+
     this.nameMap.forEach((name, path) => {
       assert(this.renderers.get(name), `Renderer for ${path} (ID ${name}) is missing`);
     });
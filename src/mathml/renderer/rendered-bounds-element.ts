@@ -0,0 +1,78 @@
+import { type SyntaxNode, type SyntaxNodeWith, hasSyntaxNodeChildren } from "../../core";
+import type { RowIndex } from "../../input-tree/row-indices";
+import type { RenderedElement, Renderer } from "../../rendering/render-result";
+import type { ViewportCoordinate } from "../../rendering/viewport-coordinate";
+import { assert } from "../../utils/assert";
+import { RenderedMathML, createMathElement, wrapInMRow } from "./rendered-element";
+import { RowsContainerMathMLElement } from "./rendered-rows-element";
+
+/**
+ * Renders an operator with a lower and an upper bound, like an integral or a sum.
+ * The children are the sign, the lower bound, the upper bound and then the rest.
+ * A missing bound is an empty node, which doesn't get rendered.
+ */
+export class BoundsMathMLElement implements RenderedElement<MathMLElement> {
+  element: RenderedMathML;
+
+  constructor(
+    public syntaxTree: SyntaxNodeWith<"Children">,
+    public rowIndex: RowIndex | null,
+    renderer: Renderer<MathMLElement>
+  ) {
+    const [sign, lower, upper, ...rest] = syntaxTree.children.Children;
+    assert(sign !== undefined && lower !== undefined && upper !== undefined, "Needs a sign and two bounds");
+    this.element = new RenderedMathML(createMathElement("mrow", []));
+
+    const renderedSign = renderer.render(sign, null);
+    const renderedBounds = [lower, upper].filter(isBound).map((bound) =>
+      renderer.render(bound, null, {
+        newRowsOperatorOverride: (node) => {
+          assert(hasSyntaxNodeChildren(node, "NewRows"));
+          return new RowsContainerMathMLElement(node, rowIndex, "mrow", renderer);
+        },
+      })
+    );
+    const renderedRest = rest.map((c) => renderer.render(c, null));
+
+    const scriptsElementName = isBound(lower) ? (isBound(upper) ? "msubsup" : "msub") : isBound(upper) ? "msup" : null;
+    const signElements = [renderedSign, ...renderedBounds].map((v) => wrapInMRow(v.getElements()));
+    const signElement = scriptsElementName === null ? signElements[0] : createMathElement(scriptsElementName, signElements);
+
+    this.element.setChildrenCustom(
+      [renderedSign, ...renderedBounds, ...renderedRest],
+      [signElement, ...renderedRest.flatMap((v) => v.getElements())]
+    );
+  }
+
+  getCaretSize(): number {
+    return this.element.getCaretSize();
+  }
+
+  getBounds() {
+    return this.element.getBounds();
+  }
+
+  getCaretPosition(offset: number): ViewportCoordinate {
+    assert(this.syntaxTree.range.start <= offset && offset <= this.syntaxTree.range.end, "Invalid offset");
+    const child = this.element
+      .getChildren()
+      .find((c) => c.syntaxTree.range.start <= offset && offset <= c.syntaxTree.range.end);
+    if (child) {
+      return child.getCaretPosition(offset);
+    } else {
+      throw new Error("Should not happen");
+    }
+  }
+
+  getElements() {
+    return this.element.getElements();
+  }
+
+  getChildren() {
+    return this.element.getChildren();
+  }
+}
+
+function isBound(node: SyntaxNode): boolean {
+  return node.range.start !== node.range.end;
+}
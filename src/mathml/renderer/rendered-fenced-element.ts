@@ -0,0 +1,82 @@
+import type { SyntaxNodeWith } from "../../core";
+import type { RowIndex } from "../../input-tree/row-indices";
+import type { RenderedElement, Renderer } from "../../rendering/render-result";
+import type { ViewportCoordinate } from "../../rendering/viewport-coordinate";
+import { assert } from "../../utils/assert";
+import { RenderedMathML, createMathElement, wrapInMRow } from "./rendered-element";
+
+/**
+ * Renders a fenced group, where the brackets are stretched to fit the contents.
+ * The brackets aren't in the input tree, the parser stores them in the value of the syntax node.
+ */
+export class FencedMathMLElement implements RenderedElement<MathMLElement> {
+  element: RenderedMathML;
+  startBaselineReader: MathMLElement;
+  endBaselineReader: MathMLElement;
+
+  constructor(
+    public syntaxTree: SyntaxNodeWith<"NewRows">,
+    public rowIndex: RowIndex | null,
+    renderer: Renderer<MathMLElement>
+  ) {
+    assert(syntaxTree.children.NewRows.values.length === 1, "A fenced group has exactly one row");
+    this.element = new RenderedMathML(createMathElement("mrow", []));
+    this.startBaselineReader = createMathElement("mphantom", []);
+    this.endBaselineReader = createMathElement("mphantom", []);
+    const indexOfContainer = this.syntaxTree.range.start;
+    assert(this.syntaxTree.range.start + 1 === this.syntaxTree.range.end, "Invalid range for a row container");
+
+    const [open, close] = [...new TextDecoder().decode(new Uint8Array(syntaxTree.value))];
+    assert(open !== undefined && close !== undefined, "Expected an opening and a closing bracket");
+    const children = syntaxTree.children.NewRows.values.map((c, rowIndex) => renderer.render(c, [indexOfContainer, rowIndex]));
+    this.element.setChildrenCustom(children, [
+      createFence(open),
+      ...children.map((v) => wrapInMRow(v.getElements())),
+      createFence(close),
+    ]);
+    assert(this.element.getChildren().length === 1, "Invalid number of children");
+  }
+
+  getCaretSize() {
+    return this.element.getCaretSize();
+  }
+  getBounds() {
+    return this.element.getBounds();
+  }
+
+  getCaretPosition(offset: number): ViewportCoordinate {
+    assert(this.syntaxTree.range.start <= offset && offset <= this.syntaxTree.range.end, "Invalid offset");
+
+    // The baseline isn't exposed as a property, so we have this questionable workaround
+    // https://github.com/w3c/mathml-core/issues/38
+    // https://jsfiddle.net/se6n81rg/1/
+
+    let positionReader: MathMLElement;
+
+    if (offset == this.syntaxTree.range.start) {
+      positionReader = this.startBaselineReader;
+    } else if (offset == this.syntaxTree.range.end) {
+      positionReader = this.endBaselineReader;
+    } else {
+      throw new Error("Don't know how to deal with this offset");
+    }
+
+    let { x, y } = positionReader.getBoundingClientRect();
+    return { x: x, y: y };
+  }
+
+  getElements(): MathMLElement[] {
+    // Or wrap the element in an extra mrow?
+    return [this.startBaselineReader, this.element.element, this.endBaselineReader];
+  }
+
+  getChildren() {
+    return this.element.getChildren();
+  }
+}
+
+function createFence(symbol: string): MathMLElement {
+  const element = createMathElement("mo", [document.createTextNode(symbol)]);
+  element.setAttribute("stretchy", "true");
+  return element;
+}